@@ -19,12 +19,15 @@
 
 use std::fmt::{self, Display, Formatter};
 
-use crate::hdu_err::InvalidRecordValueError;
+use crate::hdu_err::InvalidBitpixErr;
 
-const VALID_BITPIX_VALUES: [&'static str; 6] = ["8", "16", "32", "64", "-32", "-64"];
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bitpix {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Data type of the pixels of an image (or the elements of random groups),
+      as given by the BITPIX keyword.
+  */
   Byte,
   Short,
   Int,
@@ -34,22 +37,31 @@ pub enum Bitpix {
 }
 
 impl Bitpix {
-  pub(crate) fn from_code(code: &isize) -> Result<Bitpix, InvalidRecordValueError> {
+  pub fn byte_width(&self) -> usize {
+    //Number of bytes per pixel
+    self.to_code().unsigned_abs() / 8
+  }
+
+  pub fn dtype_name(&self) -> &'static str {
+    //Name of the rust type that stores pixels of this bitpix
     use Bitpix::*;
-    match code {
-      8 => Ok(Byte),
-      16 => Ok(Short),
-      32 => Ok(Int),
-      64 => Ok(Long),
-      -32 => Ok(Spf),
-      -64 => Ok(Dpf),
-      other => {
-        Err(InvalidRecordValueError::new("BITPIX", &other.to_string(), &VALID_BITPIX_VALUES))
-      }
+    match self {
+      Byte => "u8",
+      Short => "i16",
+      Int => "i32",
+      Long => "i64",
+      Spf => "f32",
+      Dpf => "f64",
     }
   }
 
-  pub(crate) fn to_code(&self) -> isize {
+  pub fn from_rust_type_name(name: &str) -> Option<Bitpix> {
+    //Inverse of dtype_name()
+    use Bitpix::*;
+    [Byte, Short, Int, Long, Spf, Dpf].into_iter().find(|bpx| bpx.dtype_name() == name)
+  }
+
+  pub(crate) fn to_code(self) -> isize {
     use Bitpix::*;
     match self {
       Byte => 8,
@@ -81,16 +93,33 @@ impl Bitpix {
   }
 }
 
-impl Display for Bitpix {
-  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl TryFrom<i64> for Bitpix {
+  type Error = InvalidBitpixErr;
+
+  fn try_from(code: i64) -> Result<Self, Self::Error> {
     use Bitpix::*;
-    match *self {
-      Byte => write!(f, "u8"),
-      Short => write!(f, "i16"),
-      Int => write!(f, "i32"),
-      Long => write!(f, "i62"),
-      Spf => write!(f, "f32"),
-      Dpf => write!(f, "f64"),
+    match code {
+      8 => Ok(Byte),
+      16 => Ok(Short),
+      32 => Ok(Int),
+      64 => Ok(Long),
+      -32 => Ok(Spf),
+      -64 => Ok(Dpf),
+      other => Err(InvalidBitpixErr::new(other)),
     }
   }
 }
+
+impl TryFrom<i8> for Bitpix {
+  type Error = InvalidBitpixErr;
+
+  fn try_from(code: i8) -> Result<Self, Self::Error> {
+    Self::try_from(i64::from(code))
+  }
+}
+
+impl Display for Bitpix {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.dtype_name())
+  }
+}
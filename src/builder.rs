@@ -0,0 +1,257 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Fluent API for creating FITS files from scratch. The builders take care
+    of the mandatory keywords (SIMPLE/XTENSION, BITPIX, NAXIS etc.), which
+    are derived from the data. Users only supply their own metadata:
+
+        let fits = FitsBuilder::new()
+          .primary(HduBuilder::image(array))
+          .extension(HduBuilder::table(tbl).extname("EVENTS"))
+          .build()?;
+*/
+
+use std::error::Error;
+
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  extensions::{
+    image::{Pixel, TypedImage},
+    table::{AsciiTable, AsciiTblParser},
+    Extension,
+  },
+  fits::Fits,
+  hdu_err::WrongExtensionErr,
+  header::Header,
+  header_data_unit::HeaderDataUnit,
+  raw::{header_write_utils::invalid_keyword_char, keyword_utils::quote_fits_string},
+};
+
+#[derive(Debug)]
+pub struct HeaderBuilder {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Collects user-supplied keyword records. Keywords that describe the data
+      are set by HduBuilder and cannot be added here. Values are stored as
+      they appear in the header, like in Header::insert_with_comment. The
+      first invalid keyword is reported once the header is built.
+  */
+  header: Header,
+  err: Option<Box<dyn Error>>,
+}
+
+impl Default for HeaderBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl From<Header> for HeaderBuilder {
+  fn from(header: Header) -> Self {
+    //Starts from the records of an existing header. Records that describe
+    //the data are replaced by the HduBuilder
+    HeaderBuilder { header, err: None }
+  }
+}
+
+impl HeaderBuilder {
+  pub fn new() -> Self {
+    HeaderBuilder { header: Header::new(), err: None }
+  }
+
+  pub fn keyword(self, keyword: &str, value: &str) -> Self {
+    self.keyword_with_comment(keyword, value, "")
+  }
+
+  pub fn string(self, keyword: &str, value: &str) -> Self {
+    //Same as keyword(), but quotes the value
+    self.keyword(keyword, &quote_fits_string(value))
+  }
+
+  pub fn keyword_with_comment(mut self, keyword: &str, value: &str, comment: &str) -> Self {
+    if self.err.is_none() {
+      if let Err(err) = self.insert(keyword, value, comment) {
+        self.err = Some(err);
+      }
+    }
+    self
+  }
+
+  pub fn build(self) -> Result<Header, Box<dyn Error>> {
+    match self.err {
+      Some(err) => Err(err),
+      None => Ok(self.header),
+    }
+  }
+
+  pub const fn is_valid_keyword(keyword: &str) -> bool {
+    /*
+        Keywords consist of up to 8 uppercase letters, digits, hyphens and
+        underscores. Longer keywords (or keywords with dots) are written with
+        the HIERARCH convention, in which case they consist of dot-separated
+        words of printable characters other than '='. This func is const: it
+        may be used to check keywords at compile time.
+    */
+    !keyword.is_empty() && invalid_keyword_char(keyword.as_bytes()).is_none()
+  }
+
+  fn insert(&mut self, keyword: &str, value: &str, comment: &str) -> Result<(), Box<dyn Error>> {
+    //Header checks the keyword, see validate_keyword_name()
+    Ok(self.header.insert_with_comment(keyword, value, comment)?)
+  }
+}
+
+#[derive(Debug)]
+pub struct HduBuilder {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Combines data with user-supplied metadata. The mandatory keywords are
+      derived from the data when the HDU is built by FitsBuilder, since they
+      depend on whether the HDU is the primary HDU or an extension.
+  */
+  data: Option<Extension>,
+  extname: Option<String>,
+  meta: HeaderBuilder,
+}
+
+impl HduBuilder {
+  pub fn image<T: Pixel>(array: Array<T, IxDyn>) -> Self {
+    Self::with_data(Some(Extension::Image(TypedImage::from_array(array))))
+  }
+
+  pub fn table(tbl: AsciiTable) -> Self {
+    //ASCII tables can only be stored in extensions
+    Self::with_data(Some(Extension::AsciiTable(tbl)))
+  }
+
+  pub fn empty() -> Self {
+    //HDU without data (NAXIS = 0), used for metadata only
+    Self::with_data(None)
+  }
+
+  pub fn extname(mut self, extname: &str) -> Self {
+    self.extname = Some(extname.to_string());
+    self
+  }
+
+  pub fn header(mut self, meta: HeaderBuilder) -> Self {
+    self.meta = meta;
+    self
+  }
+
+  fn with_data(data: Option<Extension>) -> Self {
+    HduBuilder { data, extname: None, meta: HeaderBuilder::new() }
+  }
+
+  fn build(self, is_primary: bool, extend: bool) -> Result<HeaderDataUnit, Box<dyn Error>> {
+    let mut header = Header::new();
+
+    //(1) Everything starts with SIMPLE or XTENSION
+    let xtension = match self.data {
+      Some(Extension::AsciiTable(_)) => "'TABLE   '",
+      _ => "'IMAGE   '",
+    };
+    match is_primary {
+      true => header.set_record("SIMPLE", String::from("T"), None),
+      false => header.set_record("XTENSION", String::from(xtension), None),
+    }
+
+    //(2) The keywords describing the data. Tables also have their layout
+    //    keywords set when they are encoded, so those stay in sync
+    match &self.data {
+      None => {
+        header.set_record("BITPIX", String::from("8"), None);
+        header.set_record("NAXIS", String::from("0"), None);
+      }
+      Some(Extension::Image(img)) => {
+        header.set_record("BITPIX", img.bpx().to_code().to_string(), None);
+        header.set_record("NAXIS", img.get_shape().len().to_string(), None);
+        for (i, axis) in img.get_shape().iter().enumerate() {
+          header.set_record(&format!("NAXIS{}", i + 1), axis.to_string(), None);
+        }
+      }
+      Some(Extension::AsciiTable(tbl)) if !is_primary => {
+        header.set_record("BITPIX", String::from("8"), None);
+        header.set_record("NAXIS", String::from("2"), None);
+        for (keyword, value) in AsciiTblParser::get_tbl_keywords(tbl)? {
+          header.set_record(&keyword, value, None);
+        }
+        for col in 0..tbl.get_shape().0 {
+          if let Some(label) = tbl.column_label(col) {
+            header.set_record(&format!("TTYPE{}", col + 1), quote_fits_string(label), None);
+          }
+        }
+      }
+      other => return Err(Box::new(WrongExtensionErr::new("an image", other.as_ref()))),
+    }
+
+    //(3) Extensions have a fixed PCOUNT and GCOUNT, primary HDU's announce
+    //    any extensions that follow
+    if !is_primary {
+      header.set_record("PCOUNT", String::from("0"), None);
+      header.set_record("GCOUNT", String::from("1"), None);
+    } else if extend {
+      header.set_record("EXTEND", String::from("T"), None);
+    }
+    if let Some(extname) = &self.extname {
+      header.set_record("EXTNAME", quote_fits_string(extname), None);
+    }
+
+    //(4) Add the user-supplied metadata, the mandatory keywords go first
+    header.copy_meta(self.meta.build()?);
+    header.sort_mandatory_keywords();
+    Ok(HeaderDataUnit::new(header, self.data))
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct FitsBuilder {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Builds a Fits struct from a primary HDU and any number of extensions.
+      Files without primary HDU get an empty one. The result can be written
+      with Fits::write().
+  */
+  primary: Option<HduBuilder>,
+  extensions: Vec<HduBuilder>,
+}
+
+impl FitsBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn primary(mut self, hdu: HduBuilder) -> Self {
+    self.primary = Some(hdu);
+    self
+  }
+
+  pub fn extension(mut self, hdu: HduBuilder) -> Self {
+    self.extensions.push(hdu);
+    self
+  }
+
+  pub fn build(self) -> Result<Fits, Box<dyn Error>> {
+    let extend = !self.extensions.is_empty();
+    let mut hdus = vec![self.primary.unwrap_or_else(HduBuilder::empty).build(true, extend)?];
+    for hdu in self.extensions {
+      hdus.push(hdu.build(false, false)?);
+    }
+    Ok(Fits::from_hdu_list(hdus))
+  }
+}
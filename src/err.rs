@@ -24,6 +24,7 @@
 */
 
 //Listing of the errors
+pub mod checksum_err;
 pub mod hdu_err;
 pub mod header_err;
 pub mod img_err;
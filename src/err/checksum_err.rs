@@ -0,0 +1,53 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::{
+  error::Error,
+  fmt::{self, Display, Formatter},
+};
+
+#[derive(Debug)]
+pub struct ChecksumMismatchErr {
+  /*
+      This error may be thrown when opening a FITS file with checksum
+      verification enabled. It signifies that the checksum stored in the
+      CHECKSUM or DATASUM keyword does not match the checksum of the bytes
+      that were actually read.
+  */
+  pub(crate) keyword: &'static str,
+  pub(crate) stored: String,
+  pub(crate) computed: u32,
+}
+
+impl Error for ChecksumMismatchErr {}
+impl Display for ChecksumMismatchErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while verifying checksums: {} = {} does not match the computed checksum ({})",
+      self.keyword, self.stored, self.computed
+    )
+  }
+}
+
+impl ChecksumMismatchErr {
+  pub(crate) fn new(keyword: &'static str, stored: &str, computed: u32) -> Self {
+    ChecksumMismatchErr { keyword, stored: stored.to_string(), computed }
+  }
+}
@@ -23,6 +23,8 @@ use std::{
   ops::Not,
 };
 
+use crate::extensions::Extension;
+
 #[derive(Debug)]
 pub struct MissingRecordError {
   /*s
@@ -85,6 +87,35 @@ impl InvalidRecordValueError {
   }
 }
 
+#[derive(Debug)]
+pub struct InvalidBitpixErr {
+  /*
+      This error may be thrown when decoding a header data unit. It signifies
+      that BITPIX does not have one of the values allowed by the standard.
+  */
+  code: i64,
+}
+
+//Values of BITPIX allowed by the standard
+const VALID_BITPIX_VALUES: [i64; 6] = [8, 16, 32, 64, -32, -64];
+
+impl Error for InvalidBitpixErr {}
+impl Display for InvalidBitpixErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while decoding BITPIX: {} is not a valid BITPIX value. Allowed values are: {:?}",
+      self.code, VALID_BITPIX_VALUES
+    )
+  }
+}
+
+impl InvalidBitpixErr {
+  pub(crate) fn new(code: i64) -> Self {
+    InvalidBitpixErr { code }
+  }
+}
+
 #[derive(Debug)]
 pub struct NotImplementedErr {
   //thrown when accessing extension that was not implemented
@@ -103,3 +134,55 @@ impl NotImplementedErr {
     NotImplementedErr { xtnsion: xtnsion }
   }
 }
+
+#[derive(Debug)]
+pub struct WrongExtensionErr {
+  //thrown when accessing the data of an HDU as the wrong kind of extension
+  expected: &'static str,
+  found: &'static str,
+}
+
+//Found "data" of HDU's without data unit
+const NO_DATA: &str = "no data";
+
+impl Error for WrongExtensionErr {}
+impl Display for WrongExtensionErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Error while accessing HDU data: expected {}, found {}", self.expected, self.found)
+  }
+}
+
+impl WrongExtensionErr {
+  pub(crate) fn new(expected: &'static str, found: Option<&Extension>) -> Self {
+    WrongExtensionErr { expected, found: found.map_or(NO_DATA, |data| data.kind_name()) }
+  }
+
+  pub fn is_no_data(&self) -> bool {
+    //True if the HDU does not contain any data
+    self.found == NO_DATA
+  }
+
+  pub fn is_variant_error(&self) -> bool {
+    //True if the HDU contains another kind of data than was expected
+    !self.is_no_data()
+  }
+}
+
+#[derive(Debug)]
+pub struct ReorderErr {
+  //thrown when the HDU's of a file cannot be reordered with a permutation
+  reason: String,
+}
+
+impl Error for ReorderErr {}
+impl Display for ReorderErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Error while reordering HDU's: {}", self.reason)
+  }
+}
+
+impl ReorderErr {
+  pub(crate) fn new(reason: String) -> Self {
+    ReorderErr { reason }
+  }
+}
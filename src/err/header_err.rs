@@ -54,3 +54,52 @@ impl HeaderBlockBufferErr {
     Self { msg: msg.to_string() }
   }
 }
+
+#[derive(Debug)]
+pub struct HeaderGrewErr {
+  /*
+      This error may be thrown when rewriting a header in place. It signifies
+      that the modified header does not fit in the FITS blocks of the original
+      header, so the whole file has to be rewritten instead.
+  */
+  pub(crate) old_blocks: usize,
+  pub(crate) new_blocks: usize,
+}
+
+impl Error for HeaderGrewErr {}
+impl Display for HeaderGrewErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while rewriting header: header would grow from {} to {} FITS blocks",
+      self.old_blocks, self.new_blocks
+    )
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidHeaderErr {
+  /*
+      This error may be thrown when decoding a header. It signifies that the
+      keywords describing the shape of the data unit contradict each other.
+  */
+  //A mandatory structural keyword (SIMPLE, BITPIX or NAXIS) appears twice
+  DuplicateKeyword { key: &'static str },
+  //The number of NAXISn keywords differs from the value of NAXIS
+  NaxisShapeMismatch { declared: u32, found: usize },
+}
+
+impl Error for InvalidHeaderErr {}
+impl Display for InvalidHeaderErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::DuplicateKeyword { key } => {
+        write!(f, "Error while decoding header: keyword {key} appears more than once")
+      }
+      Self::NaxisShapeMismatch { declared, found } => write!(
+        f,
+        "Error while decoding header: NAXIS = {declared}, but the header has {found} NAXISn keyword(s)"
+      ),
+    }
+  }
+}
@@ -20,6 +20,7 @@
 use std::{
   error::Error,
   fmt::{self, Display, Formatter},
+  ops::Range,
 };
 
 use crate::{bitpix::Bitpix, extensions::image::TypedImage};
@@ -49,6 +50,20 @@ impl WrongImgTypeErr {
   pub(crate) fn new(img: &TypedImage, wrong_type: Bitpix) -> Self {
     WrongImgTypeErr { img_type: img.bpx(), wrong_type: wrong_type }
   }
+
+  pub(crate) fn from_bpx(img_type: Bitpix, wrong_type: Bitpix) -> Self {
+    WrongImgTypeErr { img_type, wrong_type }
+  }
+
+  pub fn get_actual_bitpix(&self) -> Bitpix {
+    //Pixel type of the image
+    self.img_type
+  }
+
+  pub fn get_expected_bitpix(&self) -> Bitpix {
+    //Pixel type the image was accessed as
+    self.wrong_type
+  }
 }
 
 #[derive(Debug)]
@@ -66,3 +81,151 @@ impl InvalidMemLayout {
     InvalidMemLayout {}
   }
 }
+
+#[derive(Debug)]
+pub struct ShapeErr {
+  /*
+      This error may be thrown when manipulating images along an axis that
+      the image does not have (or an axis without any entries)
+  */
+  axis: usize,
+  shape: Vec<usize>,
+}
+
+impl Error for ShapeErr {}
+impl Display for ShapeErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while manipulating image: invalid axis {} for image with shape {:?}",
+      self.axis, self.shape
+    )
+  }
+}
+
+impl ShapeErr {
+  pub(crate) fn new(axis: usize, shape: &[usize]) -> Self {
+    ShapeErr { axis, shape: shape.to_vec() }
+  }
+}
+
+#[derive(Debug)]
+pub struct SliceErr {
+  /*
+      This error may be thrown when slicing an image with ranges that do not
+      fit the image (out of bounds, or a different number of axes)
+  */
+  ranges: Vec<Range<usize>>,
+  shape: Vec<usize>,
+}
+
+impl Error for SliceErr {}
+impl Display for SliceErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while slicing image: ranges {:?} do not fit image with shape {:?}",
+      self.ranges, self.shape
+    )
+  }
+}
+
+impl SliceErr {
+  pub(crate) fn new(ranges: &[Range<usize>], shape: &[usize]) -> Self {
+    SliceErr { ranges: ranges.to_vec(), shape: shape.to_vec() }
+  }
+}
+
+#[derive(Debug)]
+pub struct ShapeMismatchErr {
+  /*
+      This error is thrown when combining two images pixel by pixel, if the
+      images do not have the same shape
+  */
+  left: Vec<usize>,
+  right: Vec<usize>,
+}
+
+impl Error for ShapeMismatchErr {}
+impl Display for ShapeMismatchErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while combining images: image with shape {:?} cannot be combined with image with shape {:?}",
+      self.left, self.right
+    )
+  }
+}
+
+impl ShapeMismatchErr {
+  pub(crate) fn new(left: &[usize], right: &[usize]) -> Self {
+    ShapeMismatchErr { left: left.to_vec(), right: right.to_vec() }
+  }
+}
+
+#[derive(Debug)]
+pub struct CastErr {
+  /*
+      This error is thrown when casting an image to a type that cannot
+      represent all of its pixels (negative pixels to unsigned integers, NaN's
+      to integers, etc.)
+  */
+  img_type: Bitpix,
+  target_type: &'static str,
+}
+
+impl Error for CastErr {}
+impl Display for CastErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while casting image: not all pixels of the {} image can be represented as {}",
+      self.img_type, self.target_type
+    )
+  }
+}
+
+impl CastErr {
+  pub(crate) fn new(img: &TypedImage, target_type: &'static str) -> Self {
+    CastErr { img_type: img.bpx(), target_type }
+  }
+}
+
+#[cfg(feature = "rice")]
+#[derive(Debug)]
+pub struct CompressedImgErr {
+  /*
+      This error is thrown when a tile-compressed image cannot be decompressed,
+      either because its tiles are corrupted or because they were compressed
+      in a way we do not support
+  */
+  msg: &'static str,
+}
+
+//Possible messages
+#[cfg(feature = "rice")]
+pub(crate) const TILE_TRUNCATED: &str = "compressed tile contains fewer pixels than expected";
+#[cfg(feature = "rice")]
+pub(crate) const BYTEPIX: &str = "BYTEPIX must be 1, 2 or 4 for Rice compressed images";
+#[cfg(feature = "rice")]
+pub(crate) const TILE_OUT_OF_HEAP: &str = "compressed tile extends beyond the heap";
+#[cfg(feature = "rice")]
+pub(crate) const NO_TILE_DATA: &str = "compressed image has no COMPRESSED_DATA column";
+#[cfg(feature = "rice")]
+pub(crate) const TILE_COUNT: &str = "number of tiles does not match the shape of the image";
+
+#[cfg(feature = "rice")]
+impl Error for CompressedImgErr {}
+#[cfg(feature = "rice")]
+impl Display for CompressedImgErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Error while decompressing image: {}", self.msg)
+  }
+}
+
+#[cfg(feature = "rice")]
+impl CompressedImgErr {
+  pub(crate) fn new(msg: &'static str) -> Self {
+    CompressedImgErr { msg }
+  }
+}
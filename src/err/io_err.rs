@@ -30,6 +30,14 @@ pub struct InvalidFitsFileErr {
       thrown.
   */
   msg: &'static str,
+  //Only set when reading past the end of the file
+  end_of_file: Option<EndOfFile>,
+}
+
+#[derive(Debug)]
+struct EndOfFile {
+  blocks_available: usize, //#blocks left in the file
+  blocks_requested: usize, //#blocks we tried to read
 }
 
 //Possible messages
@@ -39,16 +47,37 @@ pub(crate) const BUF_BLOCK_DIV: &'static str =
   "supplied buffer not an integer multiple of FITS blocks";
 pub(crate) const FILE_END: &'static str = "tried to read more FITS blocks than the file contains";
 pub(crate) const CORRUPTED: &'static str = "tried to access corrupted data";
+pub(crate) const SEEK_END: &str = "tried to seek past the end of the file";
+pub(crate) const NO_SUCH_HDU: &str = "tried to read an HDU that the file does not contain";
+#[cfg(feature = "gz")]
+pub(crate) const COMPRESSED_SEEK: &str = "cannot seek in a compressed file, use open() instead";
 
 impl Error for InvalidFitsFileErr {}
 impl Display for InvalidFitsFileErr {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-    write!(f, "Error while accessing FITS file: {}", self.msg)
+    match &self.end_of_file {
+      None => write!(f, "Error while accessing FITS file: {}", self.msg),
+      Some(end) => write!(
+        f,
+        "Error while accessing FITS file: tried to read {} FITS block(s), but only {} remain",
+        end.blocks_requested, end.blocks_available
+      ),
+    }
   }
 }
 
 impl InvalidFitsFileErr {
   pub(crate) fn new(msg: &'static str) -> Self {
-    InvalidFitsFileErr { msg: msg }
+    InvalidFitsFileErr { msg, end_of_file: None }
+  }
+
+  pub(crate) fn end_of_file(blocks_available: usize, blocks_requested: usize) -> Self {
+    let end_of_file = Some(EndOfFile { blocks_available, blocks_requested });
+    InvalidFitsFileErr { msg: FILE_END, end_of_file }
+  }
+
+  pub fn is_eof(&self) -> bool {
+    //True if the file (or stream) ended before all blocks could be read
+    self.msg == FILE_END
   }
 }
@@ -35,6 +35,8 @@ pub struct KeywordRecordBufferErr {
 //List of possible messages:
 pub const BUFFER_LEN: &'static str = "Keyword record buffer was not exactly 80 bytes long";
 pub const ILLEGAL_CHAR: &'static str = "Keyword record contains illegal characters";
+pub const KEYWORD_LEN: &str = "Keyword is too long to fit in a keyword record";
+pub const VALUE_LEN: &str = "Value is too long to fit in a keyword record";
 
 impl Error for KeywordRecordBufferErr {}
 impl Display for KeywordRecordBufferErr {
@@ -55,26 +57,93 @@ impl KeywordRecordBufferErr {
   }
 }
 
-#[derive(Debug)]
-pub struct ProtectedKeywordErr {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordNameErrorReason {
+  //HIERARCH keywords that leave no room for a value in the record
+  TooLong,
+  //Keywords consist of uppercase letters, digits, hyphens and underscores
+  //(HIERARCH keywords of printable ASCII characters other than '=')
+  IllegalCharacter(char),
+  //Keywords that describe the data unit cannot be set by users
+  Protected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKeywordErr {
   /*
-    This error may be thrown when an instance tries to modify a protected
-    keyword.
+    This error may be thrown when a user tries to add, modify or remove a
+    keyword record with a keyword that is not allowed by the standard, or
+    that is protected (see validate_keyword_name).
   */
-  keyword: &'static str,
+  name: String,
+  reason: KeywordNameErrorReason,
 }
 
-impl Error for ProtectedKeywordErr {}
-impl Display for ProtectedKeywordErr {
+impl Error for InvalidKeywordErr {}
+impl Display for InvalidKeywordErr {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-    write!(f, "Cannot create a keyword record for ({}), since it is a protected keyword. See documentation for more info",
-      self.keyword
-    )
+    match self.reason {
+      KeywordNameErrorReason::TooLong => write!(
+        f,
+        "Error while validating keyword ({}): it is too long to fit in a keyword record",
+        self.name
+      ),
+      KeywordNameErrorReason::IllegalCharacter(found) => write!(
+        f,
+        "Error while validating keyword ({}): illegal character {found:?}",
+        self.name
+      ),
+      KeywordNameErrorReason::Protected => write!(f, "Cannot create a keyword record for ({}), since it is a protected keyword. See documentation for more info",
+        self.name
+      ),
+    }
   }
 }
 
-impl ProtectedKeywordErr {
-  pub fn new(kw: &'static str) -> Self {
-    ProtectedKeywordErr { keyword: kw }
+impl InvalidKeywordErr {
+  pub(crate) fn new(name: &str, reason: KeywordNameErrorReason) -> Self {
+    InvalidKeywordErr { name: name.to_string(), reason }
+  }
+
+  pub fn get_name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn get_reason(&self) -> &KeywordNameErrorReason {
+    &self.reason
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordRecordErr {
+  /*
+    This error may be thrown when encoding a keyword record. It signifies
+    that the keyword, value and comment do not fit in an 80 byte record, or
+    that they contain characters that are not allowed by the standard.
+  */
+  //Keywords are at most 8 characters long
+  KeyTooLong { key: String },
+  //The value indicator, value and comment take up at most 72 characters
+  ValueTooLong { value: String },
+  //Keywords consist of uppercase letters, digits, hyphens and underscores,
+  //values and comments of printable ASCII characters
+  IllegalCharacter { found: char },
+}
+
+impl Error for KeywordRecordErr {}
+impl Display for KeywordRecordErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::KeyTooLong { key } => {
+        write!(f, "Error while encoding keyword record: keyword {key} is longer than 8 characters")
+      }
+      Self::ValueTooLong { value } => write!(
+        f,
+        "Error while encoding keyword record: value {value} (and its comment) does not fit in 80 bytes"
+      ),
+      Self::IllegalCharacter { found } => {
+        write!(f, "Error while encoding keyword record: illegal character {found:?}")
+      }
+    }
   }
 }
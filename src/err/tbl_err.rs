@@ -86,6 +86,29 @@ impl ShapeMisMatchErr {
   }
 }
 
+#[derive(Debug)]
+pub struct RaggedTableErr {
+  shortest: usize,
+  longest: usize,
+}
+
+impl Error for RaggedTableErr {}
+impl Display for RaggedTableErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "cannot reorder the rows of a table with columns of {} to {} entries",
+      self.shortest, self.longest
+    )
+  }
+}
+
+impl RaggedTableErr {
+  pub(crate) fn new(shortest: usize, longest: usize) -> Self {
+    RaggedTableErr { shortest, longest }
+  }
+}
+
 #[derive(Debug)]
 pub struct TypeMisMatchErr {
   wrong_type: TableEntry,
@@ -105,6 +128,43 @@ impl TypeMisMatchErr {
   }
 }
 
+#[derive(Debug)]
+pub struct TableEntryTypeErr {
+  variant: &'static str,
+  target: &'static str,
+}
+
+impl Error for TableEntryTypeErr {}
+impl Display for TableEntryTypeErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "cannot convert TableEntry::{} to {}", self.variant, self.target)
+  }
+}
+
+impl TableEntryTypeErr {
+  pub(crate) fn new(entry: &TableEntry, target: &'static str) -> Self {
+    TableEntryTypeErr { variant: entry.variant_name(), target }
+  }
+}
+
+#[derive(Debug)]
+pub struct MissingColumnErr {
+  label: String,
+}
+
+impl Error for MissingColumnErr {}
+impl Display for MissingColumnErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "table does not contain a column labeled {}", self.label)
+  }
+}
+
+impl MissingColumnErr {
+  pub(crate) fn new(label: &str) -> Self {
+    MissingColumnErr { label: label.to_string() }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct TblDecodeErr {
   msg: String,
@@ -134,3 +194,59 @@ impl From<IndexOutOfRangeErr> for TblDecodeErr {
     TblDecodeErr { msg: format!("{err}") }
   }
 }
+
+impl From<MissingColumnErr> for TblDecodeErr {
+  fn from(err: MissingColumnErr) -> Self {
+    TblDecodeErr { msg: format!("{err}") }
+  }
+}
+
+#[derive(Debug)]
+pub struct TableConversionErr {
+  //thrown when a data frame column cannot be stored in an ASCII table
+  column: String,
+  dtype: String,
+}
+
+impl Error for TableConversionErr {}
+impl Display for TableConversionErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "cannot store column {} of type {} in an ASCII table", self.column, self.dtype)
+  }
+}
+
+impl TableConversionErr {
+  #[cfg(feature = "polars")]
+  pub(crate) fn new(column: &str, dtype: impl Display) -> Self {
+    TableConversionErr { column: column.to_string(), dtype: dtype.to_string() }
+  }
+}
+
+#[derive(Debug)]
+pub struct CsvParseErr {
+  //thrown when a CSV file cannot be read into an ASCII table
+  line: Option<usize>,
+  msg: String,
+}
+
+impl Error for CsvParseErr {}
+impl Display for CsvParseErr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "could not parse line {line} of CSV file: {}", self.msg),
+      None => write!(f, "could not read CSV file: {}", self.msg),
+    }
+  }
+}
+
+impl From<std::io::Error> for CsvParseErr {
+  fn from(err: std::io::Error) -> Self {
+    CsvParseErr { line: None, msg: format!("{err}") }
+  }
+}
+
+impl CsvParseErr {
+  pub(crate) fn new(line: usize, msg: impl Display) -> Self {
+    CsvParseErr { line: Some(line), msg: msg.to_string() }
+  }
+}
@@ -71,6 +71,58 @@ impl FieldSizeMisMatch {
   pub(crate) fn new(fmt: &TableEntryFormat, field: &str) -> Self {
     FieldSizeMisMatch { buf_size: field.len(), fmt_field_size: fmt.get_field_width() }
   }
+
+  pub(crate) fn from_sizes(fmt_field_size: usize, buf_size: usize) -> Self {
+    FieldSizeMisMatch { buf_size, fmt_field_size }
+  }
+}
+
+#[derive(Debug)]
+pub struct HeapOutOfRange {
+  /*
+      This error is thrown when the descriptor of a variable-length array
+      points to bytes outside of the heap of a binary table.
+  */
+  row: usize,
+}
+
+impl Error for HeapOutOfRange {}
+impl Display for HeapOutOfRange {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while decoding table entry: variable-length array in row {} extends beyond the heap",
+      self.row
+    )
+  }
+}
+
+impl HeapOutOfRange {
+  pub(crate) fn new(row: usize) -> Self {
+    HeapOutOfRange { row }
+  }
+}
+
+#[derive(Debug)]
+pub struct InvalidLogical {
+  field: String,
+}
+
+impl Error for InvalidLogical {}
+impl Display for InvalidLogical {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Error while decoding table entry: logical fields must be T or F, not '{}'",
+      self.field
+    )
+  }
+}
+
+impl InvalidLogical {
+  pub(crate) fn new(field: &str) -> Self {
+    InvalidLogical { field: field.to_string() }
+  }
 }
 
 #[derive(Debug)]
@@ -79,6 +131,7 @@ pub enum ParseError {
   ParseIntError(ParseIntError),
   ParseFloatError(ParseFloatError),
   InvalidFFCode(InvalidFFCode),
+  InvalidLogical(InvalidLogical),
 }
 
 impl Error for ParseError {}
@@ -111,3 +164,9 @@ impl From<InvalidFFCode> for ParseError {
     ParseError::InvalidFFCode(err)
   }
 }
+
+impl From<InvalidLogical> for ParseError {
+  fn from(err: InvalidLogical) -> Self {
+    ParseError::InvalidLogical(err)
+  }
+}
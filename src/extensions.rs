@@ -23,16 +23,19 @@ use std::{
 };
 
 use crate::{
+  hdu_err::NotImplementedErr,
   io_err::{self, InvalidFitsFileErr as IFFErr},
   raw::{raw_io::RawFitsWriter, BlockSized},
 };
 
 use self::{
+  groups::RandomGroups,
   image::{ImgParser, TypedImage},
-  table::{AsciiTable, AsciiTblParser},
+  table::{AsciiTable, AsciiTblParser, BinTable},
 };
 
 //FITS standard-conforming extensions
+pub mod groups;
 pub mod image;
 pub mod table;
 
@@ -47,6 +50,8 @@ pub enum Extension {
   Corrupted,
   Image(TypedImage),
   AsciiTable(AsciiTable),
+  BinTable(BinTable),
+  RandomGroups(RandomGroups),
 }
 
 impl BlockSized for Extension {
@@ -56,6 +61,8 @@ impl BlockSized for Extension {
       Corrupted => 0, //corrupted data is disregarded
       Image(img) => img.get_block_len(),
       AsciiTable(tbl) => tbl.get_block_len(),
+      BinTable(tbl) => tbl.get_block_len(),
+      RandomGroups(groups) => groups.get_block_len(),
     }
   }
 }
@@ -67,17 +74,92 @@ impl Display for Extension {
       Corrupted => write!(f, "(CORRUPTED_DATA)"),
       Image(img) => write!(f, "{}", img.xprint()),
       AsciiTable(tbl) => write!(f, "{}", tbl.xprint()),
+      BinTable(tbl) => write!(f, "{}", tbl.xprint()),
+      RandomGroups(groups) => write!(f, "{}", groups.xprint()),
     }
   }
 }
 
 impl Extension {
+  pub fn shape(&self) -> Option<&[usize]> {
+    //Shape of the data array. Only images consist of a single array
+    match self {
+      Extension::Image(img) => Some(img.get_shape()),
+      _ => None,
+    }
+  }
+
+  pub fn ndim(&self) -> Option<usize> {
+    self.shape().map(|shape| shape.len())
+  }
+
+  pub fn element_count(&self) -> usize {
+    /*
+        Number of values stored in the data unit: pixels for images, cells
+        for tables and the pixels of all groups for random groups (the group
+        parameters are not counted).
+    */
+    use Extension::*;
+    match self {
+      Corrupted => 0,
+      Image(img) => img.get_shape().iter().product(),
+      AsciiTable(tbl) => tbl.n_rows() * tbl.n_cols(),
+      BinTable(tbl) => {
+        let (cols, rows) = tbl.get_shape();
+        cols * rows
+      }
+      RandomGroups(groups) => groups.get_n_groups() * groups.get_shape().iter().product::<usize>(),
+    }
+  }
+
+  pub fn byte_size(&self) -> usize {
+    /*
+        Number of bytes taken up by the values in the data unit. Tables do not
+        have a single element size, so for tables the (padded) size of the
+        data unit is returned instead.
+    */
+    use Extension::*;
+    match self {
+      Corrupted => 0,
+      Image(img) => self.element_count() * img.bpx().byte_width(),
+      RandomGroups(_) => self.element_count() * std::mem::size_of::<f64>(),
+      AsciiTable(_) | BinTable(_) => self.get_block_len() * crate::BLOCK_SIZE,
+    }
+  }
+
+  pub fn dtype_name(&self) -> &'static str {
+    //Name of the type of the stored values ("u8", "i16", ..., "table")
+    use Extension::*;
+    match self {
+      Corrupted => "corrupted",
+      Image(img) => img.bpx().dtype_name(),
+      RandomGroups(_) => "f64",
+      AsciiTable(_) => "table",
+      BinTable(_) => "bintable",
+    }
+  }
+
+  pub(crate) fn kind_name(&self) -> &'static str {
+    use Extension::*;
+    match self {
+      Corrupted => "corrupted data",
+      Image(_) => "an image",
+      AsciiTable(_) => "an ASCII table",
+      BinTable(_) => "a binary table",
+      RandomGroups(_) => "random groups",
+    }
+  }
+
   pub(crate) fn write_to_buffer(self, writer: &mut RawFitsWriter) -> Result<(), Box<dyn Error>> {
     use Extension::*;
     match self {
       Corrupted => return Err(Box::new(IFFErr::new(io_err::CORRUPTED))),
       Image(img) => ImgParser::encode_img(img, writer),
       AsciiTable(tbl) => AsciiTblParser::encode_tbl(tbl, writer),
+      BinTable(_) => Err(Box::new(NotImplementedErr::new(String::from("BINTABLE (writing)")))),
+      RandomGroups(_) => {
+        Err(Box::new(NotImplementedErr::new(String::from("random groups (writing)"))))
+      }
     }
   }
 }
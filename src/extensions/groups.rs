@@ -0,0 +1,26 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//Module structure
+pub(crate) mod groups_parser;
+pub mod random_groups;
+
+//Re-exports for readability
+pub(crate) use groups_parser::GroupsParser;
+pub use random_groups::RandomGroups;
@@ -0,0 +1,101 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//Get block size from root
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE; // = 2880B
+
+use std::error::Error;
+
+use ndarray::{Array, ShapeBuilder};
+use rayon::prelude::*;
+
+use crate::{bitpix::Bitpix, extensions::Extension, raw::raw_io::RawFitsReader};
+
+use super::RandomGroups;
+
+pub(crate) struct GroupsParser {}
+impl GroupsParser {
+  pub(crate) fn decode_groups(
+    reader: &mut RawFitsReader,
+    bitpix: Bitpix,
+    shape: Vec<usize>,                         //shape of the array in each group
+    gcount: usize,                             //#groups
+    params: Vec<(Option<String>, (f64, f64))>, //(PTYPE, (PSCAL, PZERO)) of each parameter
+    scale: (f64, f64),                         //(BSCALE, BZERO) of the arrays
+  ) -> Result<Extension, Box<dyn Error>> {
+    /*  (1)
+        Each group consists of PCOUNT parameters followed by the array, all
+        stored as values of the type given by BITPIX. We read the whole data
+        unit in one go.
+    */
+    let value_size = bitpix.to_code().unsigned_abs() / 8;
+    let array_len: usize = shape.iter().product();
+    let group_len = params.len() + array_len;
+    let num_blocks = (value_size * group_len * gcount).div_ceil(BLOCK_SIZE);
+    let mut data_unit = vec![0u8; num_blocks * BLOCK_SIZE];
+    reader.read_blocks(&mut data_unit)?;
+
+    //(2) Decode all values, group by group
+    let (param_names, param_scales): (Vec<_>, Vec<_>) = params.into_iter().unzip();
+    let groups: Vec<(Vec<f64>, Vec<f64>)> = data_unit[..value_size * group_len * gcount]
+      .par_chunks(value_size * group_len)
+      .map(|group| {
+        let values: Vec<f64> =
+          group.chunks(value_size).map(|val| Self::decode_value(val, bitpix)).collect();
+        let (pars, arr) = values.split_at(param_scales.len());
+        let pars = pars.iter().zip(&param_scales).map(|(v, (scal, zero))| scal * v + zero);
+        let arr = arr.iter().map(|v| scale.0 * v + scale.1);
+        (pars.collect(), arr.collect())
+      })
+      .collect();
+
+    /*  (3)
+        The arrays are stored in the Fortran (column-major) memory layout,
+        just like regular images.
+    */
+    let mut all_params = Vec::with_capacity(gcount);
+    let mut arrays = Vec::with_capacity(gcount);
+    for (pars, arr) in groups {
+      all_params.push(pars);
+      arrays.push(Array::from_shape_vec(shape.clone().f(), arr)?);
+    }
+
+    //(R) the random groups
+    Ok(Extension::RandomGroups(RandomGroups::new_sized(
+      param_names,
+      all_params,
+      arrays,
+      shape,
+      num_blocks,
+    )))
+  }
+
+  fn decode_value(bytes: &[u8], bitpix: Bitpix) -> f64 {
+    //All values are big-endian
+    use Bitpix::*;
+    match bitpix {
+      Byte => bytes[0] as f64,
+      Short => i16::from_be_bytes([bytes[0], bytes[1]]) as f64,
+      Int => i32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+      Long => i64::from_be_bytes(bytes.try_into().unwrap()) as f64,
+      Spf => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+      Dpf => f64::from_be_bytes(bytes.try_into().unwrap()),
+    }
+  }
+}
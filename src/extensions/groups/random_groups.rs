@@ -0,0 +1,129 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt::{self, Display, Formatter};
+
+use ndarray::{Array, IxDyn};
+
+use crate::{extensions::ExtensionPrint, raw::BlockSized};
+
+/*  Description:
+    This is the user-facing api for random groups. A random groups HDU
+    consists of GCOUNT groups, each with PCOUNT parameters and an array of the
+    same shape (NAXIS2...NAXISn). All parameters and arrays are stored as
+    physical values (scaled using PSCALn/PZEROn and BSCALE/BZERO).
+*/
+#[derive(Debug, Clone)]
+pub struct RandomGroups {
+  param_names: Vec<Option<String>>,
+  params: Vec<Vec<f64>>,
+  data: Vec<Array<f64, IxDyn>>,
+  shape: Vec<usize>,
+  block_size: usize,
+}
+
+impl BlockSized for RandomGroups {
+  fn get_block_len(&self) -> usize {
+    self.block_size
+  }
+}
+
+impl Display for RandomGroups {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      ">=============================<|FITS Random Groups|>============================"
+    )?;
+    writeln!(f, ">#groups: {}, array shape: {:?}", self.data.len(), self.shape)?;
+    writeln!(f, ">Parameters:")?;
+    for (index, name) in self.param_names.iter().enumerate() {
+      writeln!(f, ">  par#{index:03} - {}", name.as_deref().unwrap_or("(no name)"))?
+    }
+    writeln!(
+      f,
+      ">==============================================================================="
+    )?;
+    Ok(())
+  }
+}
+
+impl ExtensionPrint for RandomGroups {
+  fn xprint(&self) -> String {
+    format!(
+      "(GROUPS) - #groups: {}, #parameters: {}, shape: {:?}, size: {}",
+      self.data.len(),
+      self.param_names.len(),
+      self.shape,
+      self.get_block_len()
+    )
+  }
+}
+
+impl RandomGroups {
+  /*
+      PUBLIC API
+  */
+  pub fn get_n_groups(&self) -> usize {
+    self.data.len()
+  }
+  pub fn get_shape(&self) -> &Vec<usize> {
+    //Shape of the array in each group
+    &self.shape
+  }
+  pub fn get_param_names(&self) -> &Vec<Option<String>> {
+    &self.param_names
+  }
+  pub fn get_params(&self, group: usize) -> Option<&Vec<f64>> {
+    self.params.get(group)
+  }
+  pub fn get_group(&self, group: usize) -> Option<&Array<f64, IxDyn>> {
+    self.data.get(group)
+  }
+
+  pub fn get_param(&self, group: usize, name: &str) -> Option<f64> {
+    /*
+        The value of a named parameter. The standard allows multiple
+        parameters with the same name (to increase precision), in which case
+        the value of the parameter is the sum of all of them.
+    */
+    let params = self.params.get(group)?;
+    let mut values = self
+      .param_names
+      .iter()
+      .zip(params)
+      .filter(|(param_name, _)| param_name.as_deref() == Some(name))
+      .map(|(_, value)| *value)
+      .peekable();
+    values.peek()?;
+    Some(values.sum())
+  }
+
+  /*
+      INTERNAL FUNCS
+  */
+  pub(crate) fn new_sized(
+    param_names: Vec<Option<String>>,
+    params: Vec<Vec<f64>>,
+    data: Vec<Array<f64, IxDyn>>,
+    shape: Vec<usize>,
+    size: usize,
+  ) -> Self {
+    RandomGroups { param_names, params, data, shape, block_size: size }
+  }
+}
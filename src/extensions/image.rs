@@ -18,11 +18,21 @@
 */
 
 //Module structure
+#[cfg(feature = "png")]
+mod colormap;
+#[cfg(feature = "rice")]
+mod compressed_image;
 mod generic_image;
+mod image_ops;
 mod image_parser;
 mod typed_image;
 
 //re-exports for readability
-pub use generic_image::Image;
+#[cfg(feature = "png")]
+pub use colormap::{Colormap, Stretch};
+#[cfg(feature = "rice")]
+pub(crate) use compressed_image::CompressedImage;
+pub use generic_image::{Image, ReduceOp};
 pub(crate) use image_parser::ImgParser;
-pub use typed_image::TypedImage;
+pub use image_parser::ScalingMode;
+pub use typed_image::{Pixel, TypedImage};
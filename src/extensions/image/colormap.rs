@@ -0,0 +1,165 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    This file contains the machinery used to turn a FITS image into an RGB
+    raster image (using the image crate). Rendering happens in two steps:
+    first the pixel values are mapped onto [0, 1] using a Stretch, and then
+    the normalized value is colorized using a Colormap.
+
+    This module is only compiled when the "png" feature is enabled.
+*/
+
+use image::{Rgb, RgbImage};
+use ndarray::{Array, IxDyn};
+use num_traits::ToPrimitive;
+
+/*  Polynomial fits of the matplotlib colormaps
+    Both the viridis and inferno colormaps are approximated by a sixth order
+    polynomial in each colour channel (coefficients c0 through c6). The fits
+    are accurate to well within one 8-bit colour level.
+*/
+const VIRIDIS: [[f64; 3]; 7] = [
+  [0.2777273272234177, 0.005407344544966578, 0.3340998053353061],
+  [0.1050930431085774, 1.404613529898575, 1.384590162594685],
+  [-0.3308618287255563, 0.214847559468213, 0.09509516302823659],
+  [-4.634230498983486, -5.799100973351585, -19.33244095627987],
+  [6.228269936347081, 14.17993336680509, 56.69055260068105],
+  [4.776384997670288, -13.74514537774601, -65.35303263337234],
+  [-5.435455855934631, 4.645852612178535, 26.3124352495832],
+];
+
+const INFERNO: [[f64; 3]; 7] = [
+  [0.0002189403691192265, 0.001651004631001012, -0.01948089843709184],
+  [0.1065134194856116, 0.5639564367884091, 3.932712388889277],
+  [11.60249308247187, -3.972853965665698, -15.9423941062914],
+  [-41.70399613139459, 17.43639888205313, 44.35414519872813],
+  [77.162935699427, -33.40235894210092, -81.80730925738993],
+  [-71.31942824499214, 32.62606426397723, 73.20951985803202],
+  [25.13112622477341, -12.24266895238567, -23.07032500287172],
+];
+
+//Same exponents ds9 uses for its log and asinh scales
+const LOG_EXPONENT: f64 = 1000.0;
+const ASINH_EXPONENT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Colormaps used to colorize a normalized image
+  */
+  Grayscale,
+  Viridis,
+  Inferno,
+}
+
+impl Colormap {
+  pub fn colorize(&self, t: f64) -> [u8; 3] {
+    //Normalized values outside of [0,1] (or NaN's) are painted black
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+
+    let rgb = match self {
+      Self::Grayscale => [t, t, t],
+      Self::Viridis => Self::eval_poly(&VIRIDIS, t),
+      Self::Inferno => Self::eval_poly(&INFERNO, t),
+    };
+
+    rgb.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+  }
+
+  fn eval_poly(coeffs: &[[f64; 3]; 7], t: f64) -> [f64; 3] {
+    //Horner's scheme, starting from the highest order coefficient
+    let mut rgb = [0.0f64; 3];
+    for c in coeffs.iter().rev() {
+      for (channel, coeff) in rgb.iter_mut().zip(c) {
+        *channel = *channel * t + coeff;
+      }
+    }
+    rgb
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stretch {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Stretches map pixel values onto [0,1]. Each variant holds the two pixel
+      values (low, high) that are mapped onto 0 and 1. Values outside of this
+      range are clipped.
+  */
+  Linear(f64, f64),
+  Log(f64, f64),
+  Asinh(f64, f64),
+}
+
+impl Stretch {
+  pub fn normalize(&self, value: f64) -> f64 {
+    use Stretch::*;
+    let (low, high) = match *self {
+      Linear(low, high) | Log(low, high) | Asinh(low, high) => (low, high),
+    };
+
+    //(1) linear map onto [0,1] and clip
+    let lin = ((value - low) / (high - low)).clamp(0.0, 1.0);
+
+    //(2) apply the actual stretch
+    match self {
+      Linear(..) => lin,
+      Log(..) => (LOG_EXPONENT * lin + 1.0).log10() / (LOG_EXPONENT + 1.0).log10(),
+      Asinh(..) => (ASINH_EXPONENT * lin).asinh() / ASINH_EXPONENT.asinh(),
+    }
+  }
+}
+
+pub(crate) fn render<T>(data: &Array<T, IxDyn>, cm: Colormap, stretch: Stretch) -> RgbImage
+where
+  T: ToPrimitive + Copy,
+{
+  /*  Note:
+      The first FITS axis (NAXIS1) runs along the width of the image and the
+      second axis (NAXIS2) along the height. Images with more than two axes
+      are rendered using the first plane (index zero along all other axes).
+
+      FITS images have their origin in the lower left corner, whereas raster
+      images have their origin in the upper left corner. We flip the y-axis
+      to make sure the rendered image isn't upside down.
+  */
+  let shape = data.shape();
+  let width = shape.first().copied().unwrap_or(1);
+  let height = shape.get(1).copied().unwrap_or(1);
+
+  let mut index = vec![0usize; data.ndim()];
+  let mut raster = RgbImage::new(width as u32, height as u32);
+
+  for y in 0..height {
+    for x in 0..width {
+      if let Some(ix) = index.get_mut(0) {
+        *ix = x;
+      }
+      if let Some(iy) = index.get_mut(1) {
+        *iy = y;
+      }
+
+      let value = data[index.as_slice()].to_f64().unwrap_or(f64::NAN);
+      let colour = cm.colorize(stretch.normalize(value));
+      raster.put_pixel(x as u32, (height - 1 - y) as u32, Rgb(colour));
+    }
+  }
+
+  raster
+}
@@ -0,0 +1,513 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    The FITS tiled image convention stores compressed images in binary tables
+    (with ZIMAGE = T). The image is divided into tiles of ZTILE1 x ZTILE2 x ...
+    pixels, and each row of the table contains one compressed tile. The tiles
+    themselves live in the heap of the table, the COMPRESSED_DATA column holds
+    descriptors that point to them. Floating point images are quantized to
+    integers before they are compressed, using a linear scaling (ZSCALE and
+    ZZERO) that is stored per tile, optionally with random dithering.
+
+    The keywords of the original image are stored in the header of the table,
+    prefixed with a Z (ZBITPIX, ZNAXIS...). After decompression the header is
+    turned back into the header of the original image, such that the HDU is
+    indistinguishable from an uncompressed image.
+*/
+
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE; // = 2880B
+
+//Number of random numbers used for dithering (see fits_init_randoms in cfitsio)
+const N_RANDOM: usize = 10000;
+
+//Quantized pixels with this value are zero (SUBTRACTIVE_DITHER_2 only)
+const ZERO_VALUE: i64 = -2147483646;
+
+use std::{
+  error::Error,
+  fmt::{Debug, Display},
+  sync::OnceLock,
+};
+
+use ndarray::{Array, ShapeBuilder};
+use num_traits::Num;
+use rayon::prelude::*;
+use rustronomy_core::data_type_traits::io_utils::{Decode, Encode};
+
+use crate::{
+  bitpix::Bitpix,
+  hdu_err::NotImplementedErr,
+  header::Header,
+  img_err::{self, CompressedImgErr},
+  raw::{
+    bin_entry_format::BinEntryFormat, keyword_utils::unquote_fits_string, raw_io::RawFitsReader,
+    rice,
+  },
+  Extension,
+};
+
+use super::{generic_image::Image, typed_image::TypedImage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantize {
+  //How floating point pixels were quantized (ZQUANTIZ)
+  NoDither,
+  SubtractiveDither1,
+  SubtractiveDither2,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CompressedImage {
+  bitpix: Bitpix,    //ZBITPIX
+  shape: Vec<usize>, //ZNAXIS{i}
+  tile: Vec<usize>,  //ZTILE{i}
+  block_size: usize, //BLOCKSIZE parameter of the Rice codec
+  bytepix: usize,    //BYTEPIX parameter of the Rice codec
+  quantize: Quantize,
+  dither_offset: usize,      //ZDITHER0
+  scale: Option<(f64, f64)>, //ZSCALE and ZZERO, if they are not columns
+  blank: Option<i64>,        //ZBLANK, if it is not a column
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct TileColumns {
+  //Byte offsets of the columns we use within a row of the table
+  data: Option<(usize, bool)>, //COMPRESSED_DATA (and whether it's a Q descriptor)
+  scale: Option<usize>,        //ZSCALE
+  zero: Option<usize>,         //ZZERO
+  blank: Option<usize>,        //ZBLANK
+  fallback: bool,              //GZIP_COMPRESSED_DATA or UNCOMPRESSED_DATA
+}
+
+impl CompressedImage {
+  pub(crate) fn from_header(header: &Header) -> Result<Option<Self>, Box<dyn Error>> {
+    /*
+        Returns None if the header does not describe a compressed image. We
+        require ZIMAGE = T, and the following keywords:
+            ZCMPTYPE => compression algorithm (only RICE_1 is supported)
+            ZBITPIX => data type of the image
+            ZNAXIS, ZNAXIS{i} => shape of the image
+        And optionally:
+            ZTILE{i} => shape of the tiles (default: rows of the image)
+            ZNAME{i}, ZVAL{i} => parameters of the compression algorithm
+            ZQUANTIZ, ZDITHER0 => quantization of floating point images
+            ZSCALE, ZZERO, ZBLANK => if they are constant for all tiles
+    */
+    if header.get_value("ZIMAGE").map(|val| val.as_str()) != Some("T") {
+      return Ok(None);
+    }
+
+    //(1) Check the compression algorithm
+    let cmptype: String = header.get_value_as("ZCMPTYPE")?;
+    let cmptype = unquote_fits_string(&cmptype).trim().to_string();
+    if cmptype != "RICE_1" && cmptype != "RICE_ONE" {
+      return Err(Box::new(NotImplementedErr::new(format!(
+        "BINTABLE ({cmptype} compressed image)"
+      ))));
+    }
+
+    //(2) Shape and data type of the image, and the shape of the tiles
+    let bitpix = Bitpix::try_from(header.get_value_as::<i64>("ZBITPIX")?)?;
+    let naxis: usize = header.get_value_as("ZNAXIS")?;
+    let mut shape = Vec::new();
+    let mut tile = Vec::new();
+    for i in 1..=naxis {
+      let len: usize = header.get_value_as(&format!("ZNAXIS{i}"))?;
+      let default_tile = if i == 1 { len } else { 1 };
+      tile.push(header.get_value_as(&format!("ZTILE{i}")).unwrap_or(default_tile).max(1));
+      shape.push(len);
+    }
+
+    //(3) Parameters of the Rice codec
+    let (mut block_size, mut bytepix) = (32, 4);
+    for i in 1.. {
+      let Some(name) = header.get_value(&format!("ZNAME{i}")) else { break };
+      match unquote_fits_string(name).trim() {
+        "BLOCKSIZE" => block_size = header.get_value_as(&format!("ZVAL{i}"))?,
+        "BYTEPIX" => bytepix = header.get_value_as(&format!("ZVAL{i}"))?,
+        _ => {} //unknown parameters are ignored
+      }
+    }
+
+    //(4) Quantization of floating point images
+    let quantize = match header.get_value("ZQUANTIZ").map(|val| unquote_fits_string(val)) {
+      Some(val) if val.trim() == "SUBTRACTIVE_DITHER_1" => Quantize::SubtractiveDither1,
+      Some(val) if val.trim() == "SUBTRACTIVE_DITHER_2" => Quantize::SubtractiveDither2,
+      _ => Quantize::NoDither,
+    };
+    let dither_offset = header.get_value_as("ZDITHER0").unwrap_or(1).max(1);
+    let scale = match (header.get_value("ZSCALE"), header.get_value("ZZERO")) {
+      (Some(_), _) => {
+        Some((header.get_value_as("ZSCALE")?, header.get_value_as("ZZERO").unwrap_or(0.0)))
+      }
+      _ => None,
+    };
+    let blank = header.get_value_as("ZBLANK").ok();
+
+    Ok(Some(CompressedImage {
+      bitpix,
+      shape,
+      tile,
+      block_size,
+      bytepix,
+      quantize,
+      dither_offset,
+      scale,
+      blank,
+    }))
+  }
+
+  pub(crate) fn decode(
+    &self,
+    reader: &mut RawFitsReader,
+    header: &Header,
+  ) -> Result<Extension, Box<dyn Error>> {
+    //(1) Read the table and its heap in one go, like any other binary table
+    let row_len: usize = header.get_value_as("NAXIS1")?;
+    let n_rows: usize = header.get_value_as("NAXIS2")?;
+    let heap_size: usize = header.get_value_as("PCOUNT")?;
+    let heap_start: usize = header.get_value_as("THEAP").unwrap_or(row_len * n_rows);
+    let num_blocks = (row_len * n_rows + heap_size).div_ceil(BLOCK_SIZE);
+    let mut data_unit = vec![0u8; num_blocks * BLOCK_SIZE];
+    reader.read_blocks(&mut data_unit)?;
+
+    //(2) Find the columns that describe the tiles
+    let columns = Self::find_columns(header)?;
+    if n_rows != self.n_tiles() {
+      return Err(Box::new(CompressedImgErr::new(img_err::TILE_COUNT)));
+    }
+
+    //(3) Decompress the tiles (in parallel, so errors have to be Send)
+    let heap = &data_unit[heap_start.min(data_unit.len())..];
+    let tiles = (0..n_rows)
+      .into_par_iter()
+      .map(|row| {
+        let row_bytes = &data_unit[row * row_len..(row + 1) * row_len];
+        self.decode_tile(row, row_bytes, heap, &columns)
+      })
+      .collect::<Result<Vec<Vec<f64>>, Box<dyn Error + Send + Sync>>>()
+      .map_err(|err| err as Box<dyn Error>)?;
+
+    //(4) Put the tiles in the right place and convert the pixels to the
+    //    data type of the image
+    use Bitpix::*;
+    use TypedImage::*;
+    Ok(Extension::Image(match self.bitpix {
+      Byte => ByteImg(self.assemble(&tiles, |px| px as u8)?),
+      Short => I16Img(self.assemble(&tiles, |px| px as i16)?),
+      Int => I32Img(self.assemble(&tiles, |px| px as i32)?),
+      Long => I64Img(self.assemble(&tiles, |px| px as i64)?),
+      Spf => SpfImg(self.assemble(&tiles, |px| px as f32)?),
+      Dpf => DpfImg(self.assemble(&tiles, |px| px)?),
+    }))
+  }
+
+  pub(crate) fn restore_header(header: &mut Header) {
+    /*
+        Turns the header of the table back into the header of the image it
+        contains. Checksums of the table do not apply to the image, and the
+        checksums of the original image (ZHECKSUM, ZDATASUM) cannot be
+        verified without compressing the image again, so we drop both.
+    */
+    if header.get_value("ZIMAGE").map(|val| val.as_str()) != Some("T") {
+      return;
+    }
+    let is_int = header.get_value_as::<isize>("ZBITPIX").is_ok_and(|bpx| bpx > 0);
+
+    //(1) Remove the keywords describing the table
+    let nfields: usize = header.get_value_as("TFIELDS").unwrap_or(0);
+    for i in 1..=nfields {
+      for keyword in ["TTYPE", "TFORM", "TUNIT", "TDIM", "TSCAL", "TZERO", "TNULL", "TDISP"] {
+        header.remove_record(&format!("{keyword}{i}"));
+      }
+    }
+    let naxis: usize = header.get_value_as("ZNAXIS").unwrap_or(0);
+    for keyword in ["TFIELDS", "THEAP", "NAXIS1", "NAXIS2", "CHECKSUM", "DATASUM"] {
+      header.remove_record(keyword);
+    }
+
+    //(2) Restore the keywords of the image
+    header.set_value("XTENSION", String::from("'IMAGE   '"));
+    header.set_value("PCOUNT", String::from("0"));
+    header.set_value("GCOUNT", String::from("1"));
+    let restored = ["BITPIX", "NAXIS", "XTENSION", "PCOUNT", "GCOUNT"].map(String::from);
+    for keyword in restored.into_iter().chain((1..=naxis).map(|i| format!("NAXIS{i}"))) {
+      let compressed = format!("Z{keyword}").replace("ZXTENSION", "ZTENSION");
+      if let Some(value) = header.remove_record(&compressed).and_then(|record| record.value) {
+        header.set_value(&keyword, value);
+      }
+    }
+    if let (true, Some(blank)) = (is_int, header.remove_record("ZBLANK").and_then(|rec| rec.value))
+    {
+      if !header.contains("BLANK") {
+        header.set_value("BLANK", blank);
+      }
+    }
+
+    //(3) Remove the keywords describing the compression
+    for i in 1..=naxis {
+      header.remove_record(&format!("ZTILE{i}"));
+    }
+    let n_params = (1..).take_while(|i| header.contains(&format!("ZNAME{i}"))).count();
+    for i in 1..=n_params {
+      header.remove_record(&format!("ZNAME{i}"));
+      header.remove_record(&format!("ZVAL{i}"));
+    }
+    for keyword in [
+      "ZIMAGE", "ZCMPTYPE", "ZQUANTIZ", "ZDITHER0", "ZMASKCMP", "ZSCALE", "ZZERO", "ZBLANK",
+      "ZSIMPLE", "ZEXTEND", "ZBLOCKED", "ZHECKSUM", "ZDATASUM",
+    ] {
+      header.remove_record(keyword);
+    }
+
+    //Images without a name get this name when they are compressed
+    if header
+      .get_value("EXTNAME")
+      .is_some_and(|name| unquote_fits_string(name).trim() == "COMPRESSED_IMAGE")
+    {
+      header.remove_record("EXTNAME");
+    }
+    header.update_block_len();
+  }
+
+  fn find_columns(header: &Header) -> Result<TileColumns, Box<dyn Error>> {
+    //Fields are stored back-to-back, so we have to know the width of every
+    //field to find the offsets of the ones we need
+    let nfields: usize = header.get_value_as("TFIELDS")?;
+    let mut columns = TileColumns::default();
+    let mut offset = 0;
+    for i in 1..=nfields {
+      let tform: String = header.get_value_as(&format!("TFORM{i}"))?;
+      let tform = unquote_fits_string(&tform).trim().to_string();
+      let label = header
+        .get_value(&format!("TTYPE{i}"))
+        .map(|val| unquote_fits_string(val).trim().to_string());
+
+      //Variable-length arrays are stored as descriptors: two 32 bits (P) or
+      //two 64 bits (Q) integers containing the length and offset of the array
+      let width = BinEntryFormat::from_tform(&tform)?.get_field_width();
+
+      match label.as_deref() {
+        Some("COMPRESSED_DATA") => columns.data = Some((offset, width == 16)),
+        Some("ZSCALE") => columns.scale = Some(offset),
+        Some("ZZERO") => columns.zero = Some(offset),
+        Some("ZBLANK") => columns.blank = Some(offset),
+        Some("GZIP_COMPRESSED_DATA" | "UNCOMPRESSED_DATA") => columns.fallback = true,
+        _ => {}
+      }
+      offset += width;
+    }
+
+    match columns.data {
+      Some(_) => Ok(columns),
+      None => Err(Box::new(CompressedImgErr::new(img_err::NO_TILE_DATA))),
+    }
+  }
+
+  fn decode_tile(
+    &self,
+    row: usize,
+    row_bytes: &[u8],
+    heap: &[u8],
+    columns: &TileColumns,
+  ) -> Result<Vec<f64>, Box<dyn Error + Send + Sync>> {
+    //(1) Find the compressed tile in the heap
+    let (data, is_q) = columns.data.unwrap();
+    let (len, offset) = match is_q {
+      false => (
+        i32::from_be_bytes(row_bytes[data..data + 4].try_into()?) as usize,
+        i32::from_be_bytes(row_bytes[data + 4..data + 8].try_into()?) as usize,
+      ),
+      true => (
+        i64::from_be_bytes(row_bytes[data..data + 8].try_into()?) as usize,
+        i64::from_be_bytes(row_bytes[data + 8..data + 16].try_into()?) as usize,
+      ),
+    };
+    if len == 0 && columns.fallback {
+      //Tiles that could not be Rice compressed are stored differently
+      return Err(Box::new(NotImplementedErr::new(String::from(
+        "BINTABLE (uncompressed image tiles)",
+      ))));
+    }
+    let compressed =
+      heap.get(offset..offset + len).ok_or(CompressedImgErr::new(img_err::TILE_OUT_OF_HEAP))?;
+
+    //(2) Decompress the tile
+    let (_, extent) = self.tile_extent(row);
+    let n_pixels = extent.iter().product();
+    let pixels = rice::decompress(compressed, n_pixels, self.block_size, self.bytepix)?;
+
+    //(3) Integer images are stored as they are, floating point images have
+    //    to be converted back from the quantized integers
+    let read_f64 = |offset: Option<usize>| {
+      offset.map(|at| f64::from_be_bytes(row_bytes[at..at + 8].try_into().unwrap()))
+    };
+    let scale = match (read_f64(columns.scale), read_f64(columns.zero)) {
+      (Some(scale), zero) => Some((scale, zero.unwrap_or(0.0))),
+      (None, _) => self.scale,
+    };
+    let blank = match columns.blank {
+      Some(at) => Some(i32::from_be_bytes(row_bytes[at..at + 4].try_into()?) as i64),
+      None => self.blank,
+    };
+
+    Ok(match (self.bitpix, scale) {
+      (Bitpix::Spf | Bitpix::Dpf, Some((scale, zero))) => {
+        self.unquantize(row, &pixels, scale, zero, blank)
+      }
+      (Bitpix::Spf | Bitpix::Dpf, None) => {
+        return Err(Box::new(NotImplementedErr::new(String::from(
+          "BINTABLE (unquantized floating point image)",
+        ))))
+      }
+      _ => pixels.into_iter().map(f64::from).collect(),
+    })
+  }
+
+  fn unquantize(
+    &self,
+    row: usize,
+    pixels: &[i32],
+    scale: f64,
+    zero: f64,
+    blank: Option<i64>,
+  ) -> Vec<f64> {
+    /*  Quantized pixels are converted back to floats using
+            value = (pixel - dither + 0.5) * ZSCALE + ZZERO
+        where the dither consists of (pseudo) random numbers in [0, 1). The
+        random numbers of each tile start at an offset that depends on the
+        index of the tile and ZDITHER0. We have to reproduce them exactly!
+    */
+    let undefined = |pix: i32| blank == Some(pix as i64);
+    if self.quantize == Quantize::NoDither {
+      return pixels
+        .iter()
+        .map(|&pix| if undefined(pix) { f64::NAN } else { pix as f64 * scale + zero })
+        .collect();
+    }
+
+    let randoms = dither_randoms();
+    let mut iseed = (row + self.dither_offset - 1) % N_RANDOM;
+    let mut next = (randoms[iseed] * 500.0) as usize;
+    pixels
+      .iter()
+      .map(|&pix| {
+        let value = if undefined(pix) {
+          f64::NAN
+        } else if self.quantize == Quantize::SubtractiveDither2 && pix as i64 == ZERO_VALUE {
+          0.0
+        } else {
+          (pix as f64 - randoms[next] as f64 + 0.5) * scale + zero
+        };
+
+        //Every pixel uses up a random number, even undefined ones
+        next += 1;
+        if next == N_RANDOM {
+          iseed = (iseed + 1) % N_RANDOM;
+          next = (randoms[iseed] * 500.0) as usize;
+        }
+        value
+      })
+      .collect()
+  }
+
+  fn n_tiles(&self) -> usize {
+    self.shape.iter().zip(&self.tile).map(|(&len, &tile)| len.div_ceil(tile)).product()
+  }
+
+  fn tile_extent(&self, index: usize) -> (Vec<usize>, Vec<usize>) {
+    //Position and shape of a tile. Tiles are ordered like pixels (the first
+    //axis varies fastest), and the tiles at the edges may be smaller
+    let mut rest = index;
+    let mut origin = Vec::new();
+    let mut extent = Vec::new();
+    for (&len, &tile) in self.shape.iter().zip(&self.tile) {
+      let n_tiles = len.div_ceil(tile);
+      let start = (rest % n_tiles) * tile;
+      rest /= n_tiles;
+      origin.push(start);
+      extent.push(tile.min(len - start));
+    }
+    (origin, extent)
+  }
+
+  fn assemble<T>(
+    &self,
+    tiles: &[Vec<f64>],
+    convert: fn(f64) -> T,
+  ) -> Result<Image<T>, Box<dyn Error>>
+  where
+    T: Debug + Num + Sized + Decode + Encode + Display + Clone,
+  {
+    //Copies the pixels of all tiles into one flat array (Fortran order)
+    let strides = self
+      .shape
+      .iter()
+      .scan(1, |stride, &len| {
+        let current = *stride;
+        *stride *= len;
+        Some(current)
+      })
+      .collect::<Vec<usize>>();
+    let mut flat = vec![T::zero(); self.shape.iter().product()];
+
+    for (index, tile) in tiles.iter().enumerate() {
+      let (origin, extent) = self.tile_extent(index);
+      let mut local = vec![0; extent.len()];
+      for &pixel in tile {
+        let position: usize =
+          origin.iter().zip(&local).zip(&strides).map(|((o, l), s)| (o + l) * s).sum();
+        flat[position] = convert(pixel);
+
+        //Move to the next pixel in the tile (first axis fastest)
+        for (axis, coord) in local.iter_mut().enumerate() {
+          *coord += 1;
+          if *coord < extent[axis] {
+            break;
+          }
+          *coord = 0;
+        }
+      }
+    }
+
+    let array = Array::from_shape_vec(self.shape.clone().f(), flat)?;
+    Ok(Image::new(array.into_dyn()))
+  }
+}
+
+fn dither_randoms() -> &'static [f32] {
+  /*
+      The random numbers used for dithering are generated with a Park-Miller
+      generator (seed 1), exactly like cfitsio does. They are stored as f32's,
+      since that's what cfitsio does as well.
+  */
+  static RANDOMS: OnceLock<Vec<f32>> = OnceLock::new();
+  RANDOMS.get_or_init(|| {
+    let (a, m) = (16807.0, 2147483647.0);
+    let mut seed: f64 = 1.0;
+    (0..N_RANDOM)
+      .map(|_| {
+        let temp = a * seed;
+        seed = temp - m * (temp / m).trunc();
+        (seed / m) as f32
+      })
+      .collect()
+  })
+}
@@ -17,13 +17,37 @@
     along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use std::fmt::{Debug, Display};
+use std::{
+  cmp::Ordering,
+  fmt::{Debug, Display},
+  mem,
+  ops::Range,
+};
 
-use ndarray::{Array, IxDyn};
-use num_traits::Num;
+use ndarray::{Array, ArrayView1, Axis, IxDyn, ShapeBuilder, Slice};
+use num_traits::{FromPrimitive, Num};
 use rustronomy_core::data_type_traits::io_utils::{Decode, Encode};
 
-use crate::raw::BlockSized;
+use crate::{
+  img_err::{ShapeErr, SliceErr},
+  raw::BlockSized,
+};
+
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReduceOp {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Operations that can be used to collapse an image along one of its axes.
+      The median of an even number of values is the mean of the two middle
+      values.
+  */
+  Sum,
+  Mean,
+  Min,
+  Max,
+  Median,
+}
 
 #[derive(Debug, Clone)]
 pub struct Image<T>
@@ -31,9 +55,9 @@ where
   T: Debug + Num + Sized + Decode + Encode + Display + Clone,
 {
   /*  THIS STRUCT IS NOT PART OF THE USER-FACING API
-      None of the implementations or fields of this struct are public.
-      Users interface with Images through the TypedImage enum and its impleme-
-      ntations.
+      None of the fields of this struct are public, and only a handful of
+      its implementations are. Users interface with Images through the
+      TypedImage enum and its implementations.
   */
   shape: Vec<usize>,
   data: Array<T, IxDyn>,
@@ -49,6 +73,17 @@ where
   }
 }
 
+impl<T> PartialEq for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone,
+{
+  fn eq(&self, other: &Self) -> bool {
+    //Images are equal if they have the same shape and the same pixels (so
+    //images containing NaN's are never equal)
+    self.shape == other.shape && self.data == other.data
+  }
+}
+
 impl<T> Image<T>
 where
   T: Debug + Num + Sized + Decode + Encode + Display + Clone,
@@ -57,7 +92,73 @@ where
       PUBLIC API
   */
   pub fn new(array: Array<T, IxDyn>) -> Self {
-    todo!()
+    //The size of the image in FITS blocks follows from the #bytes per entry
+    let shape = array.shape().to_vec();
+    let block_size = (array.len() * mem::size_of::<T>()).div_ceil(BLOCK_SIZE);
+    Image { shape, data: array, block_size }
+  }
+
+  pub fn get_data(&self) -> &Array<T, IxDyn> {
+    &self.data
+  }
+
+  pub fn get_shape(&self) -> &Vec<usize> {
+    &self.shape
+  }
+
+  pub fn reduce_along_axis(&self, axis: usize, op: ReduceOp) -> Result<Image<T>, ShapeErr>
+  where
+    T: PartialOrd + FromPrimitive,
+  {
+    /*
+        Collapses the image along the specified axis, for instance to turn a
+        spectral cube into a 2D moment map. The resulting image has one axis
+        fewer than the original.
+    */
+
+    //(1) We cannot reduce along axes that do not exist or are empty
+    if axis >= self.data.ndim() || self.shape[axis] == 0 {
+      return Err(ShapeErr::new(axis, &self.shape));
+    }
+
+    //(2) Apply the reduction to all lanes along the axis
+    let reduced = self.data.map_axis(Axis(axis), |lane| match op {
+      ReduceOp::Sum => Self::lane_sum(&lane),
+      ReduceOp::Mean => Self::lane_sum(&lane) / T::from_usize(lane.len()).unwrap(),
+      ReduceOp::Min => Self::lane_extremum(&lane, Ordering::Less),
+      ReduceOp::Max => Self::lane_extremum(&lane, Ordering::Greater),
+      ReduceOp::Median => Self::lane_median(&lane),
+    });
+
+    //(R) wrap the reduced array in a new image
+    Ok(Image::new(reduced))
+  }
+
+  pub fn subimage(&self, ranges: &[Range<usize>]) -> Result<Image<T>, SliceErr> {
+    /*
+        Copies a sub-region of the image, for instance a spatial subcube or
+        a single channel of a spectral cube. There has to be one range for
+        each axis. The copy keeps the memory layout of the original image.
+    */
+
+    //(1) All ranges have to fit inside the image
+    let fits =
+      |(range, len): (&Range<usize>, &usize)| range.start <= range.end && range.end <= *len;
+    if ranges.len() != self.shape.len() || !ranges.iter().zip(&self.shape).all(fits) {
+      return Err(SliceErr::new(ranges, &self.shape));
+    }
+
+    //(2) Slice the array and copy the slice
+    let view = self.data.slice_each_axis(|ax| Slice::from(ranges[ax.axis.index()].clone()));
+    let sub = match self.data.is_standard_layout() || !self.data.t().is_standard_layout() {
+      true => view.to_owned(),
+      //Fortran layout: the transposed view iterates in memory order
+      false => Array::from_shape_vec(view.raw_dim().f(), view.t().iter().cloned().collect())
+        .expect("slice should have as many pixels as its shape"),
+    };
+
+    //(R) the size in FITS blocks follows from the sliced shape
+    Ok(Image::new(sub))
   }
 
   /*
@@ -68,14 +169,52 @@ where
   }
 
   //Getters
-  pub(crate) fn get_data(&self) -> &Array<T, IxDyn> {
-    &self.data
-  }
   pub(crate) fn get_data_owned(self) -> Array<T, IxDyn> {
     self.data
   }
-  pub(crate) fn get_shape(&self) -> &Vec<usize> {
-    &self.shape
+  pub(crate) fn get_data_mut(&mut self) -> &mut Array<T, IxDyn> {
+    //Callers may only change pixel values, not the shape of the image!
+    &mut self.data
+  }
+
+  //Helpers for reduce_along_axis
+  fn lane_sum(lane: &ArrayView1<T>) -> T {
+    lane.fold(T::zero(), |sum, val| sum + val.clone())
+  }
+
+  fn lane_extremum(lane: &ArrayView1<T>, keep: Ordering) -> T
+  where
+    T: PartialOrd,
+  {
+    //Keeps the value that compares as `keep` to all others (NaN's are skipped).
+    //Lanes with only NaN's reduce to NaN
+    let mut vals = lane.iter().filter(|val| val.partial_cmp(val).is_some());
+    let Some(first) = vals.next() else { return lane[0].clone() };
+    vals.fold(first.clone(), |best, val| match val.partial_cmp(&best) {
+      Some(ord) if ord == keep => val.clone(),
+      _ => best,
+    })
+  }
+
+  fn lane_median(lane: &ArrayView1<T>) -> T
+  where
+    T: PartialOrd + FromPrimitive,
+  {
+    //Quickselect the middle value (partial sort, O(n) on average)
+    let mut vals = lane.to_vec();
+    let mid = vals.len() / 2;
+    let cmp = |a: &T, b: &T| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+    let (lower, median, _) = vals.select_nth_unstable_by(mid, cmp);
+    let median = median.clone();
+
+    if lane.len() % 2 == 1 {
+      median
+    } else {
+      //Even #values: average the two middle values. The lower middle value
+      //is the largest value in the lower partition
+      let lower_mid = lower.iter().cloned().reduce(|a, b| if cmp(&a, &b).is_lt() { b } else { a });
+      (lower_mid.unwrap() + median) / T::from_u8(2).unwrap()
+    }
   }
 
   pub(crate) fn pretty_print_shape(&self) -> String {
@@ -87,3 +226,27 @@ where
     String::from(rsp + ")")
   }
 }
+
+/*
+    Images are (de)serialized as their bare array. The shape and the size in
+    FITS blocks follow from the array, so they are recomputed on the way in.
+*/
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + serde::Serialize,
+{
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.data.serialize(serializer)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + serde::Deserialize<'de>,
+{
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Ok(Image::new(Array::deserialize(deserializer)?))
+  }
+}
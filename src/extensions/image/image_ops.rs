@@ -0,0 +1,161 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+    Pixel-wise arithmetic on images, for instance to subtract a bias level or
+    to add up exposures. Large images are processed in parallel.
+*/
+
+use std::{
+  fmt::{Debug, Display},
+  ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
+};
+
+use ndarray::Zip;
+use num_traits::Num;
+use rustronomy_core::data_type_traits::io_utils::{Decode, Encode};
+
+use super::Image;
+use crate::img_err::ShapeMismatchErr;
+
+//Images with more pixels than this are processed using rayon
+const PAR_THRESHOLD: usize = 1_000_000;
+
+impl<T> Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  fn apply_scalar<F>(&mut self, f: F)
+  where
+    F: Fn(&mut T) + Send + Sync,
+  {
+    let data = self.get_data_mut();
+    match data.len() > PAR_THRESHOLD {
+      true => data.par_map_inplace(f),
+      false => data.map_inplace(f),
+    }
+  }
+
+  fn apply_image<F>(&mut self, other: &Image<T>, f: F) -> Result<(), ShapeMismatchErr>
+  where
+    F: Fn(&mut T, &T) + Send + Sync,
+  {
+    //Images are combined pixel by pixel, they are NOT broadcast
+    if self.get_shape() != other.get_shape() {
+      return Err(ShapeMismatchErr::new(self.get_shape(), other.get_shape()));
+    }
+
+    let zip = Zip::from(self.get_data_mut()).and(other.get_data());
+    match other.get_data().len() > PAR_THRESHOLD {
+      true => zip.par_for_each(f),
+      false => zip.for_each(f),
+    }
+    Ok(())
+  }
+}
+
+/*
+    Adding, subtracting and multiplying by a scalar applies the operation to
+    each pixel
+*/
+impl<T> AddAssign<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  fn add_assign(&mut self, rhs: T) {
+    self.apply_scalar(|px| *px = px.clone() + rhs.clone())
+  }
+}
+
+impl<T> SubAssign<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  fn sub_assign(&mut self, rhs: T) {
+    self.apply_scalar(|px| *px = px.clone() - rhs.clone())
+  }
+}
+
+impl<T> MulAssign<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  fn mul_assign(&mut self, rhs: T) {
+    self.apply_scalar(|px| *px = px.clone() * rhs.clone())
+  }
+}
+
+impl<T> Add<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  type Output = Image<T>;
+  fn add(mut self, rhs: T) -> Self::Output {
+    self += rhs;
+    self
+  }
+}
+
+impl<T> Sub<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  type Output = Image<T>;
+  fn sub(mut self, rhs: T) -> Self::Output {
+    self -= rhs;
+    self
+  }
+}
+
+impl<T> Mul<T> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  type Output = Image<T>;
+  fn mul(mut self, rhs: T) -> Self::Output {
+    self *= rhs;
+    self
+  }
+}
+
+/*
+    Adding two images fails if their shapes differ. AddAssign cannot return
+    an error, so it panics instead (like ndarray does).
+*/
+impl<T> Add<Image<T>> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  type Output = Result<Image<T>, ShapeMismatchErr>;
+  fn add(mut self, rhs: Image<T>) -> Self::Output {
+    self.apply_image(&rhs, |px, other| *px = px.clone() + other.clone())?;
+    Ok(self)
+  }
+}
+
+impl<T> AddAssign<Image<T>> for Image<T>
+where
+  T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send + Sync,
+{
+  fn add_assign(&mut self, rhs: Image<T>) {
+    if let Err(err) = self.apply_image(&rhs, |px, other| *px = px.clone() + other.clone()) {
+      panic!("{err}");
+    }
+  }
+}
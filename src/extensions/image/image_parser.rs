@@ -26,7 +26,7 @@ const MIN_BLOCKS_IN_BUF: usize = 1; // = 3kB
 
 use std::{
   error::Error,
-  fmt::{Debug, Display},
+  fmt::{self, Debug, Display, Formatter},
   mem::size_of,
 };
 
@@ -47,6 +47,41 @@ use crate::{
 
 use super::{generic_image::Image, typed_image::TypedImage};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ScalingMode {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Integer images may be scaled using the BSCALE and BZERO keywords:
+          physical = BSCALE * stored + BZERO
+      Scaled images are decoded as f64 images. The default (Auto) only scales
+      images with non-trivial BSCALE or BZERO values, like cfitsio does.
+  */
+  Physical,
+  Raw,
+  #[default]
+  Auto,
+}
+
+impl Display for ScalingMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      ScalingMode::Physical => write!(f, "physical"),
+      ScalingMode::Raw => write!(f, "raw"),
+      ScalingMode::Auto => write!(f, "auto"),
+    }
+  }
+}
+
+impl ScalingMode {
+  pub(crate) fn applies(&self, bscale: f64, bzero: f64) -> bool {
+    match self {
+      ScalingMode::Physical => true,
+      ScalingMode::Raw => false,
+      ScalingMode::Auto => bscale != 1.0 || bzero != 0.0,
+    }
+  }
+}
+
 /*
     THIS IS NOT PART OF THE USER-FACING API
     ImgParser (and its implementation) are a helper struct used to decode and
@@ -74,6 +109,42 @@ impl ImgParser {
     }))
   }
 
+  //Decoder for images that already have storage of the right type and shape
+  pub(crate) fn decode_img_into(bytes: &[u8], img: &mut TypedImage) {
+    use TypedImage::*;
+
+    match img {
+      ByteImg(img) => Self::decode_into_helper(bytes, img),
+      I16Img(img) => Self::decode_into_helper(bytes, img),
+      I32Img(img) => Self::decode_into_helper(bytes, img),
+      I64Img(img) => Self::decode_into_helper(bytes, img),
+      SpfImg(img) => Self::decode_into_helper(bytes, img),
+      DpfImg(img) => Self::decode_into_helper(bytes, img),
+    }
+  }
+
+  fn decode_into_helper<T>(bytes: &[u8], img: &mut Image<T>)
+  where
+    T: Debug + Num + Sized + Decode + Encode + Display + Clone + Send,
+  {
+    /*
+        The pixels are stored in the Fortran memory-layout (see decode_helper),
+        so we reverse the axes of the array to visit the pixels in the order
+        in which they appear in the data unit. Arrays that are contiguous in
+        this order are decoded in parallel.
+    */
+    let entry_size = size_of::<T>();
+    let mut data = img.get_data_mut().view_mut().reversed_axes();
+    if data.is_standard_layout() {
+      let flat = data.as_slice_mut().unwrap();
+      flat.par_iter_mut().zip(bytes.par_chunks(entry_size)).for_each(|(px, val)| {
+        *px = T::from_bytes(val);
+      });
+    } else {
+      data.iter_mut().zip(bytes.chunks(entry_size)).for_each(|(px, val)| *px = T::from_bytes(val));
+    }
+  }
+
   fn decode_helper<T>(
     reader: &mut RawFitsReader,
     shape: &Vec<usize>,
@@ -22,15 +22,24 @@ use std::{
   fmt::{Display, Write},
 };
 
-use ndarray::{Array, IxDyn};
+use ndarray::{parallel::prelude::*, Array, ArrayViewD, ArrayViewMutD, IxDyn, Zip};
+use num_traits::{NumCast, ToPrimitive};
 
 use crate::{
-  bitpix::Bitpix, extensions::ExtensionPrint, img_err::WrongImgTypeErr as WITErr, raw::BlockSized,
+  bitpix::Bitpix,
+  extensions::ExtensionPrint,
+  header::Header,
+  img_err::{CastErr, WrongImgTypeErr as WITErr},
+  raw::{raw_io::RawFitsWriter, BlockSized},
 };
 
-use super::generic_image::Image;
+use super::{generic_image::Image, ImgParser};
+
+#[cfg(feature = "png")]
+use super::colormap::{self, Colormap, Stretch};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypedImage {
   /*  THIS ENUM IS PART OF THE USER-FACING API
       Users obtain a TypedImage variant when parsing a FITS Image.
@@ -43,6 +52,22 @@ pub enum TypedImage {
   DpfImg(Image<f64>),
 }
 
+impl PartialEq for TypedImage {
+  fn eq(&self, other: &Self) -> bool {
+    //Images of different data types are never equal
+    use TypedImage::*;
+    match (self, other) {
+      (ByteImg(a), ByteImg(b)) => a == b,
+      (I16Img(a), I16Img(b)) => a == b,
+      (I32Img(a), I32Img(b)) => a == b,
+      (I64Img(a), I64Img(b)) => a == b,
+      (SpfImg(a), SpfImg(b)) => a == b,
+      (DpfImg(a), DpfImg(b)) => a == b,
+      _ => false,
+    }
+  }
+}
+
 impl BlockSized for TypedImage {
   fn get_block_len(&self) -> usize {
     use TypedImage::*;
@@ -108,8 +133,9 @@ impl ExtensionPrint for TypedImage {
 
 impl Display for TypedImage {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    //TODO: make pretty display for image!
-    todo!()
+    //Compact summary of the image, like TypedImage(f64, 512×512, 92 blocks)
+    let shape = self.get_shape().iter().map(|ax| ax.to_string()).collect::<Vec<_>>();
+    write!(f, "TypedImage({}, {}, {} blocks)", self.bpx(), shape.join("×"), self.get_block_len())
   }
 }
 
@@ -128,6 +154,40 @@ impl TypedImage {
     }
   }
 
+  pub(crate) fn from_array<T: Pixel>(array: Array<T, IxDyn>) -> Self {
+    //Wraps the array in the variant matching its data type
+    T::into_typed_image(array)
+  }
+
+  pub(crate) fn get_shape(&self) -> &Vec<usize> {
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => img.get_shape(),
+      I16Img(img) => img.get_shape(),
+      I32Img(img) => img.get_shape(),
+      I64Img(img) => img.get_shape(),
+      SpfImg(img) => img.get_shape(),
+      DpfImg(img) => img.get_shape(),
+    }
+  }
+
+  pub fn to_fits_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+    /*
+        Serializes the image as a minimal standalone FITS file: a primary HDU
+        containing only the mandatory keywords, followed by the image data.
+    */
+    let mut writer = RawFitsWriter::in_memory();
+
+    //(1) Write the header
+    Header::new_primary_image(self.bpx(), self.get_shape()).encode_header(&mut writer)?;
+
+    //(2) Write the data (the encoder consumes the image, hence the clone)
+    ImgParser::encode_img(self.clone(), &mut writer)?;
+
+    //(R) the encoded file
+    Ok(writer.into_bytes())
+  }
+
   pub fn as_u8_array(&self) -> Result<&Array<u8, IxDyn>, Box<dyn Error>> {
     match &self {
       Self::ByteImg(img) => Ok(img.get_data()),
@@ -211,4 +271,558 @@ impl TypedImage {
       var => Err(Box::new(WITErr::new(&var, Bitpix::dpf()))),
     }
   }
+
+  pub fn view<T: Pixel>(&self) -> Result<ArrayViewD<'_, T>, WITErr> {
+    //View of the pixels, which only succeeds if T matches the pixel type
+    T::as_array(self).map(|data| data.view()).ok_or_else(|| WITErr::new(self, T::BITPIX))
+  }
+
+  pub fn view_mut<T: Pixel>(&mut self) -> Result<ArrayViewMutD<'_, T>, WITErr> {
+    //Mutable view of the pixels, which only succeeds if T matches the pixel type
+    let bpx = self.bpx();
+    T::as_array_mut(self).map(|data| data.view_mut()).ok_or(WITErr::from_bpx(bpx, T::BITPIX))
+  }
+
+  pub fn into_array<T: Pixel>(self) -> Result<Array<T, IxDyn>, WITErr> {
+    //Same as view(), but takes ownership of the pixels
+    let bpx = self.bpx();
+    T::from_typed_image(self).ok_or(WITErr::from_bpx(bpx, T::BITPIX))
+  }
+
+  pub fn into_f64(self) -> Array<f64, IxDyn> {
+    //Converts the pixels to f64 (f64 images are returned without copying)
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => pixels_to_f64(img.get_data()),
+      I16Img(img) => pixels_to_f64(img.get_data()),
+      I32Img(img) => pixels_to_f64(img.get_data()),
+      I64Img(img) => pixels_to_f64(img.get_data()),
+      SpfImg(img) => pixels_to_f64(img.get_data()),
+      DpfImg(img) => img.get_data_owned(),
+    }
+  }
+
+  pub fn cast<T: 'static + NumCast + Clone + Send>(self) -> Result<Array<T, IxDyn>, CastErr> {
+    /*
+        Casts the pixels to another type, like NumPy's astype. Casting fails
+        if some pixels cannot be represented by the new type. BLANK pixels are
+        not taken into account, use ScalingMode::Physical to turn them into
+        NaN's while reading the image.
+    */
+    use TypedImage::*;
+    let cast = match &self {
+      ByteImg(img) => cast_pixels(img.get_data()),
+      I16Img(img) => cast_pixels(img.get_data()),
+      I32Img(img) => cast_pixels(img.get_data()),
+      I64Img(img) => cast_pixels(img.get_data()),
+      SpfImg(img) => cast_pixels(img.get_data()),
+      DpfImg(img) => cast_pixels(img.get_data()),
+    };
+    cast.ok_or_else(|| CastErr::new(&self, std::any::type_name::<T>()))
+  }
+
+  pub(crate) fn to_physical(&self, bscale: f64, bzero: f64, blank: Option<i64>) -> Self {
+    /*
+        Converts the stored pixel values to physical values, following
+        physical = BSCALE * stored + BZERO
+        Pixels equal to the BLANK value (integer images only) become NaN.
+    */
+    use TypedImage::*;
+    let data = match self {
+      ByteImg(img) => physical_pixels(img.get_data(), bscale, bzero, blank),
+      I16Img(img) => physical_pixels(img.get_data(), bscale, bzero, blank),
+      I32Img(img) => physical_pixels(img.get_data(), bscale, bzero, blank),
+      I64Img(img) => physical_pixels(img.get_data(), bscale, bzero, blank),
+      SpfImg(img) => physical_pixels(img.get_data(), bscale, bzero, None),
+      DpfImg(img) => physical_pixels(img.get_data(), bscale, bzero, None),
+    };
+    DpfImg(Image::new(data))
+  }
+
+  pub fn apply<F>(&self, f: F) -> Array<f64, IxDyn>
+  where
+    F: Fn(f64) -> f64 + Sync,
+  {
+    //Applies f to all pixels (converted to f64) and returns the result. The
+    //stored image is left untouched
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => apply_pixels(img.get_data(), f),
+      I16Img(img) => apply_pixels(img.get_data(), f),
+      I32Img(img) => apply_pixels(img.get_data(), f),
+      I64Img(img) => apply_pixels(img.get_data(), f),
+      SpfImg(img) => apply_pixels(img.get_data(), f),
+      DpfImg(img) => apply_pixels(img.get_data(), f),
+    }
+  }
+
+  pub fn rescale(&self, scale: f64, zero: f64) -> Array<f64, IxDyn> {
+    //Returns scale * stored + zero for all pixels, like BSCALE and BZERO
+    self.apply(|px| scale * px + zero)
+  }
+
+  pub fn normalize(&self) -> Array<f64, IxDyn> {
+    /*
+        Maps the pixels linearly onto [0,1], using the smallest and largest
+        pixel value. NaN's stay NaN. If all pixels have the same value, the
+        normalized image is all zeros.
+    */
+    let (min, max) = self.min_max();
+    match max > min {
+      true => self.apply(|px| (px - min) / (max - min)),
+      false => self.apply(|px| px * 0.0),
+    }
+  }
+
+  pub fn blank_mask(&self, blank: i64) -> Option<Array<bool, IxDyn>> {
+    /*
+        Marks all pixels that are equal to the BLANK value as undefined
+        (true). The stored pixel values themselves are left untouched. Only
+        integer images have BLANK values, so float images return None.
+    */
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => Some(blank_pixels(img.get_data(), blank)),
+      I16Img(img) => Some(blank_pixels(img.get_data(), blank)),
+      I32Img(img) => Some(blank_pixels(img.get_data(), blank)),
+      I64Img(img) => Some(blank_pixels(img.get_data(), blank)),
+      SpfImg(_) | DpfImg(_) => None,
+    }
+  }
+
+  pub fn sum(&self) -> f64 {
+    //Sum of all pixels
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => pixel_sum(img.get_data()),
+      I16Img(img) => pixel_sum(img.get_data()),
+      I32Img(img) => pixel_sum(img.get_data()),
+      I64Img(img) => pixel_sum(img.get_data()),
+      SpfImg(img) => pixel_sum(img.get_data()),
+      DpfImg(img) => pixel_sum(img.get_data()),
+    }
+  }
+
+  pub fn product(&self) -> f64 {
+    //Product of all pixels. Integer images saturate on overflow
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => int_pixel_product(img.get_data()),
+      I16Img(img) => int_pixel_product(img.get_data()),
+      I32Img(img) => int_pixel_product(img.get_data()),
+      I64Img(img) => int_pixel_product(img.get_data()),
+      SpfImg(img) => float_pixel_product(img.get_data()),
+      DpfImg(img) => float_pixel_product(img.get_data()),
+    }
+  }
+
+  pub fn min_pixel(&self) -> f64 {
+    //Smallest pixel value. NaN's are skipped (NaN if all pixels are NaN)
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => min_pixel(img.get_data()),
+      I16Img(img) => min_pixel(img.get_data()),
+      I32Img(img) => min_pixel(img.get_data()),
+      I64Img(img) => min_pixel(img.get_data()),
+      SpfImg(img) => min_pixel(img.get_data()),
+      DpfImg(img) => min_pixel(img.get_data()),
+    }
+  }
+
+  pub fn max_pixel(&self) -> f64 {
+    //Largest pixel value. NaN's are skipped (NaN if all pixels are NaN)
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => max_pixel(img.get_data()),
+      I16Img(img) => max_pixel(img.get_data()),
+      I32Img(img) => max_pixel(img.get_data()),
+      I64Img(img) => max_pixel(img.get_data()),
+      SpfImg(img) => max_pixel(img.get_data()),
+      DpfImg(img) => max_pixel(img.get_data()),
+    }
+  }
+
+  pub fn min_max(&self) -> (f64, f64) {
+    //Smallest and largest pixel value. NaN's are skipped
+    (self.min_pixel(), self.max_pixel())
+  }
+
+  pub fn mean(&self) -> f64 {
+    //Mean of all pixels. NaN's are skipped (NaN if all pixels are NaN)
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => mean_pixel(img.get_data()),
+      I16Img(img) => mean_pixel(img.get_data()),
+      I32Img(img) => mean_pixel(img.get_data()),
+      I64Img(img) => mean_pixel(img.get_data()),
+      SpfImg(img) => mean_pixel(img.get_data()),
+      DpfImg(img) => mean_pixel(img.get_data()),
+    }
+  }
+
+  pub fn stddev(&self) -> f64 {
+    //Population standard deviation of all pixels. NaN's are skipped
+    use TypedImage::*;
+    let mean = self.mean();
+    match self {
+      ByteImg(img) => stddev_pixel(img.get_data(), mean),
+      I16Img(img) => stddev_pixel(img.get_data(), mean),
+      I32Img(img) => stddev_pixel(img.get_data(), mean),
+      I64Img(img) => stddev_pixel(img.get_data(), mean),
+      SpfImg(img) => stddev_pixel(img.get_data(), mean),
+      DpfImg(img) => stddev_pixel(img.get_data(), mean),
+    }
+  }
+
+  pub fn histogram(&self, n_bins: usize) -> (Vec<f64>, Vec<usize>) {
+    /*
+        Returns the n_bins + 1 bin edges and the n_bins counts of a histogram
+        of all pixels. The bins span [min, max] uniformly, the last bin also
+        contains the largest pixel. NaN's are skipped.
+    */
+    use TypedImage::*;
+    let (min, max) = self.min_max();
+    let edges = (0..=n_bins).map(|i| min + (max - min) * i as f64 / n_bins as f64).collect();
+    let counts = match self {
+      ByteImg(img) => histogram(img.get_data(), n_bins, min, max),
+      I16Img(img) => histogram(img.get_data(), n_bins, min, max),
+      I32Img(img) => histogram(img.get_data(), n_bins, min, max),
+      I64Img(img) => histogram(img.get_data(), n_bins, min, max),
+      SpfImg(img) => histogram(img.get_data(), n_bins, min, max),
+      DpfImg(img) => histogram(img.get_data(), n_bins, min, max),
+    };
+    (edges, counts)
+  }
+
+  #[cfg(feature = "png")]
+  pub fn to_rgb_image(&self, cm: Colormap, stretch: Stretch) -> image::RgbImage {
+    //Renders the image to an RGB raster. The stretch maps the pixel values
+    //onto [0,1], after which the colormap colorizes them.
+    use TypedImage::*;
+    match self {
+      ByteImg(img) => colormap::render(img.get_data(), cm, stretch),
+      I16Img(img) => colormap::render(img.get_data(), cm, stretch),
+      I32Img(img) => colormap::render(img.get_data(), cm, stretch),
+      I64Img(img) => colormap::render(img.get_data(), cm, stretch),
+      SpfImg(img) => colormap::render(img.get_data(), cm, stretch),
+      DpfImg(img) => colormap::render(img.get_data(), cm, stretch),
+    }
+  }
+}
+
+/*
+    Pixel types that FITS images can store. This trait is sealed: only the six
+    types that have a TypedImage variant implement it.
+*/
+pub trait Pixel: sealed::InTypedImage {}
+impl Pixel for u8 {}
+impl Pixel for i16 {}
+impl Pixel for i32 {}
+impl Pixel for i64 {}
+impl Pixel for f32 {}
+impl Pixel for f64 {}
+
+mod sealed {
+  use ndarray::{Array, IxDyn};
+
+  use super::TypedImage::{self, *};
+  use crate::{bitpix::Bitpix, extensions::image::Image};
+
+  pub trait InTypedImage: Sized {
+    const BITPIX: Bitpix;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>>;
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>>;
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage;
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>>;
+  }
+
+  impl InTypedImage for u8 {
+    const BITPIX: Bitpix = Bitpix::Byte;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        ByteImg(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        ByteImg(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      ByteImg(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        ByteImg(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+
+  impl InTypedImage for i16 {
+    const BITPIX: Bitpix = Bitpix::Short;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        I16Img(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        I16Img(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      I16Img(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        I16Img(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+
+  impl InTypedImage for i32 {
+    const BITPIX: Bitpix = Bitpix::Int;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        I32Img(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        I32Img(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      I32Img(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        I32Img(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+
+  impl InTypedImage for i64 {
+    const BITPIX: Bitpix = Bitpix::Long;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        I64Img(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        I64Img(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      I64Img(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        I64Img(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+
+  impl InTypedImage for f32 {
+    const BITPIX: Bitpix = Bitpix::Spf;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        SpfImg(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        SpfImg(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      SpfImg(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        SpfImg(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+
+  impl InTypedImage for f64 {
+    const BITPIX: Bitpix = Bitpix::Dpf;
+    fn as_array(img: &TypedImage) -> Option<&Array<Self, IxDyn>> {
+      match img {
+        DpfImg(img) => Some(img.get_data()),
+        _ => None,
+      }
+    }
+    fn as_array_mut(img: &mut TypedImage) -> Option<&mut Array<Self, IxDyn>> {
+      match img {
+        DpfImg(img) => Some(img.get_data_mut()),
+        _ => None,
+      }
+    }
+    fn into_typed_image(array: Array<Self, IxDyn>) -> TypedImage {
+      DpfImg(Image::new(array))
+    }
+    fn from_typed_image(img: TypedImage) -> Option<Array<Self, IxDyn>> {
+      match img {
+        DpfImg(img) => Some(img.get_data_owned()),
+        _ => None,
+      }
+    }
+  }
+}
+
+/*
+    Helper funcs for the global reductions. All pixels are converted to f64,
+    except when calculating the product of integer images (so that we can
+    saturate rather than lose precision).
+*/
+fn physical_pixels<T: ToPrimitive + Sync>(
+  data: &Array<T, IxDyn>,
+  bscale: f64,
+  bzero: f64,
+  blank: Option<i64>,
+) -> Array<f64, IxDyn> {
+  Zip::from(data).par_map_collect(|px| match blank {
+    Some(blank) if px.to_i64() == Some(blank) => f64::NAN,
+    _ => bscale * px.to_f64().unwrap() + bzero,
+  })
+}
+
+fn pixels_to_f64<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> Array<f64, IxDyn> {
+  Zip::from(data).par_map_collect(|px| px.to_f64().unwrap())
+}
+
+fn apply_pixels<T, F>(data: &Array<T, IxDyn>, f: F) -> Array<f64, IxDyn>
+where
+  T: ToPrimitive + Sync,
+  F: Fn(f64) -> f64 + Sync,
+{
+  Zip::from(data).par_map_collect(|px| f(px.to_f64().unwrap()))
+}
+
+fn cast_pixels<S, T>(data: &Array<S, IxDyn>) -> Option<Array<T, IxDyn>>
+where
+  S: ToPrimitive + Copy + Sync,
+  T: NumCast + Clone + Send,
+{
+  //All pixels have to survive the cast
+  let cast = Zip::from(data).par_map_collect(|&px| T::from(px));
+  match cast.iter().all(|px| px.is_some()) {
+    true => Some(cast.mapv(|px| px.unwrap())),
+    false => None,
+  }
+}
+
+fn blank_pixels<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>, blank: i64) -> Array<bool, IxDyn> {
+  Zip::from(data).par_map_collect(|px| px.to_i64() == Some(blank))
+}
+
+fn pixel_sum<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  data.par_iter().map(|px| px.to_f64().unwrap()).sum()
+}
+
+fn float_pixel_product<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  data.par_iter().map(|px| px.to_f64().unwrap()).product()
+}
+
+fn int_pixel_product<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  /*  Saturating the signed product depends on the order in which rayon
+      combines the partial products. The sign and the saturated magnitude are
+      therefore calculated separately: neither depends on the order.
+  */
+  let (negative, magnitude) = data
+    .par_iter()
+    .map(|px| px.to_i64().unwrap())
+    .map(|px| (px < 0, px.unsigned_abs()))
+    .reduce(|| (false, 1), |(neg1, mag1), (neg2, mag2)| (neg1 ^ neg2, mag1.saturating_mul(mag2)));
+
+  match (negative, magnitude) {
+    (_, 0) => 0.0,
+    (false, mag) => mag.min(i64::MAX as u64) as f64,
+    (true, mag) => -(mag.min(i64::MIN.unsigned_abs()) as f64),
+  }
+}
+
+fn min_pixel<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  data
+    .par_iter()
+    .map(|px| px.to_f64().unwrap())
+    .filter(|px| !px.is_nan())
+    .reduce_with(f64::min)
+    .unwrap_or(f64::NAN)
+}
+
+fn max_pixel<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  data
+    .par_iter()
+    .map(|px| px.to_f64().unwrap())
+    .filter(|px| !px.is_nan())
+    .reduce_with(f64::max)
+    .unwrap_or(f64::NAN)
+}
+
+fn defined_pixels<T: ToPrimitive + Sync>(
+  data: &Array<T, IxDyn>,
+) -> impl ParallelIterator<Item = f64> + '_ {
+  data.par_iter().map(|px| px.to_f64().unwrap()).filter(|px| !px.is_nan())
+}
+
+fn mean_pixel<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>) -> f64 {
+  let (sum, count) =
+    defined_pixels(data).map(|px| (px, 1usize)).reduce(|| (0.0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+  sum / count as f64
+}
+
+fn stddev_pixel<T: ToPrimitive + Sync>(data: &Array<T, IxDyn>, mean: f64) -> f64 {
+  let (sum, count) = defined_pixels(data)
+    .map(|px| ((px - mean) * (px - mean), 1usize))
+    .reduce(|| (0.0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+  (sum / count as f64).sqrt()
+}
+
+fn histogram<T: ToPrimitive + Sync>(
+  data: &Array<T, IxDyn>,
+  n_bins: usize,
+  min: f64,
+  max: f64,
+) -> Vec<usize> {
+  if n_bins == 0 {
+    return Vec::new();
+  }
+  let bin_of = |px: f64| match max > min {
+    //The largest pixel belongs in the last bin rather than its own bin
+    true => (((px - min) / (max - min) * n_bins as f64) as usize).min(n_bins - 1),
+    false => 0,
+  };
+  defined_pixels(data)
+    .fold(
+      || vec![0usize; n_bins],
+      |mut counts, px| {
+        counts[bin_of(px)] += 1;
+        counts
+      },
+    )
+    .reduce(
+      || vec![0usize; n_bins],
+      |mut a, b| {
+        a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+        a
+      },
+    )
 }
@@ -21,10 +21,17 @@
 pub mod ascii_table;
 pub(crate) mod ascii_tbl_parser;
 pub mod bin_table;
+pub(crate) mod bin_tbl_parser;
 pub mod column;
+mod csv;
+#[cfg(any(feature = "polars", feature = "arrow"))]
+mod dataframe;
 pub mod table_entry;
 
 //Re-exports for readability
-pub use ascii_table::AsciiTable;
+pub use ascii_table::{AsciiTable, ColumnStats};
 pub(crate) use ascii_tbl_parser::AsciiTblParser;
+pub use bin_table::{BinColumn, BinColumnData, BinElement, BinTable};
+pub(crate) use bin_tbl_parser::{BinFieldMeta, BinTblParser};
+pub use column::{Column, ColumnEntry};
 pub use table_entry::TableEntry;
@@ -18,28 +18,54 @@
 */
 
 use std::{
+  cmp::Ordering,
+  collections::HashSet,
   error::Error,
   fmt::{self, Display, Formatter},
 };
 
+use rayon::prelude::*;
+
 use crate::{
   extensions::ExtensionPrint,
   raw::{table_entry_format::TableEntryFormat, BlockSized},
-  tbl_err::IndexOutOfRangeErr,
   tbl_err::ShapeMisMatchErr,
+  tbl_err::{IndexOutOfRangeErr, RaggedTableErr},
+  tbl_err::{MissingColumnErr, TblDecodeErr},
+};
+
+use super::{
+  column::{AsciiCol, Column, ColumnEntry},
+  table_entry::RowKey,
+  AsciiTblParser, TableEntry,
 };
 
-use super::{column::AsciiCol, TableEntry};
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE; // = 2880B
 
 /*  Description:
     This is the abstracted user-facing api for tables. The
 */
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AsciiTable {
   cols: Vec<Box<dyn AsciiCol>>,
   block_size: Option<usize>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnStats {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Summary statistics of a numeric column. Undefined entries (NaN's) are
+      not included in the statistics, but are counted in n_nulls. The stddev
+      is the population standard deviation.
+  */
+  pub min: f64,
+  pub max: f64,
+  pub mean: f64,
+  pub stddev: f64,
+  pub n_values: usize,
+  pub n_nulls: usize,
+}
+
 impl BlockSized for AsciiTable {
   fn get_block_len(&self) -> usize {
     match self.block_size {
@@ -47,7 +73,8 @@ impl BlockSized for AsciiTable {
       None => {
         //We have to calculate the size of the table manually, as it is
         //not currently known (this is the case for user-created tables)
-        todo!()
+        let (_, row_len) = AsciiTblParser::calc_layout(&self.get_tbl_fmt());
+        (row_len * self.max_col_len()).div_ceil(BLOCK_SIZE)
       }
     }
   }
@@ -71,6 +98,31 @@ impl Display for AsciiTable {
   }
 }
 
+/*  Note:
+    Two tables are equal if they have the same shape and their entries are
+    equal. Floats that went through an ASCII representation usually do not
+    survive the round trip bit for bit, so they only have to agree up to a
+    relative tolerance (and NaN's, the undefined entries, are equal). Labels,
+    units and TNULL values are not compared.
+*/
+impl PartialEq for AsciiTable {
+  fn eq(&self, other: &Self) -> bool {
+    self.get_shape() == other.get_shape()
+      && self.cols.iter().zip(&other.cols).all(|(col, other_col)| {
+        col.len() == other_col.len()
+          && (0..col.len()).all(|row| match (col.get_entry(row), other_col.get_entry(row)) {
+            (Some(TableEntry::Float(a)), Some(TableEntry::Float(b))) => approx_eq(a, b),
+            (entry, other_entry) => entry == other_entry,
+          })
+      })
+  }
+}
+
+fn approx_eq(a: f64, b: f64) -> bool {
+  const REL_TOLERANCE: f64 = 1e-10;
+  a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= REL_TOLERANCE * a.abs().max(b.abs())
+}
+
 impl ExtensionPrint for AsciiTable {
   fn xprint(&self) -> String {
     format!(
@@ -90,6 +142,24 @@ impl AsciiTable {
       PUBLIC API
   */
 
+  pub fn new() -> Self {
+    //creates an empty table, which we can fill with add_column
+    AsciiTable { cols: Vec::new(), block_size: None }
+  }
+
+  pub fn add_column<T: ColumnEntry>(&mut self, col: Column<T>) -> &mut Self {
+    //Adds a column to the table. The size of the table is no longer known
+    T::add_to_table(col, self);
+    self.block_size = None;
+    self
+  }
+
+  pub fn with_column<T: ColumnEntry>(mut self, col: Column<T>) -> Self {
+    //Same as add_column, but for building a table in a single expression
+    self.add_column(col);
+    self
+  }
+
   pub fn get_entry(&self, col: usize, row: usize) -> Result<TableEntry, IndexOutOfRangeErr> {
     //returns an entry in the table, if it exists
 
@@ -111,6 +181,61 @@ impl AsciiTable {
     (self.cols.len(), self.max_col_len())
   }
 
+  pub fn n_cols(&self) -> usize {
+    self.cols.len()
+  }
+
+  pub fn n_rows(&self) -> usize {
+    //columns may have different lengths, the longest one counts
+    self.max_col_len()
+  }
+
+  pub fn row(&self, n: usize) -> Option<Vec<TableEntry>> {
+    //returns all entries in row n (columns that are too short are skipped)
+    match n < self.max_col_len() {
+      true => Some(self.get_row(n)),
+      false => None,
+    }
+  }
+
+  pub fn iter_rows(&self) -> impl Iterator<Item = Vec<TableEntry>> + '_ {
+    (0..self.max_col_len()).filter_map(|n| self.row(n))
+  }
+
+  pub fn get_column_by_name(&self, name: &str) -> Option<Vec<TableEntry>> {
+    //returns all entries of the column with the specified label
+    let column = self.cols.iter().find(|col| col.get_col_label() == Some(name))?;
+    Some((0..column.len()).filter_map(|row| column.get_entry(row)).collect())
+  }
+
+  pub fn get_typed_column<T: ColumnEntry + Clone + 'static>(&self, name: &str) -> Option<Vec<T>> {
+    //returns the entries of the column with the specified label, if they
+    //are of type T (see column())
+    Some(self.column::<T>(self.column_index(name)?)?.as_slice().to_vec())
+  }
+
+  pub fn get_string_column(&self, name: &str) -> Option<Vec<String>> {
+    self.get_typed_column(name)
+  }
+
+  pub fn get_float_column(&self, name: &str) -> Option<Vec<f64>> {
+    self.get_typed_column(name)
+  }
+
+  pub fn get_int_column(&self, name: &str) -> Option<Vec<i64>> {
+    self.get_typed_column(name)
+  }
+
+  pub fn column<T: ColumnEntry + 'static>(&self, col: usize) -> Option<&Column<T>> {
+    //returns the column with entries of type T (None if the type differs)
+    self.cols.get(col)?.as_any().downcast_ref()
+  }
+
+  pub fn iter_column(&self, col: usize) -> Option<Box<dyn Iterator<Item = TableEntry> + '_>> {
+    //iterates over the entries of a column, whatever their type
+    Some(self.cols.get(col)?.as_typed_iter())
+  }
+
   pub fn get_fmtd_column(&self, col: usize) -> Option<Vec<String>> {
     match self.cols.get(col) {
       None => None,
@@ -118,27 +243,151 @@ impl AsciiTable {
     }
   }
 
-  /*
-      INTERNAL FUNCS
-  */
+  pub fn column_label(&self, col: usize) -> Option<&str> {
+    //returns the label (TTYPE) of a column, if it has one
+    self.cols.get(col).and_then(|column| column.get_col_label())
+  }
 
-  pub(crate) fn get_col_fmt(&self, col: usize) -> Option<TableEntryFormat> {
+  pub fn column_unit(&self, col: usize) -> Option<&str> {
+    //returns the physical unit of a column, if it has one
     match self.cols.get(col) {
       None => None,
-      Some(column) => Some(column.get_col_fmt()),
+      Some(column) => column.get_col_unit(),
     }
   }
 
-  pub(crate) fn get_tbl_fmt(&self) -> Vec<TableEntryFormat> {
-    self.cols.iter().map(|col| col.get_col_fmt()).collect()
+  pub fn column_null(&self, col: usize) -> Option<&str> {
+    //returns the string marking undefined entries of a column, if any
+    self.cols.get(col).and_then(|column| column.get_col_null())
   }
 
-  pub(crate) fn new_sized(cols: Vec<Box<dyn AsciiCol>>, size: usize) -> Self {
-    //creates new table with known blocksize
-    AsciiTable { cols: cols, block_size: Some(size) }
+  pub fn is_sorted_asc(&self, col: usize) -> Option<bool> {
+    //checks if a column is sorted in ascending order (strings are compared
+    //lexicographically). Returns None if the column does not exist. O(n)
+    self.cols.get(col).map(|column| column.is_sorted_asc())
+  }
+
+  pub fn is_sorted_desc(&self, col: usize) -> Option<bool> {
+    //same as is_sorted_asc, but for descending order
+    self.cols.get(col).map(|column| column.is_sorted_desc())
+  }
+
+  pub fn is_sorted_by<F>(&self, col: usize, cmp: F) -> Option<bool>
+  where
+    F: Fn(&TableEntry, &TableEntry) -> Ordering,
+  {
+    //checks if a column is sorted according to a custom comparator. O(n)
+    self.cols.get(col).map(|column| column.is_sorted_by(&cmp))
+  }
+
+  pub fn column_stats(&self, col: usize) -> Result<ColumnStats, TblDecodeErr> {
+    //(1) Get the values in the column (this fails for string columns)
+    let column = match self.cols.get(col) {
+      Some(column) => column,
+      None => return Err(IndexOutOfRangeErr::new((col, 0), self).into()),
+    };
+    let mut values = column.to_f64_vec()?;
+
+    //(2) Filter out undefined values
+    let n_total = values.len();
+    values.retain(|val| !val.is_nan());
+    let n_values = values.len();
+
+    //(3) Calculate the statistics (variance in parallel w/ rayon)
+    let min = values.iter().cloned().fold(f64::NAN, f64::min);
+    let max = values.iter().cloned().fold(f64::NAN, f64::max);
+    let mean = values.iter().sum::<f64>() / n_values as f64;
+    let variance =
+      values.par_iter().map(|val| (val - mean) * (val - mean)).sum::<f64>() / n_values as f64;
+
+    Ok(ColumnStats {
+      min,
+      max,
+      mean,
+      stddev: variance.sqrt(),
+      n_values,
+      n_nulls: n_total - n_values,
+    })
+  }
+
+  pub fn column_stats_named(&self, name: &str) -> Result<ColumnStats, TblDecodeErr> {
+    //Same as column_stats, but selects the column by its label
+    match self.column_index(name) {
+      Some(index) => self.column_stats(index),
+      None => Err(MissingColumnErr::new(name).into()),
+    }
+  }
+
+  pub fn row_equals(&self, row_a: usize, row_b: usize) -> bool {
+    //compares all cells of two rows (rows that don't exist are never equal)
+    let len = self.max_col_len();
+    row_a < len && row_b < len && self.get_row(row_a) == self.get_row(row_b)
+  }
+
+  pub fn has_duplicates(&self) -> bool {
+    //stops at the first duplicate row, which is faster than a full dedup
+    let mut seen = HashSet::new();
+    (0..self.max_col_len()).any(|row| !seen.insert(RowKey(self.get_row(row))))
+  }
+
+  pub fn dedup(&mut self) {
+    //Removes all duplicate rows, keeping the first occurrence of each row
+
+    //(1) Find the indices of the duplicates
+    let mut seen = HashSet::new();
+    let duplicates: Vec<usize> =
+      (0..self.max_col_len()).filter(|&row| !seen.insert(RowKey(self.get_row(row)))).collect();
+
+    //(2) Remove them back-to-front, so the other indices stay valid
+    for &row in duplicates.iter().rev() {
+      self.cols.iter_mut().for_each(|col| {
+        col.remove_entry(row);
+      });
+    }
+  }
+
+  pub fn sort_by_column(&mut self, col_idx: usize) -> Result<(), Box<dyn Error>> {
+    //Sorts all rows (stable) by their entry in the specified column. Tables
+    //with columns of different lengths cannot be sorted: the missing entries
+    //of the shorter columns would end up in the wrong rows
+
+    //(1) Check if the column index is valid -> if yes, get the column
+    if col_idx >= self.cols.len() {
+      return Err(Box::new(IndexOutOfRangeErr::new((col_idx, 0), self)));
+    }
+    let shortest = self.cols.iter().map(|col| col.len()).min().unwrap_or(0);
+    if shortest != self.max_col_len() {
+      return Err(Box::new(RaggedTableErr::new(shortest, self.max_col_len())));
+    }
+    let column = self.cols[col_idx].as_ref();
+
+    //(2) Sort the row indices rather than the rows themselves
+    let mut order: Vec<usize> = (0..self.max_col_len()).collect();
+    order.sort_by(|&a, &b| match (column.get_entry(a), column.get_entry(b)) {
+      (Some(a), Some(b)) => a.total_cmp(&b),
+      _ => Ordering::Equal,
+    });
+
+    //(3) Reorder all columns simultaneously
+    self.cols.iter_mut().for_each(|col| col.select_rows(&order));
+    Ok(())
   }
 
-  pub(crate) fn add_row(&mut self, row: Vec<TableEntry>) -> Result<(), Box<dyn Error>> {
+  pub fn filter_rows<F>(&self, predicate: F) -> AsciiTable
+  where
+    F: Fn(&[TableEntry]) -> bool,
+  {
+    //Returns a new table containing only the rows that match the predicate.
+    //The kept rows stay in order, so shorter columns keep their alignment
+    let keep: Vec<usize> =
+      (0..self.max_col_len()).filter(|&row| predicate(&self.get_row(row))).collect();
+
+    let mut cols = self.cols.clone();
+    cols.iter_mut().for_each(|col| col.select_rows(&keep));
+    AsciiTable { cols, block_size: None }
+  }
+
+  pub fn add_row(&mut self, row: Vec<TableEntry>) -> Result<(), Box<dyn Error>> {
     //Adds row to table
     if row.len() != self.cols.len() {
       return Err(Box::new(ShapeMisMatchErr::new(&row, &self)));
@@ -153,11 +402,45 @@ impl AsciiTable {
     Ok(())
   }
 
+  /*
+      INTERNAL FUNCS
+  */
+
+  fn get_row(&self, row: usize) -> Vec<TableEntry> {
+    self.cols.iter().filter_map(|col| col.get_entry(row)).collect()
+  }
+
+  fn column_index(&self, name: &str) -> Option<usize> {
+    //index of the (first) column with the specified label
+    self.cols.iter().position(|col| col.get_col_label() == Some(name))
+  }
+
+  pub(crate) fn get_col_fmt(&self, col: usize) -> Option<TableEntryFormat> {
+    self.cols.get(col).map(|column| column.get_col_fmt())
+  }
+
+  pub(crate) fn get_tbl_fmt(&self) -> Vec<TableEntryFormat> {
+    self.cols.iter().map(|col| col.get_col_fmt()).collect()
+  }
+
+  pub(crate) fn push_col(&mut self, col: Box<dyn AsciiCol>) {
+    self.cols.push(col);
+  }
+
+  pub(crate) fn new_sized(cols: Vec<Box<dyn AsciiCol>>, size: usize) -> Self {
+    //creates new table with known blocksize
+    AsciiTable { cols, block_size: Some(size) }
+  }
+
   pub(crate) fn destroy(self) -> Vec<Vec<String>> {
     //destructs table into columns of strings
     self.cols.into_iter().map(|val| val.to_ascii_vec()).collect()
   }
 
+  pub(crate) fn column_len(&self, col: usize) -> usize {
+    self.cols.get(col).map_or(0, |col| col.len())
+  }
+
   pub(crate) fn max_col_len(&self) -> usize {
     //returns size of longest column in table
     self.cols.iter().fold(0, |max_len, col| max_len.max(col.len()))
@@ -29,6 +29,7 @@ use std::{
 use crate::{
   extensions::{table::column::AsciiCol, Extension},
   raw::{
+    keyword_utils::quote_fits_string,
     raw_io::{RawFitsReader, RawFitsWriter},
     table_entry_format::TableEntryFormat,
   },
@@ -41,6 +42,7 @@ use rayon::prelude::*;
 
 pub struct AsciiTblParser {}
 impl AsciiTblParser {
+  #[allow(clippy::too_many_arguments)]
   pub(crate) fn decode_tbl(
     reader: &mut RawFitsReader,
     chars_in_row: usize,               //#ASCII characters in a (raw) row
@@ -49,6 +51,9 @@ impl AsciiTblParser {
     row_index_col_start: Vec<usize>,   //row index where each column starts
     field_format: Vec<String>,         //data format (incl length) of each field
     field_labels: Option<Vec<String>>, //field labels
+    field_units: Vec<Option<String>>,  //physical units of the fields
+    field_scales: Vec<(f64, f64)>,     //(TSCAL, TZERO) of the fields
+    field_nulls: Vec<Option<String>>,  //undefined value of the fields
   ) -> Result<Extension, Box<dyn Error>> {
     /*  (1)
         Tables are usually pretty small compared to images. Hence it's
@@ -80,7 +85,8 @@ impl AsciiTblParser {
     let field_lengs: Vec<usize> = fmts.iter().map(|fmt| fmt.get_field_width()).collect();
 
     //(2b) Turn the formats into a typed table
-    let mut tbl = Self::setup_table(&fmts, field_labels, num_blocks)?;
+    let mut tbl =
+      Self::setup_table(&fmts, field_labels, field_units, &field_scales, &field_nulls, num_blocks)?;
 
     /*  (3)
         We may now divide the total raw file into row-sized chunks and process
@@ -117,7 +123,9 @@ impl AsciiTblParser {
         field_vec
           .into_iter()
           .enumerate()
-          .map(|(i, st)| TableEntry::from_parts(st, &fmts[i]))
+          .map(|(i, st)| {
+            Self::decode_field(st, &fmts[i], field_scales[i], field_nulls[i].as_deref())
+          })
           .collect::<Result<Vec<TableEntry>, ParseError>>()
       })
       .collect::<Vec<Result<Vec<TableEntry>, ParseError>>>();
@@ -135,35 +143,79 @@ impl AsciiTblParser {
     Ok(Extension::AsciiTable(tbl))
   }
 
+  fn decode_field(
+    raw_field: &str,
+    format: &TableEntryFormat,
+    (tscal, tzero): (f64, f64),
+    null: Option<&str>,
+  ) -> Result<TableEntry, ParseError> {
+    /*
+        Fields equal to the TNULL string are undefined. These become NaN's in
+        float columns and TableEntry::NULL_INT in integer columns. Numbers are
+        converted to physical values using physical = TSCAL * stored + TZERO,
+        which turns integer columns into float columns.
+    */
+    use TableEntryFormat::*;
+    let scaled = tscal != 1.0 || tzero != 0.0;
+    let is_null = null.is_some_and(|null| raw_field.trim() == null.trim());
+
+    Ok(match (is_null, TableEntry::from_parts(raw_field, format)) {
+      (true, _) if matches!(format, Int(_) | Bit(_)) && !scaled => {
+        TableEntry::Int(TableEntry::NULL_INT)
+      }
+      (true, _) if matches!(format, Int(_) | Bit(_) | Float(_) | Double(_) | General(_)) => {
+        TableEntry::Float(f64::NAN)
+      }
+      (_, Ok(TableEntry::Int(num))) if scaled => TableEntry::Float(tscal * num as f64 + tzero),
+      (_, Ok(TableEntry::Float(num))) if scaled => TableEntry::Float(tscal * num + tzero),
+      (_, entry) => entry?,
+    })
+  }
+
   fn setup_table(
     fmts: &Vec<TableEntryFormat>,
     labels: Option<Vec<String>>,
+    units: Vec<Option<String>>,
+    scales: &[(f64, f64)],
+    nulls: &[Option<String>],
     size: usize,
   ) -> Result<AsciiTable, InvalidFFCode> {
-    //(1) Use the column formats to set-up typed columns
+    //(1) Use the column formats to set-up typed columns. Scaled integer
+    //    columns contain floats
     let mut cols = Vec::<Box<dyn AsciiCol>>::new();
     for i in 0..fmts.len() {
+      let scaled = scales[i] != (1.0, 0.0);
       match &fmts[i] {
         TableEntryFormat::Char(_) => {
           let label = match &labels {
             None => None,
             Some(vec) => Some(vec[i].clone()),
           };
-          cols.push(Box::new(Column::<String>::new(label)));
+          cols.push(Box::new(Column::<String>::new(label, units[i].clone())));
         }
-        TableEntryFormat::Int(_) => {
+        TableEntryFormat::Int(_) | TableEntryFormat::Bit(_) if !scaled => {
           let label = match &labels {
             None => None,
             Some(vec) => Some(vec[i].clone()),
           };
-          cols.push(Box::new(Column::<i64>::new(label)));
+          let col = Column::<i64>::new(label, units[i].clone()).with_null(nulls[i].clone());
+          cols.push(Box::new(col));
         }
-        TableEntryFormat::Float(_) => {
+        TableEntryFormat::Int(_)
+        | TableEntryFormat::Bit(_)
+        | TableEntryFormat::Float(_)
+        | TableEntryFormat::Double(_)
+        | TableEntryFormat::General(_) => {
           let label = match &labels {
             None => None,
             Some(vec) => Some(vec[i].clone()),
           };
-          cols.push(Box::new(Column::<f64>::new(label)));
+          let col = Column::<f64>::new(label, units[i].clone()).with_null(nulls[i].clone());
+          cols.push(Box::new(col));
+        }
+        TableEntryFormat::Logical(_) => {
+          let label = labels.as_ref().map(|vec| vec[i].clone());
+          cols.push(Box::new(Column::<bool>::new(label, units[i].clone())));
         }
         TableEntryFormat::Invalid(invld) => {
           return Err(InvalidFFCode::new(invld.clone()));
@@ -213,24 +265,120 @@ impl AsciiTblParser {
     */
     let tbl_fmts = (&tbl).get_tbl_fmt(); //IN ORDER!
     let tbl_len = (&tbl).max_col_len();
+    let padding: Vec<String> =
+      (0..tbl_fmts.len()).map(|i| Self::padding(&tbl, i).unwrap_or_default()).collect();
     let mut cols: Vec<Vec<String>> = tbl.destroy(); //IN ORDER
 
     /*  (2)
         All columns in a FITS table should have the same length. Therefore,
-        we need to add empty entries to each column that is smaller than the
-        longest column.
+        we need to add entries to each column that is smaller than the
+        longest column. These entries are undefined (see padding()).
     */
-    cols.iter_mut().for_each(|col| {
+    cols.iter_mut().zip(&padding).for_each(|(col, pad)| {
       while col.len() < tbl_len {
-        col.push(String::from(""));
+        col.push(pad.clone());
       }
     });
 
     /*  (3)
         Furthermore, all rows in a single column must take up the same width
-        in ascii characters. This means that we must extend the co
+        in ascii characters. This means that we must extend the entries that
+        are shorter than the column width with spaces. Numbers are right-
+        justified, strings are left-justified. Columns are separated by a
+        single space.
+    */
+    let (col_start, row_len) = Self::calc_layout(&tbl_fmts);
+
+    /*  (4)
+        Now we can assemble the rows. Each row is a fixed-width line of ascii
+        text (without any newlines), which is initially filled with spaces.
+    */
+    let mut whole_table = vec![b' '; row_len * tbl_len];
+    whole_table.par_chunks_exact_mut(row_len.max(1)).enumerate().for_each(|(row, raw)| {
+      for (i, col) in cols.iter().enumerate() {
+        let width = tbl_fmts[i].get_field_width();
+        let field = match tbl_fmts[i] {
+          TableEntryFormat::Char(_) => format!("{:<width$}", col[row]),
+          _ => format!("{:>width$}", col[row]),
+        };
+        //Fields are never wider than their column (the width is the max)
+        raw[col_start[i]..(col_start[i] + width)].copy_from_slice(&field.as_bytes()[..width]);
+      }
+    });
+
+    /*  (5)
+        The data unit has to be an integer multiple of the block size. The
+        standard states that ASCII tables are padded with spaces rather than
+        zeroes.
+    */
+    while whole_table.len() % BLOCK_SIZE != 0 {
+      whole_table.push(b' ');
+    }
+
+    //(R) write the table
+    writer.write_blocks(&whole_table)?;
+    Ok(())
+  }
+
+  pub(crate) fn get_tbl_keywords(tbl: &AsciiTable) -> Result<Vec<(String, String)>, InvalidFFCode> {
+    /*
+        Returns the (keyword, value) pairs describing the layout of the table
+        as it will be written by encode_tbl. These have to be set in the header
+        of the HDU containing the table before it is encoded.
     */
+    let tbl_fmts = tbl.get_tbl_fmt();
+    let (col_start, row_len) = Self::calc_layout(&tbl_fmts);
+
+    let mut keywords = vec![
+      (String::from("NAXIS1"), row_len.to_string()),
+      (String::from("NAXIS2"), tbl.max_col_len().to_string()),
+      (String::from("TFIELDS"), tbl_fmts.len().to_string()),
+    ];
+    for (i, fmt) in tbl_fmts.iter().enumerate() {
+      //FITS indices start at 1 rather than 0
+      keywords.push((format!("TBCOL{}", i + 1), (col_start[i] + 1).to_string()));
+      keywords.push((format!("TFORM{}", i + 1), quote_fits_string(&fmt.to_fortran_format_code()?)));
+      if let Some(unit) = tbl.column_unit(i) {
+        keywords.push((format!("TUNIT{}", i + 1), quote_fits_string(unit)));
+      }
+      if let Some(null) = tbl.column_null(i).map(String::from).or(Self::padding(tbl, i)) {
+        keywords.push((format!("TNULL{}", i + 1), quote_fits_string(&null)));
+      }
+    }
+
+    Ok(keywords)
+  }
 
-    todo!()
+  fn padding(tbl: &AsciiTable, col: usize) -> Option<String> {
+    /*
+        Columns that are shorter than the table are padded with undefined
+        entries: the TNULL value of the column, or blanks for numeric columns
+        without one (which then get a blank TNULL value). Text columns are
+        padded with blanks, which are read back as empty strings.
+    */
+    use TableEntryFormat::*;
+    match (tbl.column_null(col), tbl.get_col_fmt(col)?) {
+      (Some(null), _) => Some(null.to_string()),
+      (None, Int(_) | Bit(_) | Float(_) | Double(_) | General(_))
+        if tbl.column_len(col) < tbl.max_col_len() =>
+      {
+        Some(String::new())
+      }
+      _ => None,
+    }
+  }
+
+  pub(crate) fn calc_layout(tbl_fmts: &[TableEntryFormat]) -> (Vec<usize>, usize) {
+    //Returns the (0-based) starting index of each column and the row length
+    let mut col_start = Vec::new();
+    let mut row_len = 0;
+    for fmt in tbl_fmts {
+      if row_len != 0 {
+        row_len += 1; //column separator
+      }
+      col_start.push(row_len);
+      row_len += fmt.get_field_width();
+    }
+    (col_start, row_len)
   }
 }
@@ -16,3 +16,316 @@
     You should have received a copy of the GNU General Public License
     along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
 */
+
+use std::fmt::{self, Display, Formatter};
+
+use ndarray::{Array, IxDyn, ShapeBuilder};
+
+use crate::{extensions::ExtensionPrint, raw::BlockSized};
+
+/*  Description:
+    This is the user-facing api for binary tables. Contrary to ASCII tables,
+    the fields of a binary table may contain more than one value (the repeat
+    count of the field). The values of all rows are stored in one flat vector
+    per column, so row i of a column with repeat count r is stored in entries
+    i*r..(i+1)*r of the vector.
+*/
+#[derive(Debug, Clone)]
+pub struct BinTable {
+  cols: Vec<BinColumn>,
+  n_rows: usize,
+  block_size: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BinColumn {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      A single typed column of a binary table. Columns may carry a label
+      (TTYPE), a physical unit (TUNIT) and the shape of the array in each
+      field (TDIM).
+  */
+  label: Option<String>,
+  unit: Option<String>,
+  repeat: usize,
+  dim: Option<Vec<usize>>,
+  data: BinColumnData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinColumnData {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Typed contents of a binary table column. Integer columns that are
+      scaled with the TSCAL/TZERO keywords are converted to physical values,
+      which are stored as Double. Null logicals are stored as None. Text
+      fields contain one string per row (with the padding removed) and bit
+      fields contain the packed bytes. Variable-length array columns contain
+      the array of each row, which are read from the heap of the table.
+  */
+  Logical(Vec<Option<bool>>),
+  Bit(Vec<u8>),
+  Byte(Vec<u8>),
+  Short(Vec<i16>),
+  Int(Vec<i32>),
+  Long(Vec<i64>),
+  Text(Vec<String>),
+  Float(Vec<f32>),
+  Double(Vec<f64>),
+  Complex(Vec<(f32, f32)>),
+  DoubleComplex(Vec<(f64, f64)>),
+  VarLen(Vec<BinColumnData>),
+}
+
+impl BinColumnData {
+  fn dtype_print(&self) -> &'static str {
+    use BinColumnData::*;
+    match self {
+      Logical(_) => "logical",
+      Bit(_) => "bit",
+      Byte(_) => "u8",
+      Short(_) => "i16",
+      Int(_) => "i32",
+      Long(_) => "i64",
+      Text(_) => "string",
+      Float(_) => "f32",
+      Double(_) => "f64",
+      Complex(_) => "c32",
+      DoubleComplex(_) => "c64",
+      VarLen(_) => "variable-length array",
+    }
+  }
+}
+
+impl BinColumn {
+  /*
+      PUBLIC API
+  */
+  pub fn get_label(&self) -> Option<&str> {
+    self.label.as_deref()
+  }
+  pub fn get_unit(&self) -> Option<&str> {
+    self.unit.as_deref()
+  }
+  pub fn get_repeat(&self) -> usize {
+    self.repeat
+  }
+  pub fn get_dim(&self) -> Option<&Vec<usize>> {
+    self.dim.as_ref()
+  }
+  pub fn get_data(&self) -> &BinColumnData {
+    &self.data
+  }
+
+  pub fn get_cell_shape(&self) -> Vec<usize> {
+    //shape of the array in each field: TDIM if present, the repeat count if not.
+    //The arrays in variable-length array columns do not have a fixed shape
+    self.dim.clone().unwrap_or_else(|| vec![self.repeat])
+  }
+
+  pub fn get_cell<T: BinElement>(&self, row: usize) -> Option<Array<T, IxDyn>> {
+    /*
+        Returns the field of row as an array with the shape of the cells, or
+        as a one-dimensional array for variable-length arrays. The array uses the Fortran (column-major) memory layout, just like images.
+        The TDIM shape may be smaller than the repeat count, in which case the
+        remaining values of the field are ignored.
+    */
+    if let BinColumnData::VarLen(rows) = &self.data {
+      return Some(Array::from(T::values(rows.get(row)?)?.to_vec()).into_dyn());
+    }
+
+    let shape = self.get_cell_shape();
+    let len = shape.iter().product::<usize>();
+    if len > self.repeat {
+      return None;
+    }
+    let start = row * self.repeat;
+    let values = T::values(&self.data)?.get(start..start + len)?;
+    Array::from_shape_vec(shape.f(), values.to_vec()).ok()
+  }
+
+  /*
+      INTERNAL CODE
+  */
+  pub(crate) fn new(
+    label: Option<String>,
+    unit: Option<String>,
+    repeat: usize,
+    dim: Option<Vec<usize>>,
+    data: BinColumnData,
+  ) -> Self {
+    BinColumn { label, unit, repeat, dim, data }
+  }
+
+  fn pretty_print(&self) -> String {
+    format!(
+      "label: {}, dtype: {}, repeat: {}",
+      self.label.as_deref().unwrap_or("(no label)"),
+      self.data.dtype_print(),
+      self.repeat
+    )
+  }
+}
+
+impl BlockSized for BinTable {
+  fn get_block_len(&self) -> usize {
+    self.block_size
+  }
+}
+
+impl Display for BinTable {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      ">=============================<|FITS Binary Table|>============================="
+    )?;
+    writeln!(f, ">Table Layout:")?;
+    for (index, col) in self.cols.iter().enumerate() {
+      writeln!(f, ">  col#{index:03} - {}", col.pretty_print())?
+    }
+    writeln!(
+      f,
+      ">==============================================================================="
+    )?;
+    Ok(())
+  }
+}
+
+impl ExtensionPrint for BinTable {
+  fn xprint(&self) -> String {
+    format!(
+      "(BINTABLE) - #columns: {}, #rows: {}, size: {}",
+      self.cols.len(),
+      self.n_rows,
+      self.get_block_len()
+    )
+  }
+}
+
+impl BinTable {
+  /*
+      PUBLIC API
+  */
+  pub fn get_shape(&self) -> (usize, usize) {
+    //returns shape (columns, rows) of table
+    (self.cols.len(), self.n_rows)
+  }
+
+  pub fn get_column(&self, col: usize) -> Option<&BinColumn> {
+    self.cols.get(col)
+  }
+
+  pub fn get_column_by_name(&self, name: &str) -> Option<&BinColumn> {
+    self.cols.iter().find(|col| col.get_label() == Some(name))
+  }
+
+  /*
+      INTERNAL FUNCS
+  */
+  pub(crate) fn new_sized(cols: Vec<BinColumn>, n_rows: usize, size: usize) -> Self {
+    BinTable { cols, n_rows, block_size: size }
+  }
+}
+
+/*
+    Types of the values in binary table fields that can be read as arrays. This
+    trait is sealed: only the types that have a BinColumnData variant implement
+    it. Text and bit fields cannot be read as arrays.
+*/
+pub trait BinElement: sealed::InBinColumn {}
+impl BinElement for Option<bool> {}
+impl BinElement for u8 {}
+impl BinElement for i16 {}
+impl BinElement for i32 {}
+impl BinElement for i64 {}
+impl BinElement for f32 {}
+impl BinElement for f64 {}
+impl BinElement for (f32, f32) {}
+impl BinElement for (f64, f64) {}
+
+mod sealed {
+  use super::BinColumnData::{self, *};
+
+  pub trait InBinColumn: Clone + Sized {
+    fn values(data: &BinColumnData) -> Option<&[Self]>;
+  }
+
+  impl InBinColumn for Option<bool> {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Logical(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for u8 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Byte(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for i16 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Short(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for i32 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Int(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for i64 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Long(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for f32 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Float(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for f64 {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Double(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for (f32, f32) {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        Complex(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+
+  impl InBinColumn for (f64, f64) {
+    fn values(data: &BinColumnData) -> Option<&[Self]> {
+      match data {
+        DoubleComplex(values) => Some(values),
+        _ => None,
+      }
+    }
+  }
+}
@@ -15,4 +15,231 @@
 
     You should have received a copy of the GNU General Public License
     along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
-*/
\ No newline at end of file
+*/
+
+//Get block size from root
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE; // = 2880B
+
+use std::error::Error;
+
+use rayon::prelude::*;
+
+use crate::{
+  extensions::Extension,
+  raw::{
+    bin_entry_format::{BinEntryFormat, BinEntryType, Descriptor},
+    raw_io::RawFitsReader,
+  },
+  tbl_fmt_err::{FieldSizeMisMatch, HeapOutOfRange},
+};
+
+use super::{
+  bin_table::{BinColumn, BinColumnData},
+  BinTable,
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct BinFieldMeta {
+  //Everything the header tells us about a single field of a binary table
+  pub(crate) tform: String,
+  pub(crate) label: Option<String>,
+  pub(crate) unit: Option<String>,
+  pub(crate) scale: Option<(f64, f64)>, //(TSCAL, TZERO)
+  pub(crate) dim: Option<Vec<usize>>,
+}
+
+pub struct BinTblParser {}
+impl BinTblParser {
+  pub(crate) fn decode_tbl(
+    reader: &mut RawFitsReader,
+    row_len: usize,            //#bytes in a row
+    n_rows: usize,             //#rows in the table
+    heap_size: usize,          //#bytes following the table (PCOUNT)
+    heap_start: usize,         //offset of the heap in the data unit (THEAP)
+    fields: Vec<BinFieldMeta>, //descriptions of the fields in each row
+  ) -> Result<Extension, Box<dyn Error>> {
+    /*  (1)
+        Just like ASCII tables, binary tables are usually small enough to
+        read in one go. The data unit also contains the heap, which holds
+        the contents of variable-length arrays.
+    */
+    let byte_size = row_len * n_rows + heap_size;
+    let num_blocks = byte_size.div_ceil(BLOCK_SIZE);
+    let mut whole_table = vec![0u8; num_blocks * BLOCK_SIZE];
+    reader.read_blocks(&mut whole_table)?;
+
+    //(2) Decode the formats of the fields, and check that they fill a row
+    let fmts = fields
+      .iter()
+      .map(|field| BinEntryFormat::from_tform(&field.tform))
+      .collect::<Result<Vec<BinEntryFormat>, Box<dyn Error>>>()?;
+
+    let width_sum: usize = fmts.iter().map(|fmt| fmt.get_field_width()).sum();
+    if width_sum != row_len {
+      Err(FieldSizeMisMatch::from_sizes(width_sum, row_len))?
+    }
+
+    /*  (3)
+        Fields are stored back-to-back in each row, so the byte offset of each
+        field within a row is the sum of the widths of the preceding fields.
+        Each column can then be decoded independently, which we do in parallel.
+    */
+    let offsets: Vec<usize> = fmts
+      .iter()
+      .scan(0, |offset, fmt| {
+        let start = *offset;
+        *offset += fmt.get_field_width();
+        Some(start)
+      })
+      .collect();
+
+    let table = &whole_table[..row_len * n_rows];
+    let heap = &whole_table[heap_start.min(byte_size)..byte_size];
+    let cols = fields
+      .into_par_iter()
+      .enumerate()
+      .map(|(i, meta)| {
+        let width = fmts[i].get_field_width();
+        let raw_fields: Vec<&[u8]> = (0..n_rows)
+          .map(|row| &table[(row * row_len + offsets[i])..(row * row_len + offsets[i] + width)])
+          .collect();
+        let data = match fmts[i].descriptor {
+          None => Self::decode_column(&raw_fields, &fmts[i], meta.scale),
+          Some(descriptor) => {
+            Self::decode_var_len(&raw_fields, &fmts[i], descriptor, heap, meta.scale)?
+          }
+        };
+        Ok(BinColumn::new(meta.label, meta.unit, fmts[i].repeat, meta.dim, data))
+      })
+      .collect::<Result<Vec<BinColumn>, HeapOutOfRange>>()?;
+
+    //(R) return the filled table
+    Ok(Extension::BinTable(BinTable::new_sized(cols, n_rows, num_blocks)))
+  }
+
+  fn decode_var_len(
+    raw_fields: &[&[u8]],
+    fmt: &BinEntryFormat,
+    descriptor: Descriptor,
+    heap: &[u8],
+    scale: Option<(f64, f64)>,
+  ) -> Result<BinColumnData, HeapOutOfRange> {
+    /*
+        Each field contains the length (in elements) and the offset (in bytes,
+        from the start of the heap) of an array in the heap. Fields with a
+        repeat count of zero do not contain an array at all.
+    */
+    let arrays = raw_fields
+      .iter()
+      .enumerate()
+      .map(|(row, raw)| {
+        let (len, offset) = match (descriptor, raw.is_empty()) {
+          (_, true) => (0, 0),
+          (Descriptor::P, false) => (
+            u32::from_be_bytes(raw[0..4].try_into().unwrap()) as usize,
+            u32::from_be_bytes(raw[4..8].try_into().unwrap()) as usize,
+          ),
+          (Descriptor::Q, false) => (
+            u64::from_be_bytes(raw[0..8].try_into().unwrap()) as usize,
+            u64::from_be_bytes(raw[8..16].try_into().unwrap()) as usize,
+          ),
+        };
+
+        //The array is decoded like a field with a repeat count of len
+        let elements = BinEntryFormat { repeat: len, dtype: fmt.dtype, descriptor: None };
+        let bytes = offset
+          .checked_add(elements.get_field_width())
+          .and_then(|end| heap.get(offset..end))
+          .ok_or(HeapOutOfRange::new(row))?;
+        Ok(Self::decode_column(&[bytes], &elements, scale))
+      })
+      .collect::<Result<Vec<BinColumnData>, HeapOutOfRange>>()?;
+
+    Ok(BinColumnData::VarLen(arrays))
+  }
+
+  fn decode_column(
+    raw_fields: &[&[u8]],
+    fmt: &BinEntryFormat,
+    scale: Option<(f64, f64)>,
+  ) -> BinColumnData {
+    use BinColumnData as Data;
+    use BinEntryType::*;
+
+    let data = match fmt.dtype {
+      Logical => Data::Logical(
+        raw_fields
+          .iter()
+          .flat_map(|raw| raw.iter())
+          .map(|byte| match byte {
+            b'T' => Some(true),
+            b'F' => Some(false),
+            _ => None, //null value
+          })
+          .collect(),
+      ),
+      Bit => Data::Bit(raw_fields.concat()),
+      Byte => Data::Byte(raw_fields.concat()),
+      Short => Data::Short(Self::decode_be(raw_fields, i16::from_be_bytes)),
+      Int => Data::Int(Self::decode_be(raw_fields, i32::from_be_bytes)),
+      Long => Data::Long(Self::decode_be(raw_fields, i64::from_be_bytes)),
+      Char => Data::Text(
+        raw_fields
+          .iter()
+          .map(|raw| {
+            //Strings are padded with either NUL characters or spaces
+            String::from_utf8_lossy(raw).trim_end_matches(['\0', ' ']).to_string()
+          })
+          .collect(),
+      ),
+      Float => Data::Float(Self::decode_be(raw_fields, f32::from_be_bytes)),
+      Double => Data::Double(Self::decode_be(raw_fields, f64::from_be_bytes)),
+      Complex => {
+        let parts = Self::decode_be(raw_fields, f32::from_be_bytes);
+        Data::Complex(parts.chunks_exact(2).map(|c| (c[0], c[1])).collect())
+      }
+      DoubleComplex => {
+        let parts = Self::decode_be(raw_fields, f64::from_be_bytes);
+        Data::DoubleComplex(parts.chunks_exact(2).map(|c| (c[0], c[1])).collect())
+      }
+    };
+
+    //Numeric columns may be scaled to physical values: zero + scal * val
+    match scale {
+      None => data,
+      Some((scal, zero)) => {
+        let physical =
+          |vals: Vec<f64>| Data::Double(vals.iter().map(|v| zero + scal * v).collect());
+        match data {
+          Data::Byte(vals) => physical(vals.into_iter().map(f64::from).collect()),
+          Data::Short(vals) => physical(vals.into_iter().map(f64::from).collect()),
+          Data::Int(vals) => physical(vals.into_iter().map(f64::from).collect()),
+          Data::Long(vals) => physical(vals.into_iter().map(|v| v as f64).collect()),
+          Data::Float(vals) => physical(vals.into_iter().map(f64::from).collect()),
+          Data::Double(vals) => physical(vals),
+          other => other, //scaling does not apply to other types
+        }
+      }
+    }
+  }
+
+  fn decode_be<T, const N: usize>(raw_fields: &[&[u8]], decode: fn([u8; N]) -> T) -> Vec<T> {
+    //Decodes big-endian values of N bytes from all fields
+    raw_fields
+      .iter()
+      .flat_map(|raw| raw.chunks_exact(N))
+      .map(|bytes| decode(bytes.try_into().unwrap()))
+      .collect()
+  }
+
+  pub(crate) fn parse_tdim(tdim: &str) -> Option<Vec<usize>> {
+    //TDIM values look like (a,b,c). Invalid values are ignored
+    tdim
+      .trim()
+      .strip_prefix('(')?
+      .strip_suffix(')')?
+      .split(',')
+      .map(|axis| axis.trim().parse::<usize>().ok())
+      .collect()
+  }
+}
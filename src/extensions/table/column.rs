@@ -16,7 +16,7 @@
     You should have received a copy of the GNU General Public License
     along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
 */
-use std::fmt::Debug;
+use std::{any::Any, cmp::Ordering, fmt::Debug, slice};
 
 use dyn_clone::{clone_trait_object, DynClone};
 use rayon::prelude::*;
@@ -49,14 +49,36 @@ pub(crate) trait AsciiCol: Debug + DynClone {
   fn pop_entry(&mut self) -> Option<TableEntry>;
   fn set_entry(&mut self, entry: TableEntry, index: usize) -> Result<(), TblDecodeErr>;
   fn get_entry(&self, index: usize) -> Option<TableEntry>;
+  fn as_typed_iter(&self) -> Box<dyn Iterator<Item = TableEntry> + '_>;
   fn remove_entry(&mut self, index: usize) -> Option<TableEntry>;
 
+  //Keeps only the entries at the specified indices, in the specified order
+  fn select_rows(&mut self, rows: &[usize]);
+
   //Other funcs
   fn len(&self) -> usize;
   fn get_col_label(&self) -> Option<&str>;
+  fn get_col_unit(&self) -> Option<&str>;
+  fn get_col_null(&self) -> Option<&str>;
   fn get_col_fmt(&self) -> TableEntryFormat;
   fn pretty_print(&self) -> String;
 
+  //Numeric values of the column as floats (only for numeric columns)
+  fn to_f64_vec(&self) -> Result<Vec<f64>, TypeMisMatchErr>;
+
+  /*  Sorting predicates
+      These compare adjacent entries, so they run in O(n) time and stop at
+      the first pair of entries that is out of order. Empty and single-entry
+      columns are always sorted. Columns containing NaN's are never sorted.
+  */
+  fn is_sorted_asc(&self) -> bool;
+  fn is_sorted_desc(&self) -> bool;
+  fn is_sorted_by(&self, cmp: &dyn Fn(&TableEntry, &TableEntry) -> Ordering) -> bool {
+    //Default impl converts all entries to TableEntries, so it's slower
+    (1..self.len())
+      .all(|i| cmp(&self.get_entry(i - 1).unwrap(), &self.get_entry(i).unwrap()).is_le())
+  }
+
   /*  PRIVATE FUNCS
       These funcs are used for decoding and encoding columns. Not to be used
       by the end user
@@ -64,31 +86,116 @@ pub(crate) trait AsciiCol: Debug + DynClone {
 
   //Funcs for properly encoding/decoding
   fn to_ascii_vec(&self) -> Vec<String>;
+
+  //Used to get the typed column back from the trait object
+  fn as_any(&self) -> &dyn Any;
 }
 
 //This macro makes Col a clonable trait object
 clone_trait_object!(AsciiCol);
 
 #[derive(Debug, Clone)]
-pub(crate) struct Column<T> {
-  /*
-      Internal datacontainer for columns of a FITS table. All entries in a
+pub struct Column<T> {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Datacontainer for columns of a FITS table. All entries in a
       column have the same type. Instead of storing strings (like the FITS
       standard does), we will store actual primitive types and convert the
       Fortran-formatted strings when the table is opened.
 
-      Columns may be labeled as per the FITS standard.
+      Columns may be labeled as per the FITS standard, and may carry the
+      physical unit of their entries (TUNIT keyword) and the string that
+      marks undefined entries (TNULL keyword).
   */
   label: Option<String>,
+  unit: Option<String>,
+  null: Option<String>,
   container: Vec<T>,
 }
 
+/*
+    Entry types that are allowed in user-created columns. This trait is sealed:
+    only the types that have an AsciiCol implementation implement it.
+*/
+pub trait ColumnEntry: sealed::IntoAsciiCol {}
+impl ColumnEntry for String {}
+impl ColumnEntry for i64 {}
+impl ColumnEntry for f64 {}
+impl ColumnEntry for bool {}
+
+mod sealed {
+  use super::{super::AsciiTable, AsciiCol, Column};
+
+  pub trait IntoAsciiCol: Sized {
+    fn add_to_table(col: Column<Self>, tbl: &mut AsciiTable);
+  }
+
+  impl<T> IntoAsciiCol for T
+  where
+    Column<T>: AsciiCol + 'static,
+  {
+    fn add_to_table(col: Column<Self>, tbl: &mut AsciiTable) {
+      tbl.push_col(Box::new(col))
+    }
+  }
+}
+
 impl<T> Column<T> {
-  pub(crate) fn new(label: Option<String>) -> Self {
-    Column { label: label, container: Vec::new() }
+  pub fn new(label: Option<String>, unit: Option<String>) -> Self {
+    Column { label, unit, null: None, container: Vec::new() }
+  }
+
+  pub fn from_vec(label: Option<String>, unit: Option<String>, entries: Vec<T>) -> Self {
+    //Creates a column that already contains some entries
+    Column { label, unit, null: None, container: entries }
+  }
+
+  pub(crate) fn with_null(mut self, null: Option<String>) -> Self {
+    //Sets the string that is written for undefined entries
+    self.null = null;
+    self
+  }
+
+  pub fn push(&mut self, entry: T) {
+    self.container.push(entry)
+  }
+
+  pub fn get(&self, index: usize) -> Option<&T> {
+    //Same as AsciiTable::get_entry, but without copying the entry
+    self.container.get(index)
+  }
+
+  pub fn as_slice(&self) -> &[T] {
+    &self.container
+  }
+
+  pub fn iter(&self) -> slice::Iter<'_, T> {
+    self.container.iter()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a Column<T> {
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.container.iter()
   }
 }
 
+fn select_rows<T: Clone>(container: &mut Vec<T>, rows: &[usize]) {
+  //Rows that are missing from this column are skipped
+  *container = rows.iter().filter_map(|&row| container.get(row).cloned()).collect();
+}
+
+fn is_sorted<T: PartialOrd>(container: &[T], order: Ordering) -> bool {
+  //Adjacent entries should either be equal or compare as `order`
+  container.windows(2).all(|pair| match pair[0].partial_cmp(&pair[1]) {
+    Some(Ordering::Equal) => true,
+    Some(ord) => ord == order,
+    None => false,
+  })
+}
+
 impl AsciiCol for Column<String> {
   fn push_entry(&mut self, entry: TableEntry) -> Result<(), TypeMisMatchErr> {
     match entry {
@@ -107,7 +214,7 @@ impl AsciiCol for Column<String> {
   fn set_entry(&mut self, entry: TableEntry, index: usize) -> Result<(), TblDecodeErr> {
     match entry {
       TableEntry::Text(txt) => {
-        if self.container.len() >= index {
+        if index >= self.container.len() {
           Err(IndexOutOfRangeErr::from_idx((None, index), (None, self.container.len())).into())
         } else {
           self.container[index] = txt;
@@ -125,18 +232,30 @@ impl AsciiCol for Column<String> {
     }
   }
 
+  fn as_typed_iter(&self) -> Box<dyn Iterator<Item = TableEntry> + '_> {
+    Box::new(self.container.iter().cloned().map(TableEntry::Text))
+  }
+
   fn remove_entry(&mut self, index: usize) -> Option<TableEntry> {
-    if self.container.len() >= index {
+    if index >= self.container.len() {
       None
     } else {
       Some(TableEntry::Text(self.container.remove(index)))
     }
   }
 
+  fn select_rows(&mut self, rows: &[usize]) {
+    select_rows(&mut self.container, rows)
+  }
+
   fn len(&self) -> usize {
     self.container.len()
   }
 
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
   fn to_ascii_vec(&self) -> Vec<String> {
     self.container.par_iter().map(|primitive| primitive.to_string()).collect()
   }
@@ -148,9 +267,26 @@ impl AsciiCol for Column<String> {
     }
   }
 
+  fn get_col_unit(&self) -> Option<&str> {
+    self.unit.as_deref()
+  }
+
+  fn get_col_null(&self) -> Option<&str> {
+    self.null.as_deref()
+  }
+
+  fn is_sorted_asc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Less)
+  }
+
+  fn is_sorted_desc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Greater)
+  }
+
   fn get_col_fmt(&self) -> TableEntryFormat {
     //(1) Find the entry with the largest width, use it as return val
-    let width = self.container.iter().fold(0, |acc, entry| acc.max(entry.len()));
+    //(fields must be at least one character wide)
+    let width = self.container.iter().fold(1, |acc, entry| acc.max(entry.len()));
 
     //(R) return a Char tblfmt with specified width
     TableEntryFormat::Char(width)
@@ -165,6 +301,10 @@ impl AsciiCol for Column<String> {
       }
     )
   }
+
+  fn to_f64_vec(&self) -> Result<Vec<f64>, TypeMisMatchErr> {
+    Err(TypeMisMatchErr::new(TableEntry::float(), &TableEntry::txt()))
+  }
 }
 
 impl AsciiCol for Column<i64> {
@@ -185,7 +325,7 @@ impl AsciiCol for Column<i64> {
   fn set_entry(&mut self, entry: TableEntry, index: usize) -> Result<(), TblDecodeErr> {
     match entry {
       TableEntry::Int(num) => {
-        if self.container.len() >= index {
+        if index >= self.container.len() {
           Err(IndexOutOfRangeErr::from_idx((None, index), (None, self.container.len())).into())
         } else {
           self.container[index] = num;
@@ -203,20 +343,39 @@ impl AsciiCol for Column<i64> {
     }
   }
 
+  fn as_typed_iter(&self) -> Box<dyn Iterator<Item = TableEntry> + '_> {
+    Box::new(self.container.iter().cloned().map(TableEntry::Int))
+  }
+
   fn remove_entry(&mut self, index: usize) -> Option<TableEntry> {
-    if self.container.len() >= index {
+    if index >= self.container.len() {
       None
     } else {
       Some(TableEntry::Int(self.container.remove(index)))
     }
   }
 
+  fn select_rows(&mut self, rows: &[usize]) {
+    select_rows(&mut self.container, rows)
+  }
+
   fn len(&self) -> usize {
     self.container.len()
   }
 
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
   fn to_ascii_vec(&self) -> Vec<String> {
-    self.container.par_iter().map(|primitive| primitive.to_string()).collect()
+    self
+      .container
+      .par_iter()
+      .map(|&primitive| match (&self.null, primitive) {
+        (Some(null), TableEntry::NULL_INT) => null.clone(),
+        _ => primitive.to_string(),
+      })
+      .collect()
   }
 
   fn get_col_label(&self) -> Option<&str> {
@@ -226,12 +385,34 @@ impl AsciiCol for Column<i64> {
     }
   }
 
-  fn get_col_fmt(&self) -> TableEntryFormat {
-    //(1) get the largest value, it'll be the longest
-    let width = self.container.iter().fold(0, |acc, entry| acc.max(entry.abs() as usize));
+  fn get_col_unit(&self) -> Option<&str> {
+    self.unit.as_deref()
+  }
+
+  fn get_col_null(&self) -> Option<&str> {
+    self.null.as_deref()
+  }
 
-    //(R) return width + 1 character for the sign of the integer
-    TableEntryFormat::Int(width + 1)
+  fn is_sorted_asc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Less)
+  }
+
+  fn is_sorted_desc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Greater)
+  }
+
+  fn get_col_fmt(&self) -> TableEntryFormat {
+    //(1) get the number of digits of the longest (defined) value
+    let width = self
+      .container
+      .iter()
+      .filter(|&&entry| self.null.is_none() || entry != TableEntry::NULL_INT)
+      .fold(1, |acc, entry| acc.max(entry.unsigned_abs().to_string().len()));
+
+    //(R) return width + 1 character for the sign of the integer (or the
+    //    width of the null string, if that is longer)
+    let null_width = self.null.as_ref().map_or(0, |null| null.len());
+    TableEntryFormat::Int((width + 1).max(null_width))
   }
 
   fn pretty_print(&self) -> String {
@@ -243,6 +424,20 @@ impl AsciiCol for Column<i64> {
       }
     )
   }
+
+  fn to_f64_vec(&self) -> Result<Vec<f64>, TypeMisMatchErr> {
+    //Undefined entries become NaN's
+    Ok(
+      self
+        .container
+        .par_iter()
+        .map(|&num| match (&self.null, num) {
+          (Some(_), TableEntry::NULL_INT) => f64::NAN,
+          _ => num as f64,
+        })
+        .collect(),
+    )
+  }
 }
 
 impl AsciiCol for Column<f64> {
@@ -263,7 +458,7 @@ impl AsciiCol for Column<f64> {
   fn set_entry(&mut self, entry: TableEntry, index: usize) -> Result<(), TblDecodeErr> {
     match entry {
       TableEntry::Float(num) => {
-        if self.container.len() >= index {
+        if index >= self.container.len() {
           Err(IndexOutOfRangeErr::from_idx((None, index), (None, self.container.len())).into())
         } else {
           self.container[index] = num;
@@ -281,23 +476,38 @@ impl AsciiCol for Column<f64> {
     }
   }
 
+  fn as_typed_iter(&self) -> Box<dyn Iterator<Item = TableEntry> + '_> {
+    Box::new(self.container.iter().cloned().map(TableEntry::Float))
+  }
+
   fn remove_entry(&mut self, index: usize) -> Option<TableEntry> {
-    if self.container.len() >= index {
+    if index >= self.container.len() {
       None
     } else {
       Some(TableEntry::Float(self.container.remove(index)))
     }
   }
 
+  fn select_rows(&mut self, rows: &[usize]) {
+    select_rows(&mut self.container, rows)
+  }
+
   fn len(&self) -> usize {
     self.container.len()
   }
 
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
   fn to_ascii_vec(&self) -> Vec<String> {
     self
       .container
       .par_iter()
-      .map(|primitive| format!("{primitive:.0$e}", DIGITS_AFTER_COMMA))
+      .map(|primitive| match &self.null {
+        Some(null) if primitive.is_nan() => null.clone(),
+        _ => format!("{primitive:.0$e}", DIGITS_AFTER_COMMA),
+      })
       .collect()
   }
 
@@ -308,13 +518,34 @@ impl AsciiCol for Column<f64> {
     }
   }
 
+  fn get_col_unit(&self) -> Option<&str> {
+    self.unit.as_deref()
+  }
+
+  fn get_col_null(&self) -> Option<&str> {
+    self.null.as_deref()
+  }
+
+  fn is_sorted_asc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Less)
+  }
+
+  fn is_sorted_desc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Greater)
+  }
+
   fn get_col_fmt(&self) -> TableEntryFormat {
-    //(1) Find the largest number -> it defines the width
-    let largest = self.container.iter().fold(0.0f64, |acc, entry| acc.max(entry.abs()));
+    //(1) Find the longest number -> it defines the width. Note that this is
+    //not necessarily the largest number, since exponents can be negative!
+    let width = self
+      .container
+      .iter()
+      .fold(1, |acc, entry| acc.max(format!("{:.1$e}", entry.abs(), DIGITS_AFTER_COMMA).len()));
 
-    //(R) width is width of largest number plus one for the sign
-    let width = format!("{largest:.0$e}", DIGITS_AFTER_COMMA).len() + 1;
-    TableEntryFormat::Float((width, DIGITS_AFTER_COMMA))
+    //(R) width is width of longest number plus one for the sign (or the
+    //    width of the null string, if that is longer)
+    let null_width = self.null.as_ref().map_or(0, |null| null.len());
+    TableEntryFormat::Float(((width + 1).max(null_width), DIGITS_AFTER_COMMA))
   }
 
   fn pretty_print(&self) -> String {
@@ -326,4 +557,103 @@ impl AsciiCol for Column<f64> {
       }
     )
   }
+
+  fn to_f64_vec(&self) -> Result<Vec<f64>, TypeMisMatchErr> {
+    Ok(self.container.clone())
+  }
+}
+
+impl AsciiCol for Column<bool> {
+  fn push_entry(&mut self, entry: TableEntry) -> Result<(), TypeMisMatchErr> {
+    match entry {
+      TableEntry::Bool(val) => {
+        self.container.push(val);
+        Ok(())
+      }
+      other => Err(TypeMisMatchErr::new(TableEntry::bool(), &other)),
+    }
+  }
+
+  fn pop_entry(&mut self) -> Option<TableEntry> {
+    self.container.pop().map(TableEntry::Bool)
+  }
+
+  fn set_entry(&mut self, entry: TableEntry, index: usize) -> Result<(), TblDecodeErr> {
+    match entry {
+      TableEntry::Bool(val) => {
+        if index >= self.container.len() {
+          Err(IndexOutOfRangeErr::from_idx((None, index), (None, self.container.len())).into())
+        } else {
+          self.container[index] = val;
+          Ok(())
+        }
+      }
+      other => Err(TypeMisMatchErr::new(TableEntry::bool(), &other).into()),
+    }
+  }
+
+  fn get_entry(&self, index: usize) -> Option<TableEntry> {
+    self.container.get(index).map(|&val| TableEntry::Bool(val))
+  }
+
+  fn as_typed_iter(&self) -> Box<dyn Iterator<Item = TableEntry> + '_> {
+    Box::new(self.container.iter().cloned().map(TableEntry::Bool))
+  }
+
+  fn remove_entry(&mut self, index: usize) -> Option<TableEntry> {
+    if index >= self.container.len() {
+      None
+    } else {
+      Some(TableEntry::Bool(self.container.remove(index)))
+    }
+  }
+
+  fn select_rows(&mut self, rows: &[usize]) {
+    select_rows(&mut self.container, rows)
+  }
+
+  fn len(&self) -> usize {
+    self.container.len()
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn to_ascii_vec(&self) -> Vec<String> {
+    //Logicals are written as T or F
+    self.container.par_iter().map(|&val| String::from(if val { "T" } else { "F" })).collect()
+  }
+
+  fn get_col_label(&self) -> Option<&str> {
+    self.label.as_deref()
+  }
+
+  fn get_col_unit(&self) -> Option<&str> {
+    self.unit.as_deref()
+  }
+
+  fn get_col_null(&self) -> Option<&str> {
+    self.null.as_deref()
+  }
+
+  fn is_sorted_asc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Less)
+  }
+
+  fn is_sorted_desc(&self) -> bool {
+    is_sorted(&self.container, Ordering::Greater)
+  }
+
+  fn get_col_fmt(&self) -> TableEntryFormat {
+    TableEntryFormat::Logical(1)
+  }
+
+  fn pretty_print(&self) -> String {
+    format!("label: {}, dtype: bool", self.label.as_deref().unwrap_or("(no label)"))
+  }
+
+  fn to_f64_vec(&self) -> Result<Vec<f64>, TypeMisMatchErr> {
+    Err(TypeMisMatchErr::new(TableEntry::float(), &TableEntry::bool()))
+  }
 }
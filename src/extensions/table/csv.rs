@@ -0,0 +1,220 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+    Conversions between ASCII tables and CSV files. Fields that contain the
+    delimiter, quotes or line breaks are quoted, with quotes escaped as "".
+    Undefined integers (see TNULL) and NaN's are written as empty fields, and
+    empty fields are read back as undefined values.
+*/
+
+use std::{
+  io::{Read, Write},
+  mem,
+};
+
+use crate::tbl_err::CsvParseErr;
+
+use super::{AsciiTable, Column, TableEntry};
+
+//TNULL value of integer columns that contain empty fields
+const INT_NULL: &str = "NULL";
+
+impl AsciiTable {
+  pub fn to_csv<W: Write>(&self, writer: W, delimiter: u8) -> Result<(), std::io::Error> {
+    //Tables without any labels are written without a row of labels
+    self.encode_csv(writer, delimiter, false)
+  }
+
+  pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), std::io::Error> {
+    /*
+        Same as to_csv() with a comma as delimiter, but the first row always
+        holds the column labels. Unlabelled columns are called col_N, with N
+        the (1-based) FITS column number.
+    */
+    self.encode_csv(writer, b',', true)
+  }
+
+  fn encode_csv<W: Write>(
+    &self,
+    mut writer: W,
+    delimiter: u8,
+    placeholders: bool,
+  ) -> Result<(), std::io::Error> {
+    let delimiter = delimiter as char;
+    let mut write_line = |fields: Vec<String>| {
+      let fields: Vec<_> = fields.iter().map(|field| quote_field(field, delimiter)).collect();
+      writeln!(writer, "{}", fields.join(&delimiter.to_string()))
+    };
+
+    //(1) Labels go in the first row, if there are any
+    if placeholders || (0..self.n_cols()).any(|col| self.column_label(col).is_some()) {
+      let label = |col: usize| match (self.column_label(col), placeholders) {
+        (Some(label), _) => label.to_string(),
+        (None, true) => format!("col_{}", col + 1),
+        (None, false) => String::new(),
+      };
+      write_line((0..self.n_cols()).map(label).collect())?;
+    }
+
+    //(2) Followed by the data. Short columns are padded with empty fields
+    for row in 0..self.n_rows() {
+      let fields = (0..self.n_cols())
+        .map(|col| match self.get_entry(col, row) {
+          Ok(entry) => format_entry(entry, self.column_null(col).is_some()),
+          Err(_) => String::new(),
+        })
+        .collect();
+      write_line(fields)?;
+    }
+
+    Ok(())
+  }
+
+  pub fn from_csv<R: Read>(
+    mut reader: R,
+    delimiter: u8,
+    has_header: bool,
+  ) -> Result<AsciiTable, CsvParseErr> {
+    /*
+        Columns that only contain integers become i64 columns, columns that
+        only contain numbers become f64 columns and all other columns become
+        string columns. Empty fields do not count towards the column type.
+    */
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut records = parse_records(&text, delimiter as char)?.into_iter();
+
+    //(1) The first record sets the number of columns
+    let labels: Vec<Option<String>> = match has_header {
+      true => match records.next() {
+        Some((_, labels)) => {
+          labels.into_iter().map(|label| Some(label).filter(|l| !l.is_empty())).collect()
+        }
+        None => return Ok(AsciiTable::new()),
+      },
+      false => Vec::new(),
+    };
+    let records: Vec<_> = records.collect();
+    let n_cols = match (has_header, records.first()) {
+      (true, _) => labels.len(),
+      (false, Some((_, fields))) => fields.len(),
+      (false, None) => return Ok(AsciiTable::new()),
+    };
+
+    //(2) Transpose the records into columns
+    let mut cells = vec![Vec::with_capacity(records.len()); n_cols];
+    for (line, fields) in records {
+      if fields.len() != n_cols {
+        let msg = format!("expected {n_cols} fields, found {}", fields.len());
+        return Err(CsvParseErr::new(line, msg));
+      }
+      cells.iter_mut().zip(fields).for_each(|(col, field)| col.push(field));
+    }
+
+    //(3) And find out what type of column each column is
+    let mut tbl = AsciiTable::new();
+    for (col, cells) in cells.into_iter().enumerate() {
+      let label = labels.get(col).cloned().flatten();
+      let mut values = cells.iter().map(|cell| cell.trim()).filter(|cell| !cell.is_empty());
+      if values.clone().next().is_none() {
+        tbl.add_column(Column::from_vec(label, None, cells));
+      } else if values.clone().all(|cell| cell.parse::<i64>().is_ok()) {
+        let null = cells.iter().any(|cell| cell.trim().is_empty());
+        let entries = cells.iter().map(|cell| cell.trim().parse().unwrap_or(TableEntry::NULL_INT));
+        let column = Column::from_vec(label, None, entries.collect());
+        tbl.add_column(column.with_null(null.then(|| INT_NULL.to_string())));
+      } else if values.all(|cell| cell.parse::<f64>().is_ok()) {
+        let entries = cells.iter().map(|cell| cell.trim().parse().unwrap_or(f64::NAN));
+        tbl.add_column(Column::from_vec(label, None, entries.collect()));
+      } else {
+        tbl.add_column(Column::from_vec(label, None, cells));
+      }
+    }
+
+    Ok(tbl)
+  }
+}
+
+fn format_entry(entry: TableEntry, has_null: bool) -> String {
+  match entry {
+    TableEntry::Int(TableEntry::NULL_INT) if has_null => String::new(),
+    TableEntry::Float(num) if num.is_nan() => String::new(),
+    //Debug formatting keeps the decimal point, so floats are read back as floats
+    TableEntry::Float(num) => format!("{num:?}"),
+    TableEntry::Int(num) => num.to_string(),
+    TableEntry::Bool(val) => String::from(if val { "T" } else { "F" }),
+    TableEntry::Text(txt) => txt,
+  }
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+  match field.contains([delimiter, '"', '\n', '\r']) {
+    true => format!("\"{}\"", field.replace('"', "\"\"")),
+    false => field.to_string(),
+  }
+}
+
+fn parse_records(text: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, CsvParseErr> {
+  //Splits a CSV file into records, together with the line they start on.
+  //Blank lines are skipped
+  let mut records = Vec::new();
+  let (mut record, mut field) = (Vec::new(), String::new());
+  let (mut line, mut start, mut quoted) = (1, 1, false);
+
+  let mut chars = text.chars().peekable();
+  while let Some(char) = chars.next() {
+    match char {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        chars.next();
+        field.push('"');
+      }
+      '"' if quoted => quoted = false,
+      '"' if field.is_empty() => quoted = true,
+      '\n' if quoted => {
+        line += 1;
+        field.push('\n');
+      }
+      _ if quoted => field.push(char),
+      '\r' if chars.peek() == Some(&'\n') => {} //CRLF line endings
+      '\n' => {
+        record.push(mem::take(&mut field));
+        if record.len() > 1 || !record[0].is_empty() {
+          records.push((start, mem::take(&mut record)));
+        }
+        record.clear();
+        line += 1;
+        start = line;
+      }
+      _ if char == delimiter => record.push(mem::take(&mut field)),
+      _ => field.push(char),
+    }
+  }
+
+  //The last line does not have to end with a line break
+  if quoted {
+    return Err(CsvParseErr::new(start, "quoted field is never closed"));
+  }
+  if !record.is_empty() || !field.is_empty() {
+    record.push(field);
+    records.push((start, record));
+  }
+
+  Ok(records)
+}
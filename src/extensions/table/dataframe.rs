@@ -0,0 +1,177 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+    Conversions between ASCII tables and the column-oriented tables of polars
+    and arrow. Text columns become string columns, integer and bit columns
+    become i64 columns, logical columns become boolean columns and all other
+    columns become f64 columns. Undefined integers (see TNULL) and the
+    missing entries of columns that are shorter than the table become nulls.
+*/
+
+use crate::raw::table_entry_format::TableEntryFormat;
+
+use super::{AsciiTable, TableEntry};
+
+//Entries of a single column, padded with None's to the height of the table
+enum ColumnValues {
+  Text(Vec<Option<String>>),
+  Int(Vec<Option<i64>>),
+  Float(Vec<Option<f64>>),
+  Bool(Vec<Option<bool>>),
+}
+
+fn column_values(tbl: &AsciiTable, col: usize) -> ColumnValues {
+  use TableEntryFormat::*;
+  let null = tbl.column_null(col);
+  let entries = (0..tbl.n_rows()).map(|row| tbl.get_entry(col, row).ok());
+  match tbl.get_col_fmt(col) {
+    Some(Int(_) | Bit(_)) => ColumnValues::Int(
+      entries
+        .map(|entry| entry?.as_int().filter(|&int| null.is_none() || int != TableEntry::NULL_INT))
+        .collect(),
+    ),
+    Some(Logical(_)) => ColumnValues::Bool(entries.map(|entry| entry?.as_bool()).collect()),
+    Some(Float(_) | Double(_) | General(_)) => {
+      ColumnValues::Float(entries.map(|entry| entry?.as_float()).collect())
+    }
+    _ => ColumnValues::Text(
+      entries
+        .map(|entry| entry?.into_string().ok().filter(|text| Some(text.as_str()) != null))
+        .collect(),
+    ),
+  }
+}
+
+fn column_name(tbl: &AsciiTable, col: usize) -> String {
+  //Unlabeled columns are named after their position, like TTYPE{i}
+  tbl.column_label(col).map_or_else(|| format!("COL{}", col + 1), String::from)
+}
+
+#[cfg(feature = "polars")]
+mod polars_conversion {
+  use polars::prelude::*;
+
+  use super::{column_name, column_values, ColumnValues};
+  use crate::{
+    extensions::table::{AsciiTable, Column, TableEntry},
+    tbl_err::TableConversionErr,
+  };
+
+  //TNULL value of integer columns that contain nulls
+  const INT_NULL: &str = "NULL";
+
+  impl AsciiTable {
+    pub fn to_polars(self) -> PolarsResult<DataFrame> {
+      //Fails if two columns have the same name
+      let columns = (0..self.n_cols())
+        .map(|col| {
+          let name = PlSmallStr::from(column_name(&self, col));
+          let series = match column_values(&self, col) {
+            ColumnValues::Text(values) => Series::new(name, values),
+            ColumnValues::Int(values) => Series::new(name, values),
+            ColumnValues::Float(values) => Series::new(name, values),
+            ColumnValues::Bool(values) => Series::new(name, values),
+          };
+          series.into()
+        })
+        .collect();
+      DataFrame::new(self.n_rows(), columns)
+    }
+
+    pub fn from_polars(df: DataFrame) -> Result<AsciiTable, TableConversionErr> {
+      /*
+          Integer columns become i64 columns and float columns become f64
+          columns. Nulls in integer columns are marked with a TNULL value,
+          nulls in float columns become NaN's and nulls in string columns
+          become empty strings. Boolean columns cannot contain nulls.
+      */
+      let mut tbl = AsciiTable::new();
+      for col in df.columns() {
+        let name = col.name().to_string();
+        let err = || TableConversionErr::new(&name, col.dtype());
+        let label = Some(name.clone());
+
+        match col.dtype() {
+          dtype if dtype.is_integer() => {
+            let col = col.cast(&DataType::Int64).map_err(|_| err())?;
+            let values = col.i64().map_err(|_| err())?;
+            let null = values.null_count() > 0;
+            let entries = values.iter().map(|int| int.unwrap_or(TableEntry::NULL_INT)).collect();
+            let column = Column::from_vec(label, None, entries);
+            tbl.add_column(column.with_null(null.then(|| INT_NULL.to_string())));
+          }
+          dtype if dtype.is_float() => {
+            let col = col.cast(&DataType::Float64).map_err(|_| err())?;
+            let values = col.f64().map_err(|_| err())?;
+            let entries = values.iter().map(|float| float.unwrap_or(f64::NAN)).collect();
+            tbl.add_column(Column::from_vec(label, None, entries));
+          }
+          DataType::String => {
+            let values = col.str().map_err(|_| err())?;
+            let entries = values.iter().map(|text| text.unwrap_or("").to_string()).collect();
+            tbl.add_column(Column::<String>::from_vec(label, None, entries));
+          }
+          DataType::Boolean => {
+            let values = col.bool().map_err(|_| err())?;
+            let entries = values.iter().collect::<Option<Vec<bool>>>().ok_or_else(err)?;
+            tbl.add_column(Column::from_vec(label, None, entries));
+          }
+          _ => return Err(err()),
+        }
+      }
+      Ok(tbl)
+    }
+  }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow_conversion {
+  use std::sync::Arc;
+
+  use arrow::{
+    array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray},
+    datatypes::{Field, Schema},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+  };
+
+  use super::{column_name, column_values, ColumnValues};
+  use crate::extensions::table::AsciiTable;
+
+  impl AsciiTable {
+    pub fn to_record_batch(self) -> Result<RecordBatch, ArrowError> {
+      let (mut fields, mut arrays) = (Vec::new(), Vec::new());
+      for col in 0..self.n_cols() {
+        let array: ArrayRef = match column_values(&self, col) {
+          ColumnValues::Text(values) => Arc::new(StringArray::from(values)),
+          ColumnValues::Int(values) => Arc::new(Int64Array::from(values)),
+          ColumnValues::Float(values) => Arc::new(Float64Array::from(values)),
+          ColumnValues::Bool(values) => Arc::new(BooleanArray::from(values)),
+        };
+        fields.push(Field::new(column_name(&self, col), array.data_type().clone(), true));
+        arrays.push(array);
+      }
+
+      //The row count is needed for tables without columns
+      let options = RecordBatchOptions::new().with_row_count(Some(self.n_rows()));
+      RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)
+    }
+  }
+}
@@ -18,13 +18,16 @@
 */
 
 use std::{
+  cmp::Ordering,
   error::Error,
   fmt::{self, Display, Formatter},
+  hash::{Hash, Hasher},
 };
 
 use crate::{
   raw::table_entry_format::TableEntryFormat,
-  tbl_fmt_err::{FieldSizeMisMatch, InvalidFFCode, ParseError},
+  tbl_err::TableEntryTypeErr,
+  tbl_fmt_err::{FieldSizeMisMatch, InvalidFFCode, InvalidLogical, ParseError},
 };
 
 #[derive(Debug, Clone)]
@@ -32,6 +35,7 @@ pub enum TableEntry {
   Text(String),
   Int(i64),
   Float(f64),
+  Bool(bool),
 }
 
 impl Display for TableEntry {
@@ -44,12 +48,178 @@ impl Display for TableEntry {
         Text(txt) => format!("{txt} (string)"),
         Int(num) => format!("{num} (int)"),
         Float(num) => format!("{num} (float)"),
+        Bool(val) => format!("{val} (bool)"),
       }
     )
   }
 }
 
+impl PartialEq for TableEntry {
+  fn eq(&self, other: &Self) -> bool {
+    use TableEntry::*;
+    match (self, other) {
+      (Text(a), Text(b)) => a == b,
+      (Int(a), Int(b)) => a == b,
+      (Float(a), Float(b)) => a == b,
+      (Bool(a), Bool(b)) => a == b,
+      _ => false,
+    }
+  }
+}
+
+/*  Note:
+    Entries of different types are ordered as Text < Int < Float < Bool. Like
+    f64 itself, NaN's cannot be compared to other floats.
+*/
+impl PartialOrd for TableEntry {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    use TableEntry::*;
+    match (self, other) {
+      (Text(a), Text(b)) => a.partial_cmp(b),
+      (Int(a), Int(b)) => a.partial_cmp(b),
+      (Float(a), Float(b)) => a.partial_cmp(b),
+      (Bool(a), Bool(b)) => a.partial_cmp(b),
+      _ => self.type_rank().partial_cmp(&other.type_rank()),
+    }
+  }
+}
+
+/*  Note:
+    Floats are hashed by their bit patterns, except for zero: 0.0 and -0.0 are
+    equal, so they have to end up with the same hash.
+*/
+impl Hash for TableEntry {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    use TableEntry::*;
+    std::mem::discriminant(self).hash(state);
+    match self {
+      Text(txt) => txt.as_bytes().hash(state),
+      Int(num) => num.hash(state),
+      Float(num) if *num == 0.0 => 0f64.to_bits().hash(state),
+      Float(num) => num.to_bits().hash(state),
+      Bool(val) => val.hash(state),
+    }
+  }
+}
+
+/*  Note:
+    Rows can only be used as hash set keys if equality is reflexive, which
+    f64 equality isn't for NaN. This key compares floats by their bit patterns
+    instead, so NaN entries are equal to each other while 0.0 and -0.0 are not.
+*/
+pub(crate) struct RowKey(pub(crate) Vec<TableEntry>);
+
+impl PartialEq for RowKey {
+  fn eq(&self, other: &Self) -> bool {
+    use TableEntry::*;
+    self.0.len() == other.0.len()
+      && self.0.iter().zip(&other.0).all(|pair| match pair {
+        (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+        (a, b) => a == b,
+      })
+  }
+}
+
+impl Eq for RowKey {}
+
+impl Hash for RowKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.0.hash(state)
+  }
+}
+
+/*
+    Conversions to the primitive types stored in table columns. Text entries
+    T and F (like FITS logicals) are accepted as booleans too.
+*/
+impl TryFrom<TableEntry> for String {
+  type Error = TableEntryTypeErr;
+  fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
+    entry.into_string().map_err(|entry| TableEntryTypeErr::new(&entry, "String"))
+  }
+}
+
+impl TryFrom<TableEntry> for i64 {
+  type Error = TableEntryTypeErr;
+  fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
+    entry.as_int().ok_or_else(|| TableEntryTypeErr::new(&entry, "i64"))
+  }
+}
+
+impl TryFrom<TableEntry> for f64 {
+  type Error = TableEntryTypeErr;
+  fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
+    entry.as_float().ok_or_else(|| TableEntryTypeErr::new(&entry, "f64"))
+  }
+}
+
+impl TryFrom<TableEntry> for bool {
+  type Error = TableEntryTypeErr;
+  fn try_from(entry: TableEntry) -> Result<Self, Self::Error> {
+    if let Some(val) = entry.as_bool() {
+      return Ok(val);
+    }
+    match entry.as_string().map(str::trim) {
+      Some("T") => Ok(true),
+      Some("F") => Ok(false),
+      _ => Err(TableEntryTypeErr::new(&entry, "bool")),
+    }
+  }
+}
+
 impl TableEntry {
+  //Undefined (TNULL) entries of integer columns have this value
+  pub const NULL_INT: i64 = i64::MIN;
+
+  /*
+      Typed accessors, these return None if the entry has a different type
+  */
+  pub fn as_string(&self) -> Option<&str> {
+    match self {
+      Self::Text(txt) => Some(txt),
+      _ => None,
+    }
+  }
+
+  pub fn as_int(&self) -> Option<i64> {
+    match self {
+      Self::Int(num) => Some(*num),
+      _ => None,
+    }
+  }
+
+  pub fn as_float(&self) -> Option<f64> {
+    match self {
+      Self::Float(num) => Some(*num),
+      _ => None,
+    }
+  }
+
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      Self::Bool(val) => Some(*val),
+      _ => None,
+    }
+  }
+
+  pub fn into_string(self) -> Result<String, TableEntry> {
+    //gives the entry back if it isn't a string
+    match self {
+      Self::Text(txt) => Ok(txt),
+      other => Err(other),
+    }
+  }
+
+  pub(crate) fn variant_name(&self) -> &'static str {
+    use TableEntry::*;
+    match self {
+      Text(_) => "Text",
+      Int(_) => "Int",
+      Float(_) => "Float",
+      Bool(_) => "Bool",
+    }
+  }
+
   pub(crate) fn from_parts(raw_field: &str, format: &TableEntryFormat) -> Result<Self, ParseError> {
     //(1) Check if the field is as long as was specified in the format
     if format.get_field_width() != raw_field.len() {
@@ -63,7 +233,17 @@ impl TableEntry {
     Ok(match format {
       Char(_) => Self::Text(String::from(raw_field)),
       Int(_) => Self::Int(str::parse(raw_field.trim())?),
-      Float(_) => Self::Float(str::parse(raw_field.trim())?),
+      //Fortran Dw.d fields use a 'D' instead of an 'E' for the exponent
+      Float(_) | Double(_) | General(_) => {
+        Self::Float(str::parse(&raw_field.trim().replace(['D', 'd'], "E"))?)
+      }
+      Logical(_) => match raw_field.trim() {
+        "T" => Self::Bool(true),
+        "F" => Self::Bool(false),
+        other => return Err(InvalidLogical::new(other).into()),
+      },
+      //Bit fields are written as binary numbers
+      Bit(_) => Self::Int(i64::from_str_radix(raw_field.trim(), 2)?),
       Invalid(invalid_format) => {
         return Err(InvalidFFCode::new(invalid_format.to_string()).into());
       }
@@ -76,6 +256,27 @@ impl TableEntry {
       Text(_) => String::from("(string)"),
       Int(_) => String::from("(int)"),
       Float(_) => String::from("(float)"),
+      Bool(_) => String::from("(bool)"),
+    }
+  }
+
+  pub(crate) fn total_cmp(&self, other: &Self) -> Ordering {
+    //Same as partial_cmp, but orders floats with f64::total_cmp (NaN's are
+    //sorted after all other numbers)
+    use TableEntry::*;
+    match (self, other) {
+      (Float(a), Float(b)) => a.total_cmp(b),
+      _ => self.partial_cmp(other).unwrap_or(Ordering::Equal),
+    }
+  }
+
+  fn type_rank(&self) -> u8 {
+    use TableEntry::*;
+    match self {
+      Text(_) => 0,
+      Int(_) => 1,
+      Float(_) => 2,
+      Bool(_) => 3,
     }
   }
 
@@ -88,4 +289,7 @@ impl TableEntry {
   pub(crate) fn float() -> Self {
     Self::Float(0.0)
   }
+  pub(crate) fn bool() -> Self {
+    Self::Bool(false)
+  }
 }
@@ -25,17 +25,125 @@ use core::fmt;
 use std::{
   error::Error,
   fmt::{Display, Formatter},
-  path::Path,
+  fs::OpenOptions,
+  io::{self, Read, Seek, SeekFrom, Write},
+  ops::{Index, IndexMut},
+  path::{Path, PathBuf},
+  slice, vec,
 };
 
+use ndarray::{Array, IxDyn};
+
+#[cfg(feature = "tokio")]
+use crate::raw::async_io::AsyncFitsReader;
+#[cfg(feature = "gz")]
+use crate::raw::gz_io::{self, GzFitsReader};
 use crate::{
+  bitpix::Bitpix,
+  builder::{FitsBuilder, HduBuilder, HeaderBuilder},
+  checksum_err::ChecksumMismatchErr,
+  extensions::{
+    image::{Pixel, ScalingMode},
+    Extension,
+  },
+  hdu_err::{ReorderErr, WrongExtensionErr},
+  header::Header,
   header_data_unit::HeaderDataUnit,
+  header_err::HeaderGrewErr,
+  io_err::{self, InvalidFitsFileErr},
+  options::{FitsReadOptions, ParseMode},
   raw::{
+    header_write_utils::validate_keyword_name,
+    keyword_utils::unquote_fits_string,
     raw_io::{RawFitsReader, RawFitsWriter},
     BlockSized,
   },
+  validation::{FitsValidator, ValidationIssue},
 };
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HduSummary {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Inventory of a single HDU, obtained without decoding its data. The
+      data type is the XTENSION of extension HDU's, and IMAGE or GROUPS for
+      primary HDU's that contain data. The start block is the offset of the
+      header in the file (in FITS blocks), see Fits::read_hdu().
+  */
+  pub slot: usize,
+  pub start_block: usize,
+  pub extname: Option<String>,
+  pub data_type: Option<String>,
+  pub shape: Vec<usize>,
+  pub bitpix: i8,
+  pub n_keywords: usize,
+  pub data_blocks: usize,
+}
+
+impl HduSummary {
+  fn from_header(header: &Header, slot: usize, start_block: usize) -> Result<Self, Box<dyn Error>> {
+    let shape = header.get_axes()?;
+    let naxis = shape.len();
+
+    let data_type = match header.get_value("XTENSION") {
+      Some(xtension) => Some(unquote_fits_string(xtension).into_owned()),
+      None if naxis == 0 => None,
+      None if header.get_value("GROUPS").map(|val| val.as_str()) == Some("T") => {
+        Some(String::from("GROUPS"))
+      }
+      None => Some(String::from("IMAGE")),
+    };
+
+    Ok(HduSummary {
+      slot,
+      start_block,
+      extname: header.get_value("EXTNAME").map(|name| unquote_fits_string(name).into_owned()),
+      data_type,
+      shape,
+      bitpix: header.get_value_as("BITPIX")?,
+      n_keywords: header.iter().count(),
+      data_blocks: header.get_data_block_len()?,
+    })
+  }
+}
+
+impl Display for HduSummary {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    //Compact single-line representation, meant for log messages
+    write!(
+      f,
+      "HduSummary {{ slot: {}, extname: {}, data_type: {}, dim: {}, shape: {:?}, bitpix: {} }}",
+      self.slot,
+      self.extname.as_deref().unwrap_or("-"),
+      self.data_type.as_deref().unwrap_or("-"),
+      self.shape.len(),
+      self.shape,
+      self.bitpix
+    )
+  }
+}
+
+//HDU index paired with the reason its checksums could not be verified
+type ChecksumFailures = Vec<(usize, Box<dyn Error>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumStatus {
+  //THIS STRUCT IS PART OF THE USER-FACING API
+  pub hdu_index: usize,
+  pub result: ChecksumResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumResult {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Outcome of verifying the CHECKSUM and DATASUM keywords of an HDU. A
+      failing HDU reports the first keyword that did not match: the value it
+      has in the header and the checksum that was computed instead.
+  */
+  Pass,
+  Fail { keyword: &'static str, expected: String, computed: u32 },
+  Missing,
+}
+
 #[derive(Debug, Clone)]
 pub struct Fits {
   hdus: Vec<HeaderDataUnit>,
@@ -43,28 +151,195 @@ pub struct Fits {
 
 impl Fits {
   pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+    /*
+        Reads all HDU's in the file. With the gz feature, files ending in .gz
+        are decompressed while they are read. Compressed files cannot be
+        opened lazily, since their blocks cannot be seeked to.
+    */
+    Self::open_with_options(path, FitsReadOptions::new())
+  }
+
+  pub fn open_with_scaling(path: &Path, scaling: ScalingMode) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), but with control over the scaling of images to
+        physical values (see ScalingMode). open() uses ScalingMode::Auto.
+    */
+    Self::open_with_options(path, FitsReadOptions::new().scaling(scaling))
+  }
+
+  pub fn open_with_options(path: &Path, options: FitsReadOptions) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), but with control over how the file is decoded (see
+        FitsReadOptions). Use FitsReadOptions::new().lenient() to read files
+        that do not quite follow the standard.
+    */
+    #[cfg(feature = "gz")]
+    if gz_io::is_gzip_path(path) {
+      //Decompress the file and decode it from memory
+      let bytes = GzFitsReader::new(path)?.read_to_end()?;
+      return Self::decode_all(&mut RawFitsReader::from_bytes(&bytes)?, options);
+    }
+
     //(1) Construct a RawFitsReader
     let mut reader = RawFitsReader::new(path)?;
 
     //(2) Read HDU's from the fits file until it is empty
+    Self::decode_all(&mut reader, options)
+  }
+
+  #[cfg(feature = "mmap")]
+  pub fn open_mmap(path: &Path) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), but the file is memory-mapped rather than read. This
+        is usually faster for large files. The file must not be modified by
+        other processes while it is being read!
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    let mut reader = RawFitsReader::new_mapped(path)?;
+    Self::decode_all(&mut reader, FitsReadOptions::new())
+  }
+
+  pub fn open_lazy(path: &Path) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), but only the headers are read. The data of each HDU
+        is decoded when it is first accessed, by seeking to its offset in the
+        file. Deferred data is never scaled (see ScalingMode::Raw), and the
+        file must not be modified while the Fits struct is in use.
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    let mut reader = RawFitsReader::new(path)?;
+
     let mut hdus = Vec::new();
-    while reader.get_block_index() < reader.get_block_len() {
-      hdus.push(HeaderDataUnit::decode_hdu(&mut reader)?)
+    while reader.get_blocks_remaining() > 0 {
+      let start_block = reader.get_block_index();
+      let header = Header::decode_header(&mut reader, ParseMode::Strict)?;
+      reader.skip_blocks(header.get_data_block_len()?)?;
+      hdus.push(HeaderDataUnit::new_deferred(header, path, start_block));
+    }
+
+    Ok(Fits { hdus })
+  }
+
+  pub fn read_primary_image<T: Pixel>(
+    path: &Path,
+  ) -> Result<(Array<T, IxDyn>, Header), Box<dyn Error>> {
+    /*
+        Reads the image in the primary HDU, together with its header. Only
+        the primary HDU is decoded. T must match the type of the pixels after
+        scaling (see ScalingMode::Auto), so images with BSCALE or BZERO
+        keywords are usually read as floats.
+    */
+    #[cfg(feature = "gz")]
+    if gz_io::is_gzip_path(path) {
+      let bytes = GzFitsReader::new(path)?.read_to_end()?;
+      return Self::decode_primary_image(&mut RawFitsReader::from_bytes(&bytes)?);
     }
+    Self::decode_primary_image(&mut RawFitsReader::new(path)?)
+  }
+
+  pub fn write_primary_image<T: Pixel>(
+    path: &Path,
+    array: Array<T, IxDyn>,
+    meta: Option<Header>,
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        Writes a file containing only a primary HDU with the supplied image.
+        The records of meta are copied to the header, except for the ones
+        that describe the data (BITPIX, NAXIS etc.).
+    */
+    let hdu = HduBuilder::image(array).header(meta.map(HeaderBuilder::from).unwrap_or_default());
+    FitsBuilder::new().primary(hdu).build()?.write(path)
+  }
 
-    //File is empty, we don't need the reader anymore!
-    // (3) return the completed file
+  pub(crate) fn from_hdu_list(hdus: Vec<HeaderDataUnit>) -> Self {
+    //The first HDU must be a primary HDU, the others extensions
+    Fits { hdus }
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), but for FITS files that are already resident in
+        memory (for instance downloaded files). The length of the buffer must
+        be an integer multiple of the FITS block size.
+    */
+    let mut reader = RawFitsReader::from_bytes(bytes)?;
+    Self::decode_all(&mut reader, FitsReadOptions::new())
+  }
+
+  #[cfg(feature = "tokio")]
+  pub async fn read_from_async(reader: &mut impl AsyncFitsReader) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as from_bytes(), but the FITS blocks are read asynchronously
+        from the reader. Decoding itself runs on the calling task: the Fits
+        struct is not Send, so it cannot be decoded on tokio's blocking pool.
+    */
+    //(1) Read all blocks (in chunks of 16 blocks)
+    let mut bytes = Vec::with_capacity(reader.blocks_remaining().unwrap_or(0) * crate::BLOCK_SIZE);
+    let mut chunk = vec![0u8; 16 * crate::BLOCK_SIZE];
+    loop {
+      let n_blocks = reader.read_blocks_into(&mut chunk).await?;
+      if n_blocks == 0 {
+        break;
+      }
+      bytes.extend_from_slice(&chunk[..n_blocks * crate::BLOCK_SIZE]);
+    }
+
+    //(2) Decode the file from memory
+    Self::from_bytes(&bytes)
+  }
+
+  #[cfg(feature = "gz")]
+  fn reject_compressed(path: &Path) -> Result<(), InvalidFitsFileErr> {
+    //Compressed files can only be read from start to finish
+    match gz_io::is_gzip_path(path) {
+      true => Err(InvalidFitsFileErr::new(io_err::COMPRESSED_SEEK)),
+      false => Ok(()),
+    }
+  }
+
+  fn decode_all(
+    reader: &mut RawFitsReader,
+    options: FitsReadOptions,
+  ) -> Result<Self, Box<dyn Error>> {
+    //Keep track of the checksums while reading, such that the CHECKSUM and
+    //DATASUM keywords can be verified afterwards
+    reader.enable_checksums();
+
+    //Read HDU's from the reader until it is empty
+    let mut hdus = Vec::new();
+    while reader.get_blocks_remaining() > 0 {
+      //Lenient mode ignores trailing blocks that do not start an extension
+      let lenient = options.parse_mode == ParseMode::Lenient;
+      if lenient && !hdus.is_empty() && !Self::starts_extension(reader)? {
+        let n_blocks = reader.get_blocks_remaining();
+        log::warn!("ignored {n_blocks} FITS block(s) after the last HDU");
+        break;
+      }
+      hdus.push(HeaderDataUnit::decode_hdu(reader, options)?)
+    }
+
+    //Source is empty, we don't need the reader anymore!
+    // (R) return the completed file
     Ok(Fits { hdus: hdus })
   }
 
+  fn starts_extension(reader: &mut RawFitsReader) -> Result<bool, Box<dyn Error>> {
+    //Checks if the next block starts with XTENSION, without consuming it
+    let start = reader.get_block_index();
+    let mut block = vec![0u8; crate::BLOCK_SIZE];
+    reader.read_blocks(&mut block)?;
+    reader.seek_to_block(start)?;
+    Ok(block.starts_with(b"XTENSION"))
+  }
+
   pub fn write(self, path: &Path) -> Result<(), Box<dyn Error>> {
     //(1) Construct a RawFitsWriter
     let mut writer = RawFitsWriter::new(path)?;
 
     //(2) Write all HDU's to this thing
-    for hdu in self.hdus {
-      hdu.encode_hdu(&mut writer)?;
-    }
+    self.encode_all(&mut writer)?;
 
     //(3) Flush writer and close the file
     writer.flush()?;
@@ -73,10 +348,615 @@ impl Fits {
     Ok(())
   }
 
+  pub fn write_synced(self, path: &Path) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as write(), but only returns once the file has reached the disk,
+        such that it survives a crash or power failure. This is slower than
+        write(), since we have to wait for the disk (see write_synced_data()
+        for a faster alternative).
+    */
+    let mut writer = RawFitsWriter::new(path)?;
+    self.encode_all(&mut writer)?;
+    writer.flush_and_sync()?;
+    Ok(())
+  }
+
+  pub fn write_synced_data(self, path: &Path) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as write_synced(), but only the data and the file size are
+        synced, not the other metadata of the file (such as its modification
+        time). This saves a write to disk on some filesystems, and the file
+        can still be read back completely after a crash.
+    */
+    let mut writer = RawFitsWriter::new(path)?;
+    self.encode_all(&mut writer)?;
+    writer.flush_and_sync_data()?;
+    Ok(())
+  }
+
+  pub fn write_with_progress<F>(self, path: &Path, mut callback: F) -> Result<(), Box<dyn Error>>
+  where
+    F: FnMut(usize, usize),
+  {
+    /*
+        Same as write(), but calls callback(blocks_written, total_blocks)
+        every time a chunk of the file has been written (to display a
+        progress bar, for instance). The total is estimated up front from the
+        HDU's as they were read, and headers may shrink when re-encoded.
+        Hence the final call always reports the actual #blocks as the total.
+    */
+    let total = self.get_block_len();
+    let mut writer =
+      RawFitsWriter::new(path)?.with_progress(|written| callback(written, total.max(written)));
+    self.encode_all(&mut writer)?;
+    writer.flush()?;
+
+    let written = writer.get_blocks_written();
+    drop(writer);
+    if written != total {
+      callback(written, written);
+    }
+    Ok(())
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+    /*
+        Same as write(), but the file is returned as a buffer rather than
+        written to disk. The bytes are exactly those that write() would
+        write, and can be read back with from_bytes().
+    */
+    let mut writer = RawFitsWriter::in_memory();
+    self.clone().encode_all(&mut writer)?;
+    Ok(writer.into_bytes())
+  }
+
+  fn decode_primary_image<T: Pixel>(
+    reader: &mut RawFitsReader,
+  ) -> Result<(Array<T, IxDyn>, Header), Box<dyn Error>> {
+    let (header, data) = HeaderDataUnit::decode_hdu(reader, FitsReadOptions::new())?.to_parts();
+    match data {
+      Some(Extension::Image(img)) => Ok((img.into_array()?, header)),
+      other => Err(Box::new(WrongExtensionErr::new("an image", other.as_ref()))),
+    }
+  }
+
+  fn fill_block<R: Read>(reader: &mut R, block: &mut [u8]) -> Result<bool, Box<dyn Error>> {
+    //Reads a whole block. Returns false at the end of the reader, and fails
+    //if the reader ends halfway through the block
+    let mut filled = 0;
+    while filled < block.len() {
+      match reader.read(&mut block[filled..])? {
+        0 if filled == 0 => return Ok(false),
+        0 => return Err(Box::new(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV))),
+        n => filled += n,
+      }
+    }
+    Ok(true)
+  }
+
+  fn encode_all(self, writer: &mut RawFitsWriter) -> Result<(), Box<dyn Error>> {
+    //With the checksum feature enabled, the CHECKSUM and DATASUM keywords are
+    //always updated
+    for hdu in self.hdus {
+      #[cfg(feature = "checksum")]
+      hdu.encode_hdu_with_checksum(writer)?;
+      #[cfg(not(feature = "checksum"))]
+      hdu.encode_hdu(writer)?;
+    }
+    Ok(())
+  }
+
+  pub fn open_with_checksum(path: &Path) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as open(), except that the CHECKSUM and DATASUM keywords of all
+        HDU's are verified while reading. HDU's without these keywords are
+        not checked. Rescaled data loses these keywords (the checksums no
+        longer match the data), so those HDU's are verified before scaling.
+    */
+    let options = FitsReadOptions { verify_checksums: true, ..FitsReadOptions::new() };
+    let fits = Self::open_with_options(path, options)?;
+    match fits.verify_checksums() {
+      Ok(()) => Ok(fits),
+      Err(mut failures) => Err(failures.swap_remove(0).1),
+    }
+  }
+
+  pub fn verify_checksums(&self) -> Result<(), ChecksumFailures> {
+    /*
+        Verifies the CHECKSUM and DATASUM keywords of all HDU's, and returns
+        the index of each HDU that failed together with the reason why.
+        HDU's without these keywords are not checked.
+    */
+    let failures = self
+      .hdus
+      .iter()
+      .enumerate()
+      .filter_map(|(index, hdu)| hdu.verify_checksum().err().map(|err| (index, err)))
+      .collect::<Vec<_>>();
+
+    if failures.is_empty() {
+      Ok(())
+    } else {
+      Err(failures)
+    }
+  }
+
+  pub fn checksum_status(&self) -> Result<Vec<ChecksumStatus>, Box<dyn Error>> {
+    /*
+        Same as verify_checksums(), but reports the outcome for every HDU,
+        including the ones without CHECKSUM and DATASUM keywords. HDU's that
+        cannot be encoded to compute their checksums are an error.
+    */
+    let mut statuses = Vec::new();
+    for (hdu_index, hdu) in self.hdus.iter().enumerate() {
+      let header = hdu.get_header();
+      if !header.contains("CHECKSUM") && !header.contains("DATASUM") {
+        statuses.push(ChecksumStatus { hdu_index, result: ChecksumResult::Missing });
+        continue;
+      }
+      let result = match hdu.verify_checksum() {
+        Ok(()) => ChecksumResult::Pass,
+        Err(err) => match err.downcast::<ChecksumMismatchErr>() {
+          Ok(mismatch) => ChecksumResult::Fail {
+            keyword: mismatch.keyword,
+            expected: mismatch.stored,
+            computed: mismatch.computed,
+          },
+          Err(err) => return Err(err),
+        },
+      };
+      statuses.push(ChecksumStatus { hdu_index, result });
+    }
+
+    Ok(statuses)
+  }
+
+  pub fn update_checksums(&mut self) -> Result<(), Box<dyn Error>> {
+    //Adds (or updates) the CHECKSUM and DATASUM keywords of all HDU's
+    for hdu in &mut self.hdus {
+      hdu.update_checksums()?;
+    }
+    Ok(())
+  }
+
+  pub fn write_with_checksum(self, path: &Path) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as write(), except that the CHECKSUM and DATASUM keywords are
+        added to (or updated in) the header of every HDU.
+    */
+    let mut writer = RawFitsWriter::new(path)?;
+
+    for hdu in self.hdus {
+      hdu.encode_hdu_with_checksum(&mut writer)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+  }
+
+  pub fn validate(&self) -> Vec<ValidationIssue> {
+    //Checks all headers against the FITS standard (see FitsValidator)
+    FitsValidator::new().validate_fits(self)
+  }
+
+  pub fn validate_file(path: &Path) -> Result<Vec<ValidationIssue>, Box<dyn Error>> {
+    /*
+        Same as validate(), but for a file on disk. Only the headers are read,
+        so files that cannot be opened (because their data is missing or an
+        END keyword is absent) are validated too.
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    FitsValidator::new().validate_file(path)
+  }
+
+  pub fn scan_headers_only(path: &Path) -> Result<Vec<HduSummary>, Box<dyn Error>> {
+    /*
+        Reads all headers in the file, but skips over the data units. This
+        is much faster than open() for files with large images.
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    let mut reader = RawFitsReader::new(path)?;
+
+    let mut summaries = Vec::new();
+    while reader.get_blocks_remaining() > 0 {
+      //(1) Read the header and skip the data
+      let start_block = reader.get_block_index();
+      let header = Header::decode_header(&mut reader, ParseMode::Strict)?;
+      reader.skip_blocks(header.get_data_block_len()?)?;
+
+      //(2) Summarize the header
+      summaries.push(HduSummary::from_header(&header, summaries.len(), start_block)?);
+    }
+
+    Ok(summaries)
+  }
+
+  pub fn peek<R: Read>(reader: &mut R) -> Result<Vec<HduSummary>, Box<dyn Error>> {
+    /*
+        Same as scan_headers_only(), but for any reader (a decompressing
+        reader, a network stream etc.). Readers cannot be seeked, so the data
+        units are still read from the reader, but they are not decoded.
+    */
+    let mut summaries = Vec::new();
+    let mut start_block = 0;
+    let mut block = vec![0u8; crate::BLOCK_SIZE];
+    loop {
+      //(1) The file ends where a new header would start
+      if !Self::fill_block(reader, &mut block)? {
+        break;
+      }
+      let header = Header::read_from(&mut block.as_slice().chain(&mut *reader))?;
+
+      //(2) Summarize the header and skip the data
+      let summary = HduSummary::from_header(&header, summaries.len(), start_block)?;
+      let data_len = (summary.data_blocks * crate::BLOCK_SIZE) as u64;
+      let copied = io::copy(&mut reader.take(data_len), &mut io::sink())?;
+      if copied != data_len {
+        let available = copied as usize / crate::BLOCK_SIZE;
+        return Err(Box::new(InvalidFitsFileErr::end_of_file(available, summary.data_blocks)));
+      }
+      start_block += header.get_block_len() + summary.data_blocks;
+      summaries.push(summary);
+    }
+
+    Ok(summaries)
+  }
+
+  pub fn read_hdu(path: &Path, summary: &HduSummary) -> Result<HeaderDataUnit, Box<dyn Error>> {
+    /*
+        Reads a single HDU from the file, using the block offset recorded by
+        scan_headers_only(). The HDU's in front of it are not read at all.
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    let mut reader = RawFitsReader::new(path)?;
+    reader.seek_to_block(summary.start_block)?;
+    if reader.get_blocks_remaining() == 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::NO_SUCH_HDU)));
+    }
+
+    //Keep track of the checksums, like open() does
+    reader.enable_checksums();
+    HeaderDataUnit::decode_hdu(&mut reader, FitsReadOptions::new())
+  }
+
+  pub fn read_hdu_into(
+    path: &Path,
+    summary: &HduSummary,
+    hdu: &mut HeaderDataUnit,
+    buffer: &mut Vec<u8>,
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as read_hdu(), but the HDU replaces the contents of hdu. The data
+        unit is read into buffer, and images with the same type and shape as
+        the image in hdu are decoded into its pixels. Pipelines that read
+        many similar HDU's can therefore reuse both allocations. On errors
+        hdu is left untouched.
+    */
+    #[cfg(feature = "gz")]
+    Self::reject_compressed(path)?;
+    let mut reader = RawFitsReader::new(path)?;
+    reader.seek_to_block(summary.start_block)?;
+    if reader.get_blocks_remaining() == 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::NO_SUCH_HDU)));
+    }
+
+    //Keep track of the checksums, like open() does
+    reader.enable_checksums();
+    hdu.decode_into(&mut reader, buffer)
+  }
+
+  pub fn rewrite_header_in_place(
+    path: &Path,
+    hdu_idx: usize,
+    key: &str,
+    value: &str,
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        Sets a single keyword in the header of an HDU without rewriting the
+        rest of the file. Values are stored as they appear in the header, so
+        strings have to be quoted (see quote_fits_string). The header may not
+        grow beyond the FITS blocks it already occupies, since the data unit
+        would have to move. Checksums are not updated.
+    */
+    //(1) Restricted keywords describe the data, which we do not touch
+    validate_keyword_name(key)?;
+
+    //(2) Find the header using the block offsets from scan_headers_only()
+    let summary = Self::scan_headers_only(path)?
+      .into_iter()
+      .nth(hdu_idx)
+      .ok_or(InvalidFitsFileErr::new(io_err::NO_SUCH_HDU))?;
+    let offset = (summary.start_block * crate::BLOCK_SIZE) as u64;
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    //(3) Read only the header blocks and modify the record
+    let mut header = Header::read_from(&mut file)?;
+    let old_blocks = header.get_block_len();
+    header.set_value(key, value.to_string());
+    let mut bytes = Vec::new();
+    header.write_to(&mut bytes)?;
+
+    //(4) Headers that shrunk are padded with blank records in front of the
+    //    END keyword, so the data unit still starts where it used to
+    let new_blocks = bytes.len() / crate::BLOCK_SIZE;
+    if new_blocks > old_blocks {
+      return Err(Box::new(HeaderGrewErr { old_blocks, new_blocks }));
+    }
+    let end = bytes.rchunks(80).position(|record| record.starts_with(b"END ")).unwrap();
+    let end = bytes.len() - 80 * (end + 1);
+    bytes.splice(end..end, vec![b' '; (old_blocks - new_blocks) * crate::BLOCK_SIZE]);
+
+    //(5) Overwrite the old header blocks
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&bytes)?;
+    Ok(())
+  }
+
+  pub fn copy_file(src: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    /*
+        Copies a FITS file without decoding it. Only the headers are read, to
+        check that the source is a FITS file. The data is copied as is.
+    */
+    Self::scan_headers_only(src)?;
+    Self::reject_same_file(src, dest)?;
+    std::fs::copy(src, dest)?;
+    Ok(())
+  }
+
+  pub fn copy_file_with_header_patch(
+    src: &Path,
+    dest: &Path,
+    hdu_idx: usize,
+    patches: &[(&str, &str)],
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as copy_file(), but sets the (keyword, value) pairs in the header
+        of one HDU. Values are stored as they appear in the header, like in
+        rewrite_header_in_place(). Only this header is re-encoded, so it may
+        grow, and all other blocks are copied without decoding them.
+        Checksums are not updated.
+    */
+    //(1) Restricted keywords describe the data, which we do not touch
+    for (key, _) in patches {
+      validate_keyword_name(key)?;
+    }
+
+    //(2) Find the header using the block offsets from scan_headers_only()
+    let summary = Self::scan_headers_only(src)?
+      .into_iter()
+      .nth(hdu_idx)
+      .ok_or(InvalidFitsFileErr::new(io_err::NO_SUCH_HDU))?;
+    Self::reject_same_file(src, dest)?;
+    let mut input = std::fs::File::open(src)?;
+    let mut output = std::fs::File::create(dest)?;
+
+    //(3) Copy everything in front of the header
+    let offset = (summary.start_block * crate::BLOCK_SIZE) as u64;
+    io::copy(&mut (&mut input).take(offset), &mut output)?;
+
+    //(4) Patch the header
+    let mut header = Header::read_from(&mut input)?;
+    for (key, value) in patches {
+      header.set_value(key, value.to_string());
+    }
+    header.write_to(&mut output)?;
+
+    //(5) Copy the data unit and all HDU's after it
+    io::copy(&mut input, &mut output)?;
+    output.flush()?;
+    Ok(())
+  }
+
+  fn reject_same_file(src: &Path, dest: &Path) -> Result<(), io::Error> {
+    //Copying a file onto itself would truncate it before it is read
+    match dest.exists() && src.canonicalize()? == dest.canonicalize()? {
+      true => Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot copy a file onto itself")),
+      false => Ok(()),
+    }
+  }
+
   pub fn get_hdu(&self, index: usize) -> Option<&HeaderDataUnit> {
     self.hdus.get(index)
   }
 
+  pub fn get_hdu_mut(&mut self, index: usize) -> Option<&mut HeaderDataUnit> {
+    self.hdus.get_mut(index)
+  }
+
+  pub fn get(&self, index: usize) -> Option<&HeaderDataUnit> {
+    //Non-panicking alternative to fits[index], like Vec::get
+    self.hdus.get(index)
+  }
+
+  pub fn primary(&self) -> Option<&HeaderDataUnit> {
+    //The primary HDU is always the first HDU in the file
+    self.hdus.first()
+  }
+
+  pub fn iter(&self) -> slice::Iter<'_, HeaderDataUnit> {
+    //Iterates over all HDU's without consuming the file
+    self.hdus.iter()
+  }
+
+  pub fn get_hdu_by_name(&self, extname: &str) -> Option<&HeaderDataUnit> {
+    //returns the first HDU with a matching EXTNAME keyword
+    self.hdus.iter().find(|hdu| hdu.get_extname().as_deref() == Some(extname))
+  }
+
+  pub fn get_hdu_by_extname(&self, extname: &str) -> Option<(usize, &HeaderDataUnit)> {
+    //Same as get_hdu_by_name, but also returns the index of the HDU
+    self.hdus.iter().enumerate().find(|(_, hdu)| hdu.get_extname().as_deref() == Some(extname))
+  }
+
+  pub fn get_all_hdus_by_extname(&self, extname: &str) -> Vec<(usize, &HeaderDataUnit)> {
+    /*
+        Returns all HDU's with a matching EXTNAME keyword (and their index),
+        in the order in which they appear in the file. HDU's with the same
+        name are told apart by their EXTVER keyword (see get_extver).
+    */
+    self
+      .hdus
+      .iter()
+      .enumerate()
+      .filter(|(_, hdu)| hdu.get_extname().as_deref() == Some(extname))
+      .collect()
+  }
+
+  pub fn resolve_inheritance(&mut self) {
+    //Applies the INHERIT keyword of all extensions (see inherit_from)
+    if let Some((primary, extensions)) = self.hdus.split_first_mut() {
+      for hdu in extensions {
+        hdu.inherit_from(primary);
+      }
+    }
+  }
+
+  pub fn append_empty_hdu(&mut self, extname: &str) -> usize {
+    /*
+        Appends a metadata-only IMAGE extension (NAXIS = 0) with the supplied
+        name to the file. Returns the index of the new HDU.
+    */
+    self.hdus.push(HeaderDataUnit::new_empty(extname, None));
+    self.hdus.len() - 1
+  }
+
+  pub fn append_empty_hdu_with_meta(&mut self, extname: &str, meta: Header) -> usize {
+    /*
+        Same as append_empty_hdu, but copies the records of the supplied
+        header to the new HDU. Records that describe a data unit (BITPIX,
+        NAXIS etc.) are not copied.
+    */
+    self.hdus.push(HeaderDataUnit::new_empty(extname, Some(meta)));
+    self.hdus.len() - 1
+  }
+
+  pub fn merge(mut self, other: Fits) -> Fits {
+    /*
+        Appends all HDU's of other to this file, in order. The primary HDU of
+        other becomes an IMAGE extension.
+    */
+    for hdu in other.hdus {
+      self.push_hdu(hdu);
+    }
+    self
+  }
+
+  pub fn split_hdus(&self, dir: &Path, stem: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    /*
+        Writes every HDU to a file of its own: HDU 0 to {stem}.fits, HDU 1 to
+        {stem}_1.fits and so on. Image extensions become primary HDU's (with
+        the keywords they inherit from the primary HDU). Other extensions
+        cannot be primary HDU's, so they follow an empty primary HDU.
+    */
+    let mut paths = Vec::new();
+    for (index, hdu) in self.hdus.iter().enumerate() {
+      let name = match index {
+        0 => format!("{stem}.fits"),
+        _ => format!("{stem}_{index}.fits"),
+      };
+      let path = dir.join(name);
+
+      let mut hdu = hdu.clone();
+      if index > 0 {
+        hdu.inherit_from(&self.hdus[0]);
+      }
+      let mut single = Fits { hdus: Vec::new() };
+      match hdu.get_data() {
+        None | Some(Extension::Image(_)) => {
+          hdu.get_header_mut().make_primary();
+          single.hdus.push(hdu);
+        }
+        Some(_) => single.push_hdu(hdu),
+      }
+      single.write(&path)?;
+      paths.push(path);
+    }
+
+    Ok(paths)
+  }
+
+  pub fn from_hdus(paths: &[PathBuf]) -> Result<Fits, Box<dyn Error>> {
+    /*
+        Inverse of split_hdus(): combines the HDU's of several files into one
+        file, in order. The primary HDU's of all files but the first become
+        IMAGE extensions. Primary HDU's without data or metadata only exist
+        to precede the extensions of their file, so those are left out.
+    */
+    let mut fits = Fits { hdus: Vec::new() };
+    for path in paths {
+      let hdus = Fits::open(path)?.hdus;
+      let n_hdus = hdus.len();
+      for (index, hdu) in hdus.into_iter().enumerate() {
+        let placeholder = index == 0 && n_hdus > 1 && !hdu.has_data() && !hdu.has_meta();
+        if !fits.hdus.is_empty() && placeholder {
+          continue;
+        }
+        fits.push_hdu(hdu);
+      }
+    }
+
+    Ok(fits)
+  }
+
+  pub fn clone_hdu(&self, src_slot: usize, target: &mut Fits) -> Result<(), Box<dyn Error>> {
+    //Appends a copy of a single HDU to target, like merge does
+    let hdu = self.hdus.get(src_slot).ok_or(InvalidFitsFileErr::new(io_err::NO_SUCH_HDU))?;
+    target.push_hdu(hdu.clone());
+    Ok(())
+  }
+
+  pub fn insert_hdu(&mut self, index: usize, hdu: HeaderDataUnit) {
+    /*
+        Inserts an HDU at index, shifting the HDU's behind it. Primary HDU's
+        become IMAGE extensions, like in merge(). Panics if index is 0 (the
+        primary HDU always comes first) or larger than the number of HDU's.
+    */
+    assert!(index > 0, "HDU's cannot be inserted in front of the primary HDU");
+    assert!(index <= self.hdus.len(), "index {index} is out of range");
+    self.insert_extension(index, hdu);
+  }
+
+  pub fn swap_hdus(&mut self, i: usize, j: usize) {
+    //Swaps two extensions. Panics if either index is 0 or out of range
+    assert!(i > 0 && j > 0, "the primary HDU cannot be swapped");
+    self.hdus.swap(i, j);
+  }
+
+  pub fn reorder_hdus(&mut self, permutation: &[usize]) -> Result<(), ReorderErr> {
+    /*
+        Reorders the HDU's such that HDU permutation[i] ends up at index i.
+        The permutation must contain every index exactly once, and the
+        primary HDU has to stay first.
+    */
+    //(1) Check the permutation
+    let n_hdus = self.hdus.len();
+    if permutation.len() != n_hdus {
+      let reason =
+        format!("permutation has {} indices, file has {n_hdus} HDU's", permutation.len());
+      return Err(ReorderErr::new(reason));
+    }
+    let mut seen = vec![false; n_hdus];
+    for &index in permutation {
+      match seen.get_mut(index) {
+        None => return Err(ReorderErr::new(format!("index {index} is out of range"))),
+        Some(true) => return Err(ReorderErr::new(format!("index {index} appears twice"))),
+        Some(seen) => *seen = true,
+      }
+    }
+    if permutation.first().is_some_and(|&first| first != 0) {
+      return Err(ReorderErr::new(String::from("the primary HDU has to stay first")));
+    }
+
+    //(2) Move the HDU's to their new positions
+    let mut old: Vec<Option<HeaderDataUnit>> = self.hdus.drain(..).map(Some).collect();
+    self.hdus = permutation.iter().map(|&index| old[index].take().unwrap()).collect();
+    Ok(())
+  }
+
   pub fn remove_hdu(&mut self, index: usize) -> Option<HeaderDataUnit> {
     if self.hdus.len() < index {
       return None;
@@ -85,6 +965,81 @@ impl Fits {
   }
 }
 
+impl Fits {
+  /*
+      INTERNAL FUNCS
+  */
+  fn push_hdu(&mut self, hdu: HeaderDataUnit) {
+    /*
+        Appends an HDU to the file. Primary HDU's become extensions, unless the
+        file is empty. Extensions cannot be primary HDU's, so an empty file
+        gets an empty primary HDU first (like FitsBuilder does).
+    */
+    match (self.hdus.is_empty(), hdu.get_header().contains("XTENSION")) {
+      (true, false) => return self.hdus.push(hdu),
+      (true, true) => {
+        let primary = Header::new_primary_image(Bitpix::Byte, &[]);
+        self.hdus.push(HeaderDataUnit::new(primary, None));
+      }
+      (false, _) => {}
+    }
+    self.insert_extension(self.hdus.len(), hdu);
+  }
+
+  fn insert_extension(&mut self, index: usize, mut hdu: HeaderDataUnit) {
+    //Inserts an HDU behind the primary HDU, which announces the extension
+    let header = self.hdus[0].get_header_mut();
+    header.set_value("EXTEND", String::from("T"));
+    header.update_block_len();
+    hdu.get_header_mut().make_extension();
+    self.hdus.insert(index, hdu);
+  }
+}
+
+impl IntoIterator for Fits {
+  type Item = HeaderDataUnit;
+  type IntoIter = vec::IntoIter<HeaderDataUnit>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.hdus.into_iter()
+  }
+}
+
+impl<'a> IntoIterator for &'a Fits {
+  type Item = &'a HeaderDataUnit;
+  type IntoIter = slice::Iter<'a, HeaderDataUnit>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+/*
+    fits[i] panics if the file has no i-th HDU, just like indexing a Vec.
+    HDU's that are replaced through fits[i] = hdu are written as they are, so
+    the primary HDU should not be replaced by an extension (or vice versa).
+*/
+impl Index<usize> for Fits {
+  type Output = HeaderDataUnit;
+
+  fn index(&self, index: usize) -> &Self::Output {
+    match self.hdus.get(index) {
+      Some(hdu) => hdu,
+      None => panic!("HDU index {index} out of range for FITS file with {} HDU's", self.hdus.len()),
+    }
+  }
+}
+
+impl IndexMut<usize> for Fits {
+  fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+    let len = self.hdus.len();
+    match self.hdus.get_mut(index) {
+      Some(hdu) => hdu,
+      None => panic!("HDU index {index} out of range for FITS file with {len} HDU's"),
+    }
+  }
+}
+
 impl BlockSized for Fits {
   fn get_block_len(&self) -> usize {
     (&self.hdus).iter().fold(0, |sum, hdu| sum + hdu.get_block_len())
@@ -20,24 +20,42 @@
 use std::{
   error::Error,
   fmt::{self, Display},
+  io::{Read, Write},
   rc::Rc,
   str::FromStr,
 };
 
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, NaiveDateTime, Utc};
 use indexmap::IndexMap;
 
 use crate::{
-  hdu_err::MissingRecordError,
+  bitpix::Bitpix,
+  hdu_err::{InvalidBitpixErr, MissingRecordError},
+  header_err::InvalidHeaderErr,
+  keyword_err::InvalidKeywordErr,
+  options::ParseMode,
   raw::{
     header_block::HeaderBlock,
-    keyword_record::KeywordRecord,
+    header_write_utils::validate_keyword_name,
+    keyword_record::{KeywordRecord, COMMENTARY, COMMENTARY_LEN},
+    keyword_utils::{parse_fits_datetime, quote_fits_string, unquote_fits_string},
     raw_io::{RawFitsReader, RawFitsWriter},
     BlockSized,
   },
 };
 
 const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
+const RECORD_SIZE: usize = 80;
+
+//Mandatory keywords that may appear only once in a header
+const UNIQUE_KEYWORDS: [&str; 3] = ["SIMPLE", "BITPIX", "NAXIS"];
+
+//Keywords describing the data unit. These are never copied from user-supplied
+//metadata into a new header (NAXIS{i} keywords are checked separately)
+const STRUCTURAL_KEYWORDS: [&str; 14] = [
+  "SIMPLE", "XTENSION", "BITPIX", "NAXIS", "PCOUNT", "GCOUNT", "EXTEND", "GROUPS", "EXTNAME",
+  "BSCALE", "BZERO", "BLANK", "CHECKSUM", "DATASUM",
+];
 
 /*
     Public version of the header is a Simple IndexMap with a wrapper around it
@@ -50,7 +68,7 @@ pub struct Header {
 }
 
 impl Header {
-  pub fn decode_header(raw: &mut RawFitsReader) -> Result<Self, Box<dyn Error>> {
+  pub fn decode_header(raw: &mut RawFitsReader, mode: ParseMode) -> Result<Self, Box<dyn Error>> {
     /*  Setup:
         We'll keep reading headerblocks (= FITS blocks) until we encounter
         the END keyword. We'll also have to keep track of the block size of
@@ -63,7 +81,7 @@ impl Header {
     while !end {
       //Read the next headerblock (2880 bytes) and decode it!
       block_len += raw.read_blocks(&mut hb_buf)?;
-      let (hb, finished) = HeaderBlock::decode_from_bytes(&hb_buf)?;
+      let (hb, finished) = HeaderBlock::decode_from_bytes(&hb_buf, mode)?;
 
       //Append the keywords that we found
       hbs.push(hb);
@@ -72,10 +90,33 @@ impl Header {
       end = finished;
     }
 
-    Ok(Self::from_parts(hbs, block_len)?)
+    Self::from_parts(hbs, block_len, mode)
   }
 
-  fn from_parts(hbs: Vec<HeaderBlock>, block_len: usize) -> Result<Self, Box<dyn Error>> {
+  pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Box<dyn Error>> {
+    /*
+        Reads a single header from any reader, one FITS block at a time, up to
+        and including the block with the END keyword. The reader is left at
+        the start of the data unit that follows the header.
+    */
+    let (mut hbs, mut end) = (Vec::<HeaderBlock>::new(), false);
+    let mut hb_buf = vec![0u8; BLOCK_SIZE];
+    while !end {
+      reader.read_exact(&mut hb_buf)?;
+      let (hb, finished) = HeaderBlock::decode_from_bytes(&hb_buf, ParseMode::Strict)?;
+      hbs.push(hb);
+      end = finished;
+    }
+
+    let block_len = hbs.len();
+    Self::from_parts(hbs, block_len, ParseMode::Strict)
+  }
+
+  fn from_parts(
+    hbs: Vec<HeaderBlock>,
+    block_len: usize,
+    mode: ParseMode,
+  ) -> Result<Self, Box<dyn Error>> {
     //Parse the Keywordrecords to plain Key-Data pairs
     let mut parsed_map: IndexMap<Rc<String>, KeywordRecord> = IndexMap::new();
 
@@ -91,13 +132,20 @@ impl Header {
             //Should never panic... hopefully
             let last_parsed = parsed_map.get_mut(&last_keyword).unwrap();
 
-            //(1) remove the trailing {'&} from the previous record's
+            //(1) remove the trailing {&'} from the previous record's
             //value
             last_parsed.value.as_mut().unwrap().pop();
             last_parsed.value.as_mut().unwrap().pop();
 
-            //(2) append the continued value
-            last_parsed.value.as_mut().unwrap().push_str(unparsed_record.value.unwrap().as_str());
+            //(2) append the continued value (without its opening quote)
+            let continued = unparsed_record.value.unwrap_or_default();
+            let continued = continued.strip_prefix('\'').unwrap_or(&continued);
+            last_parsed.value.as_mut().unwrap().push_str(continued);
+
+            //(2b) the comment of a long string is on its last record
+            if unparsed_record.comment.is_some() {
+              last_parsed.comment = unparsed_record.comment;
+            }
 
             //(3) do not append keyword-record pair as separate entry
             continue;
@@ -105,11 +153,24 @@ impl Header {
           _ => {} //do nothing
         }
 
+        //The keywords describing the data unit may only appear once. In
+        //lenient mode the last one wins
+        let keyword = unparsed_record.keyword.as_str();
+        if let Some(key) = UNIQUE_KEYWORDS.into_iter().find(|key| *key == keyword) {
+          if parsed_map.contains_key(&unparsed_record.keyword) {
+            let err = InvalidHeaderErr::DuplicateKeyword { key };
+            match mode {
+              ParseMode::Strict => return Err(Box::new(err)),
+              ParseMode::Lenient => log::warn!("{err}, using the last value"),
+            }
+          }
+        }
+
         //update last keyword
         last_keyword = (*unparsed_record.keyword).clone();
 
         //and add our beautiful string
-        parsed_map.insert(unparsed_record.keyword.clone(), unparsed_record);
+        Self::insert_record(&mut parsed_map, unparsed_record);
       }
     }
 
@@ -119,15 +180,7 @@ impl Header {
   pub fn encode_header(self, writer: &mut RawFitsWriter) -> Result<(), Box<dyn Error>> {
     //Buffer to write whole header in one go.
     //Also keeps track of number of bytes we wrote to the header!
-    let mut buf = Vec::new();
-
-    for (_, record) in self.records {
-      record.encode_fill_buff(&mut buf)?;
-    }
-
-    //We musn't forget to add an END keyword!
-    KeywordRecord { keyword: Rc::new(String::from("END     ")), value: None, comment: None }
-      .encode_fill_buff(&mut buf)?;
+    let mut buf = self.encode_records()?;
 
     //make sure that the size of the whole header is an integer multiple
     //of the block size. Btw we fill it with spaces not zeroes
@@ -142,6 +195,57 @@ impl Header {
     Ok(())
   }
 
+  pub fn to_keyword_records(&self) -> Result<Vec<[u8; RECORD_SIZE]>, Box<dyn Error>> {
+    /*
+        Encodes the header as 80-byte keyword records, with the mandatory
+        keywords in the order prescribed by the standard. The last record is
+        the END keyword. The padding up to a whole FITS block is left out.
+    */
+    let buf = self.clone().encode_records()?;
+    Ok(buf.chunks_exact(RECORD_SIZE).map(|record| record.try_into().unwrap()).collect())
+  }
+
+  pub fn from_keyword_records(records: &[[u8; RECORD_SIZE]]) -> Result<Self, Box<dyn Error>> {
+    //Inverse of to_keyword_records(). Records after the END keyword are
+    //ignored, and a missing END keyword is allowed
+    let mut parsed = Vec::new();
+    for bytes in records {
+      let record = KeywordRecord::decode_from_bytes(bytes, ParseMode::Strict)?;
+      if *record.keyword == "END" {
+        break;
+      }
+      parsed.push(record);
+    }
+
+    let mut header = Self::from_parts(vec![HeaderBlock { records: parsed }], 0, ParseMode::Strict)?;
+    header.update_block_len();
+    Ok(header)
+  }
+
+  fn encode_records(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+    //The standard requires the mandatory keywords to come first
+    self.sort_mandatory_keywords();
+
+    let mut buf = Vec::new();
+    for (_, record) in self.records {
+      record.encode_fill_buff(&mut buf)?;
+    }
+
+    //We musn't forget to add an END keyword!
+    KeywordRecord { keyword: Rc::new(String::from("END     ")), value: None, comment: None }
+      .encode_fill_buff(&mut buf)?;
+    Ok(buf)
+  }
+
+  pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+    //Writes the header exactly like encode_header does: all records, the
+    //END keyword and padding up to a whole number of FITS blocks
+    let mut raw = RawFitsWriter::in_memory();
+    self.clone().encode_header(&mut raw)?;
+    writer.write_all(&raw.into_bytes())?;
+    Ok(())
+  }
+
   pub(crate) fn new() -> Self {
     /*
         Creates new empty header.
@@ -157,6 +261,239 @@ impl Header {
     return header;
   }
 
+  pub(crate) fn new_empty_extension(extname: &str, meta: Option<Header>) -> Self {
+    /*
+        Creates the header of an IMAGE extension without data (NAXIS = 0).
+        These are used as named metadata containers. The mandatory keywords
+        are always set by this func, all other records are copied over from
+        the supplied metadata.
+    */
+    let mut header = Header { records: IndexMap::new(), block_len: 0 };
+
+    //(1) Mandatory keywords, in the order required by the standard
+    header.set_record(
+      "XTENSION",
+      String::from("'IMAGE   '"),
+      Some(String::from("Image extension")),
+    );
+    header.set_record("BITPIX", String::from("8"), None);
+    header.set_record("NAXIS", String::from("0"), Some(String::from("no data")));
+    header.set_record("PCOUNT", String::from("0"), None);
+    header.set_record("GCOUNT", String::from("1"), None);
+    header.set_record("EXTNAME", quote_fits_string(extname), None);
+
+    //(2) Copy the user-supplied records that do not describe the data
+    if let Some(meta) = meta {
+      header.copy_meta(meta);
+    }
+
+    //(3) We created the header, so we should indicate that!
+    header.update_last_modified();
+
+    //(R) the header, with its size in FITS blocks
+    header.update_block_len();
+    header
+  }
+
+  fn is_meta(keyword: &str) -> bool {
+    //Metadata records are all records that do not describe the data unit
+    !STRUCTURAL_KEYWORDS.contains(&keyword) && !keyword.starts_with("NAXIS")
+  }
+
+  pub(crate) fn copy_meta(&mut self, meta: Header) {
+    //Copies the records of meta that do not describe a data unit
+    for (keyword, record) in meta.records {
+      if !Self::is_meta(&keyword) {
+        continue;
+      }
+      Self::insert_record(&mut self.records, record);
+    }
+    self.update_block_len();
+  }
+
+  pub(crate) fn take_meta(&mut self) -> Header {
+    //Moves the records that do not describe the data unit to a new header
+    let mut meta = Header { records: IndexMap::new(), block_len: 0 };
+    let (taken, kept) = self.records.drain(..).partition(|(keyword, _)| Self::is_meta(keyword));
+    (meta.records, self.records) = (taken, kept);
+    meta.update_block_len();
+    self.update_block_len();
+    meta
+  }
+
+  pub(crate) fn has_meta(&self) -> bool {
+    self.records.keys().any(|keyword| Self::is_meta(keyword))
+  }
+
+  pub(crate) fn inherit(&mut self, primary: &Header) {
+    /*
+        Copies the records of the primary header that are missing in this
+        header. Records describing the primary data unit are not inherited,
+        and neither is commentary (COMMENT, HISTORY and blank keywords).
+    */
+    for (keyword, record) in &primary.records {
+      if record.is_commentary()
+        || keyword.is_empty()
+        || STRUCTURAL_KEYWORDS.contains(&keyword.as_str())
+        || keyword.starts_with("NAXIS")
+        || self.records.contains_key(keyword)
+      {
+        continue;
+      }
+      self.records.insert(keyword.clone(), record.clone());
+    }
+    self.update_block_len();
+  }
+
+  pub(crate) fn new_primary_image(bitpix: Bitpix, shape: &[usize]) -> Self {
+    //Creates the minimal primary header for an image with the given shape
+    let mut header = Header { records: IndexMap::new(), block_len: 0 };
+    header.set_record("SIMPLE", String::from("T"), Some(String::from("conforms to FITS standard")));
+    header.set_record("BITPIX", bitpix.to_code().to_string(), None);
+    header.set_record("NAXIS", shape.len().to_string(), None);
+    for (i, axis) in shape.iter().enumerate() {
+      header.set_record(&format!("NAXIS{}", i + 1), axis.to_string(), None);
+    }
+
+    header.update_block_len();
+    header
+  }
+
+  /*
+      Headers with only the mandatory keywords of an image, to start new HDU's
+      from. BITPIX codes are checked, see Bitpix.
+  */
+  pub fn for_primary_image(shape: &[usize], bitpix: i8) -> Result<Self, InvalidBitpixErr> {
+    Ok(Self::new_primary_image(Bitpix::try_from(bitpix)?, shape))
+  }
+
+  pub fn for_extension_image(
+    extname: &str,
+    shape: &[usize],
+    bitpix: i8,
+  ) -> Result<Self, InvalidBitpixErr> {
+    let mut header = Self::new_primary_image(Bitpix::try_from(bitpix)?, shape);
+    header.make_extension();
+    header.set_record("EXTNAME", quote_fits_string(extname), None);
+    header.update_block_len();
+    Ok(header)
+  }
+
+  fn mandatory_keywords(&self) -> Vec<String> {
+    /*
+        Lists the mandatory keywords of this header in the order prescribed by
+        the FITS standard. Which keywords are mandatory depends on the kind
+        of HDU (primary, random groups or extension) and on NAXIS.
+    */
+    let mut keywords = Vec::new();
+    let is_extension = self.get_value("XTENSION").is_some();
+
+    //(1) These keywords are shared by all HDU's
+    keywords.push(String::from(if is_extension { "XTENSION" } else { "SIMPLE" }));
+    keywords.push(String::from("BITPIX"));
+    keywords.push(String::from("NAXIS"));
+    let naxis: usize = self.get_value_as("NAXIS").unwrap_or(0);
+    for i in 1..=naxis {
+      keywords.push(format!("NAXIS{i}"));
+    }
+
+    //(2) Extension and random groups specific keywords
+    if is_extension {
+      keywords.push(String::from("PCOUNT"));
+      keywords.push(String::from("GCOUNT"));
+      keywords.push(String::from("TFIELDS")); //only present for tables
+    } else if self.get_value("GROUPS").is_some() {
+      keywords.push(String::from("GROUPS"));
+      keywords.push(String::from("PCOUNT"));
+      keywords.push(String::from("GCOUNT"));
+    }
+
+    keywords
+  }
+
+  pub(crate) fn sort_mandatory_keywords(&mut self) {
+    //Moves the mandatory keywords to the front of the header, the order of
+    //the remaining keywords is left untouched
+    let mut position = 0;
+    for keyword in self.mandatory_keywords() {
+      if let Some(index) = self.records.get_index_of(&keyword) {
+        self.records.move_index(index, position);
+        position += 1;
+      }
+    }
+  }
+
+  pub(crate) fn make_extension(&mut self) {
+    /*
+        Turns the header of a primary HDU into the header of an IMAGE
+        extension, such that the HDU can be appended to another file. The
+        headers of extensions are left untouched.
+    */
+    if self.remove_record("SIMPLE").is_none() {
+      return;
+    }
+    self.remove_record("EXTEND");
+    self.set_record("XTENSION", String::from("'IMAGE   '"), Some(String::from("Image extension")));
+    self.set_record("PCOUNT", String::from("0"), None);
+    self.set_record("GCOUNT", String::from("1"), None);
+    self.sort_mandatory_keywords();
+    self.update_block_len();
+  }
+
+  pub(crate) fn make_primary(&mut self) {
+    /*
+        Inverse of make_extension(): turns the header of an IMAGE extension
+        into the header of a primary HDU, such that the HDU can be written to
+        a file of its own. INHERIT has no meaning for primary HDU's.
+    */
+    if self.remove_record("XTENSION").is_none() {
+      return;
+    }
+    for keyword in ["PCOUNT", "GCOUNT", "INHERIT"] {
+      self.remove_record(keyword);
+    }
+    self.set_record("SIMPLE", String::from("T"), Some(String::from("conforms to FITS standard")));
+    self.sort_mandatory_keywords();
+    self.update_block_len();
+  }
+
+  fn insert_record(records: &mut IndexMap<Rc<String>, KeywordRecord>, record: KeywordRecord) {
+    /*
+        Records are stored by their keyword, except for commentary records
+        (COMMENT and HISTORY) which may appear more than once. These are
+        stored under a unique key instead, such that they are not replaced.
+        The key contains a space, which keywords cannot contain.
+    */
+    let key = match record.is_commentary() {
+      false => record.keyword.clone(),
+      true => (records.len()..)
+        .map(|n| Rc::new(format!("{} {n}", record.keyword)))
+        .find(|key| !records.contains_key(key))
+        .unwrap(),
+    };
+    records.insert(key, record);
+  }
+
+  fn insert_commentary(&mut self, keyword: &str, text: &str) {
+    //Text that does not fit in a single record is spread over several records
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks: Vec<String> = chars.chunks(COMMENTARY_LEN).map(String::from_iter).collect();
+    if chunks.is_empty() {
+      chunks.push(String::new()); //empty records are allowed too
+    }
+    for chunk in chunks {
+      Self::insert_record(&mut self.records, KeywordRecord::new_commentary(keyword, &chunk));
+    }
+    self.update_block_len();
+  }
+
+  pub(crate) fn update_block_len(&mut self) {
+    //Size of the encoded header in FITS blocks (all records + END keyword)
+    let n_records: usize = self.records.values().map(KeywordRecord::n_records).sum();
+    let n_bytes = (n_records + 1) * RECORD_SIZE;
+    self.block_len = n_bytes.div_ceil(BLOCK_SIZE);
+  }
+
   /*
       Some getters for full records and single values or comments (just some
       utility funcs)
@@ -179,6 +516,92 @@ impl Header {
     }
   }
 
+  /*
+      Users may add, replace and remove records, except for the restricted
+      records that describe the data unit (see KeywordRecord). Values are
+      stored as they appear in the header, so strings have to be quoted
+      (see quote_fits_string). Added keywords have to follow the standard,
+      see validate_keyword_name().
+  */
+  pub fn insert_with_comment(
+    &mut self,
+    keyword: &str,
+    value: &str,
+    comment: &str,
+  ) -> Result<(), InvalidKeywordErr> {
+    validate_keyword_name(keyword)?;
+    let record = KeywordRecord::new(keyword, Some(value.to_string()), Some(comment.to_string()))?;
+    Self::insert_record(&mut self.records, record);
+    self.update_block_len();
+    Ok(())
+  }
+
+  /*
+      COMMENT and HISTORY records may appear more than once. New records are
+      appended to the end of the header, rather than replacing earlier ones.
+  */
+  pub fn insert_comment(&mut self, text: &str) {
+    self.insert_commentary("COMMENT", text);
+  }
+
+  pub fn insert_history(&mut self, text: &str) {
+    self.insert_commentary("HISTORY", text);
+  }
+
+  pub fn get_all(&self, keyword: &str) -> Vec<String> {
+    //Values of all records with this keyword, in the order in which they
+    //appear in the header. The values of commentary records are their text
+    self
+      .records
+      .values()
+      .filter(|record| *record.keyword == keyword)
+      .filter_map(|record| match record.is_commentary() {
+        true => record.comment.clone(),
+        false => record.value.clone(),
+      })
+      .collect()
+  }
+
+  pub fn remove(&mut self, keyword: &str) -> Result<Option<String>, InvalidKeywordErr> {
+    //Removes a record (keeping the order of the others), returns its value
+    KeywordRecord::new(keyword, None, None)?;
+    let removed = self.remove_record(keyword);
+    self.update_block_len();
+    Ok(removed.and_then(|record| record.value))
+  }
+
+  pub(crate) fn set_record(&mut self, keyword: &str, value: String, comment: Option<String>) {
+    //Inserts a keyword record, replacing the existing record if there is one.
+    //pub(crate) since this func does not check for restricted keywords!
+    let key = Rc::new(String::from(keyword));
+    self.records.insert(key.clone(), KeywordRecord::from_string(key, value, comment));
+  }
+
+  pub(crate) fn set_value(&mut self, keyword: &str, value: String) {
+    //Same as set_record, but keeps the comment of existing records
+    match self.records.get_mut(&keyword.to_string()) {
+      Some(record) => record.value = Some(value),
+      None => self.set_record(keyword, value, None),
+    }
+  }
+
+  pub(crate) fn remove_record(&mut self, keyword: &str) -> Option<KeywordRecord> {
+    //Removes a record, keeping the order of the remaining records. All
+    //commentary records with this keyword are removed, the last is returned
+    if !COMMENTARY.contains(&keyword) {
+      return self.records.shift_remove(&keyword.to_string());
+    }
+    let mut removed = None;
+    self.records.retain(|_, record| {
+      let keep = *record.keyword != keyword;
+      if !keep {
+        removed = Some(record.clone());
+      }
+      keep
+    });
+    removed
+  }
+
   pub(crate) fn update_last_modified(&mut self) {
     /*
         This function modifies the DATE keyword in the primary header which
@@ -190,7 +613,7 @@ impl Header {
     */
     let now = Utc::now();
     let now_fmtd = format!(
-      "{:0000}/{:00}/{:00}", //yyyy/mm/dd format as specified in standard
+      "'{:04}-{:02}-{:02}'", //yyyy-mm-dd format as specified in standard
       now.year(),
       now.month(),
       now.day()
@@ -218,9 +641,171 @@ impl Header {
     }
   }
 
+  pub(crate) fn get_data_block_len(&self) -> Result<usize, Box<dyn Error>> {
+    /*
+        Calculates the size of the data unit described by this header in
+        FITS blocks, using the formula from the standard:
+            #bits = |BITPIX| * GCOUNT * (PCOUNT + NAXIS1 * ... * NAXISn)
+        For random groups NAXIS1 = 0, and it is left out of the product.
+    */
+    let mut axes = self.get_axes()?;
+    if axes.is_empty() {
+      return Ok(0);
+    }
+    if axes[0] == 0 {
+      axes.remove(0); //random groups
+    }
+
+    let bytes_per_entry = self.get_value_as::<isize>("BITPIX")?.unsigned_abs() / 8;
+    let pcount: usize = self.get_value_as("PCOUNT").unwrap_or(0);
+    let gcount: usize = self.get_value_as("GCOUNT").unwrap_or(1);
+    let n_bytes = bytes_per_entry * gcount * (pcount + axes.iter().product::<usize>());
+
+    Ok(n_bytes.div_ceil(BLOCK_SIZE))
+  }
+
+  pub(crate) fn get_axes(&self) -> Result<Vec<usize>, Box<dyn Error>> {
+    //Returns the values of NAXIS1 up to NAXISn. Superfluous NAXISn keywords
+    //do not change the shape of the data unit, missing ones do
+    let naxis: usize = self.get_value_as("NAXIS")?;
+    match self.check_naxis() {
+      Err(err @ InvalidHeaderErr::NaxisShapeMismatch { found, .. }) if found < naxis => {
+        return Err(Box::new(err))
+      }
+      _ => {}
+    }
+
+    let mut axes = Vec::new();
+    for i in 1..=naxis {
+      axes.push(self.get_value_as::<usize>(&format!("NAXIS{i}"))?);
+    }
+    Ok(axes)
+  }
+
+  pub(crate) fn check_naxis(&self) -> Result<(), InvalidHeaderErr> {
+    //Checks that there are exactly NAXIS NAXISn keywords (n >= 1). Headers
+    //without a valid NAXIS keyword are left to the other checks
+    let Some(declared) = self.get_value("NAXIS").and_then(|val| val.parse::<u32>().ok()) else {
+      return Ok(());
+    };
+    let found = self
+      .records
+      .keys()
+      .filter_map(|keyword| keyword.strip_prefix("NAXIS")?.parse::<usize>().ok())
+      .filter(|axis| *axis >= 1)
+      .count();
+    match found == declared as usize {
+      true => Ok(()),
+      false => Err(InvalidHeaderErr::NaxisShapeMismatch { declared, found }),
+    }
+  }
+
   pub fn get_num_records(&self) -> usize {
     self.records.len()
   }
+
+  pub fn contains(&self, keyword: &str) -> bool {
+    match COMMENTARY.contains(&keyword) {
+      false => self.records.contains_key(&keyword.to_string()),
+      true => self.records.values().any(|record| *record.keyword == keyword),
+    }
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str, Option<&str>)> {
+    //Iterates over (keyword, value, comment) triples in the order in which
+    //they appear in the header. Records without value yield an empty value
+    self.records.values().map(|record| {
+      (record.keyword.as_str(), record.value.as_deref().unwrap_or(""), record.comment.as_deref())
+    })
+  }
+
+  /*
+      Typed getters. These return None if the keyword is missing or if its
+      value does not have the requested type.
+  */
+  pub fn get_int(&self, keyword: &str) -> Option<i64> {
+    self.get_value(keyword)?.parse().ok()
+  }
+
+  pub fn get_float(&self, keyword: &str) -> Option<f64> {
+    //Fortran-style exponents (1.0D+03) are allowed in the header
+    self.get_value(keyword)?.replace(['D', 'd'], "E").parse().ok()
+  }
+
+  pub fn get_bool(&self, keyword: &str) -> Option<bool> {
+    match self.get_value(keyword)?.as_str() {
+      "T" => Some(true),
+      "F" => Some(false),
+      _ => None,
+    }
+  }
+
+  pub fn get_string(&self, keyword: &str) -> Option<String> {
+    //Strings are quoted, and may be padded with trailing spaces
+    let value = self.get_value(keyword)?;
+    if !value.starts_with('\'') {
+      return None;
+    }
+    Some(unquote_fits_string(value).into_owned())
+  }
+
+  pub fn get_datetime(&self, keyword: &str) -> Option<NaiveDateTime> {
+    //Both ISO-8601 and old-style (DD/MM/YY) dates, see parse_fits_datetime()
+    parse_fits_datetime(self.get_value(keyword)?)
+  }
+}
+
+/*
+    Headers are (de)serialized as a map of keywords to {value, comment}
+    objects, in the order in which they appear in the header. Deserializing
+    restores this order, so the header can be encoded again.
+*/
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeRecord {
+  value: Option<String>,
+  comment: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Header {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeMap;
+    let mut map = serializer.serialize_map(Some(self.records.len()))?;
+    for (keyword, record) in &self.records {
+      let record = SerdeRecord { value: record.value.clone(), comment: record.comment.clone() };
+      map.serialize_entry(keyword.as_str(), &record)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Header {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    //An IndexMap keeps the entries in the order they were deserialized in
+    let map = IndexMap::<String, SerdeRecord>::deserialize(deserializer)?;
+    let mut header = Header { records: IndexMap::new(), block_len: 0 };
+    for (key, record) in map {
+      //Commentary records are serialized under their unique key
+      let keyword = Rc::new(key.split(' ').next().unwrap_or_default().to_string());
+      let record = KeywordRecord { keyword, value: record.value, comment: record.comment };
+      Self::insert_record(&mut header.records, record);
+    }
+    header.update_block_len();
+    Ok(header)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Header {
+  pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+    Ok(serde_json::to_string(self)?)
+  }
+
+  pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+    Ok(serde_json::from_str(json)?)
+  }
 }
 
 impl BlockSized for Header {
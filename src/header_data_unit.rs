@@ -18,25 +18,85 @@
 */
 
 use core::fmt;
-use std::{borrow::Cow, error::Error, fmt::Display};
+use std::{
+  borrow::Cow,
+  cell::OnceCell,
+  error::Error,
+  fmt::Display,
+  path::{Path, PathBuf},
+};
+
+use ndarray::{Array, ArrayViewD, ArrayViewMutD, IxDyn};
 
 use crate::{
   bitpix::Bitpix,
-  extensions::{image::ImgParser, table::AsciiTblParser, Extension},
+  checksum_err::ChecksumMismatchErr,
+  extensions::{
+    groups::GroupsParser,
+    image::{ImgParser, Pixel, ScalingMode, TypedImage},
+    table::{AsciiTable, AsciiTblParser, BinFieldMeta, BinTblParser},
+    Extension,
+  },
   hdu_err::*,
   header::Header,
+  observation::Observation,
+  options::{FitsReadOptions, ParseMode},
   raw::{
+    checksum,
+    keyword_utils::unquote_fits_string,
     raw_io::{RawFitsReader, RawFitsWriter},
     BlockSized,
   },
+  wcs::Wcs,
 };
 
+#[cfg(feature = "rice")]
+use crate::extensions::image::CompressedImage;
+
 const VALID_EXTENSION_NAMES: [&'static str; 3] = ["'IMAGE   '", "'TABLE   '", "'BINTABLE'"];
 
 #[derive(Debug, Clone)]
 pub struct HeaderDataUnit {
   header: Header,
   data: Option<Extension>,
+  //Checksums of the header and data bytes this HDU was decoded from
+  read_sums: Option<(u32, u32)>,
+  //Data that is only decoded once it is accessed (see Fits::open_lazy)
+  deferred: Option<DeferredData>,
+}
+
+#[derive(Debug, Clone)]
+struct DeferredData {
+  /*
+      Location of a data unit that has not been decoded yet. Deferred data is
+      always decoded without scaling, so that the header (which has already
+      been read) stays valid. Data that can no longer be read from the file
+      is decoded as Extension::Corrupted.
+  */
+  path: PathBuf,
+  start_block: usize,
+  data: OnceCell<Option<Extension>>,
+}
+
+impl DeferredData {
+  fn get(&self) -> Option<&Extension> {
+    self.data.get_or_init(|| Self::decode(&self.path, self.start_block)).as_ref()
+  }
+
+  fn into_data(self) -> Option<Extension> {
+    let (path, start_block) = (self.path, self.start_block);
+    self.data.into_inner().unwrap_or_else(|| Self::decode(&path, start_block))
+  }
+
+  fn decode(path: &Path, start_block: usize) -> Option<Extension> {
+    let decode = || -> Result<Option<Extension>, Box<dyn Error>> {
+      let mut reader = RawFitsReader::new(path)?;
+      reader.seek_to_block(start_block)?;
+      let options = FitsReadOptions::new().scaling(ScalingMode::Raw);
+      Ok(HeaderDataUnit::decode_hdu(&mut reader, options)?.data)
+    };
+    decode().unwrap_or(Some(Extension::Corrupted))
+  }
 }
 
 impl HeaderDataUnit {
@@ -44,47 +104,228 @@ impl HeaderDataUnit {
       INTERNAL CODE
   */
 
-  pub(crate) fn decode_hdu(raw: &mut RawFitsReader) -> Result<Self, Box<dyn Error>> {
+  pub(crate) fn decode_hdu(
+    raw: &mut RawFitsReader,
+    options: FitsReadOptions,
+  ) -> Result<Self, Box<dyn Error>> {
     //(1) Read the header
-    let header = Header::decode_header(raw)?;
+    let mut header = Header::decode_header(raw, options.parse_mode)?;
+    let header_sum = raw.take_checksum();
+
+    //(2) Read the data, converting it to physical values (if requested)
+    let (extension, rescaled) = Self::decode_data(raw, &mut header, options)?;
+
+    //(3) Keep the checksums if the reader keeps track of them, so we can
+    //    verify the CHECKSUM and DATASUM keywords later on. Rescaled data no
+    //    longer matches these keywords, so they are verified right away (if
+    //    requested) and removed
+    let mut read_sums = header_sum.map(|header_sum| (header_sum, raw.take_checksum().unwrap_or(0)));
+    if rescaled {
+      if let (true, Some((header_sum, data_sum))) = (options.verify_checksums, read_sums) {
+        Self::verify_checksums(&header, header_sum, data_sum)?;
+      }
+      Self::remove_checksums(&mut header);
+      read_sums = None;
+    }
+
+    //(R) return complete HDU
+    Ok(HeaderDataUnit { header, data: extension, read_sums, deferred: None })
+  }
+
+  pub(crate) fn decode_into(
+    &mut self,
+    raw: &mut RawFitsReader,
+    buffer: &mut Vec<u8>,
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        Same as decode_hdu(), but the HDU replaces the contents of self. The
+        data unit is read into buffer first. Images with the same type and
+        shape as the image in self are decoded into its pixels, all other
+        data is decoded from the buffer. Nothing changes on errors.
+    */
+    //(1) Read the header and the bytes of the data unit
+    let options = FitsReadOptions::new();
+    let mut header = Header::decode_header(raw, options.parse_mode)?;
+    let header_sum = raw.take_checksum();
+    buffer.clear();
+    buffer.resize(header.get_data_block_len()? * crate::BLOCK_SIZE, 0);
+    raw.read_blocks(buffer)?;
+    let mut read_sums = header_sum.map(|header_sum| (header_sum, raw.take_checksum().unwrap_or(0)));
+
+    //(2) Decode the data into the pixels we already have, if we can
+    let reuse = match (&self.data, &self.deferred) {
+      (Some(Extension::Image(img)), None) => Self::fits_image(&header, img)?,
+      _ => false,
+    };
+    match (reuse, &mut self.data) {
+      (true, Some(Extension::Image(img))) => ImgParser::decode_img_into(buffer, img),
+      _ => {
+        let mut reader = RawFitsReader::from_bytes(buffer)?;
+        let (data, rescaled) = Self::decode_data(&mut reader, &mut header, options)?;
+        if rescaled {
+          Self::remove_checksums(&mut header);
+          read_sums = None;
+        }
+        self.data = data;
+      }
+    }
+
+    //(R) the new header describes the new data
+    self.header = header;
+    self.read_sums = read_sums;
+    self.deferred = None;
+    Ok(())
+  }
 
-    //(2) Read data, if there is any
+  fn fits_image(header: &Header, img: &TypedImage) -> Result<bool, Box<dyn Error>> {
+    /*
+        True if the data described by header is an image that can be decoded
+        into img: it must have the same type and shape, and it must not be
+        scaled (scaled images are f64 images, see scale_img).
+    */
+    let is_image = match header.get_value("XTENSION") {
+      None => header.get_value("GROUPS").is_none(),
+      Some(xtension) => xtension.as_str() == "'IMAGE   '",
+    };
+    if !is_image || header.contains("BSCALE") || header.contains("BZERO") {
+      return Ok(false);
+    }
+    let bitpix = Bitpix::try_from(header.get_value_as::<i64>("BITPIX")?)?;
+    Ok(bitpix == img.bpx() && header.get_axes()? == *img.get_shape())
+  }
+
+  fn decode_data(
+    raw: &mut RawFitsReader,
+    header: &mut Header,
+    options: FitsReadOptions,
+  ) -> Result<(Option<Extension>, bool), Box<dyn Error>> {
+    //(1) Read data, if there is any. We also report if it was rescaled
+    let FitsReadOptions { parse_mode, scaling, .. } = options;
     let extension = match &header.get_value("XTENSION") {
       None => {
-        /*  (2a)
+        /*  (1a)
             This is the primary header (or there is simply no data in
             this hdu). This means that this HDU may contain random
-            groups. Random groups and emtpy arrays have the NAXIS
-            keyword set to zero.
+            groups. Empty arrays have the NAXIS keyword set to zero,
+            random groups have GROUPS = T and NAXIS1 set to zero.
         */
         if header.get_value_as::<usize>("NAXIS")? == 0 {
-          //For now I'll just return None rather than implement random
-          //groups
           None
+        } else if header.get_value("GROUPS").map(|val| val.as_str()) == Some("T")
+          && header.get_value_as::<usize>("NAXIS1")? == 0
+        {
+          Some(Self::read_groups(raw, header)?)
         } else {
           //Image
-          Some(Self::read_img(raw, &header)?)
+          Some(Self::read_img(raw, header)?)
         }
       }
       Some(extension_type) => {
-        /*  (2b)
+        /*  (1b)
             This is not a primary header, but the header of an extension
             hdu.
         */
         match extension_type.as_str() {
-          "'IMAGE   '" => Some(Self::read_img(raw, &header)?),
-          _kw @ "'TABLE   '" => Some(Self::read_table(raw, &header)?),
-          kw @ "'BINTABLE'" => Err(Self::not_impl(kw))?,
+          "'IMAGE   '" => {
+            //IMAGE extensions with NAXIS = 0 are metadata-only
+            if header.get_value_as::<usize>("NAXIS")? == 0 {
+              None
+            } else {
+              Some(Self::read_img(raw, header)?)
+            }
+          }
+          _kw @ "'TABLE   '" => Some(Self::read_table(raw, header, scaling, parse_mode)?),
+          _kw @ "'BINTABLE'" => Some(Self::read_bintable(raw, header, parse_mode)?),
           kw => Err(InvalidRecordValueError::new("XTENSION", kw, &VALID_EXTENSION_NAMES))?,
         }
       }
     };
 
-    //(3) return complete HDU
-    Ok(HeaderDataUnit { header: header, data: extension })
+    //(1c) Decompressed images get the header of the original image back
+    #[cfg(feature = "rice")]
+    if let Some(Extension::Image(_)) = extension {
+      CompressedImage::restore_header(header);
+    }
+
+    //(2) Convert images to physical values (if requested). Tables have
+    //    already been scaled, so they no longer need the scaling keywords
+    let mut rescaled = false;
+    let extension = match extension {
+      Some(Extension::Image(img)) => {
+        let (img, scaled) = Self::scale_img(img, header, scaling)?;
+        rescaled = scaled;
+        Some(Extension::Image(img))
+      }
+      Some(Extension::AsciiTable(tbl)) => {
+        let nfields = header.get_value_as("TFIELDS")?;
+        for (i, scale) in Self::read_tbl_scales(header, nfields, scaling)?.iter().enumerate() {
+          if *scale != (1.0, 0.0) {
+            header.remove_record(&format!("TSCAL{}", i + 1));
+            header.remove_record(&format!("TZERO{}", i + 1));
+            rescaled = true;
+          }
+        }
+        Some(Extension::AsciiTable(tbl))
+      }
+      other => other,
+    };
+
+    Ok((extension, rescaled))
+  }
+
+  fn remove_checksums(header: &mut Header) {
+    //The checksums of rescaled data no longer match the data we'd write
+    header.remove_record("CHECKSUM");
+    header.remove_record("DATASUM");
   }
 
-  fn read_table(raw: &mut RawFitsReader, header: &Header) -> Result<Extension, Box<dyn Error>> {
+  fn verify_checksums(
+    header: &Header,
+    header_sum: u32,
+    data_sum: u32,
+  ) -> Result<(), ChecksumMismatchErr> {
+    /*
+        Both the CHECKSUM and DATASUM keywords are optional. HDU's without
+        them cannot be verified, so we just skip those.
+    */
+    if let Some(stored) = header.get_value("DATASUM") {
+      //DATASUM is stored as a string containing an unsigned integer
+      let stored_sum = unquote_fits_string(stored).trim().parse::<u32>().ok();
+      if stored_sum != Some(data_sum) {
+        return Err(ChecksumMismatchErr::new("DATASUM", stored, data_sum));
+      }
+    }
+
+    if let Some(stored) = header.get_value("CHECKSUM") {
+      //The 1's complement sum of a HDU with a valid CHECKSUM is always -0
+      let hdu_sum = checksum::ones_add(header_sum, data_sum);
+      if hdu_sum != checksum::VALID_HDU_SUM {
+        return Err(ChecksumMismatchErr::new("CHECKSUM", stored, hdu_sum));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn check_table_bitpix(bitpix: isize, mode: ParseMode) -> Result<(), InvalidRecordValueError> {
+    //Tables always consist of bytes. Lenient mode also accepts tables with
+    //another (valid) BITPIX, since they are decoded bytewise anyway
+    match bitpix {
+      8 => Ok(()),
+      _ if mode == ParseMode::Lenient && Bitpix::try_from(bitpix as i64).is_ok() => {
+        log::warn!("table has BITPIX = {bitpix} rather than 8, decoded as bytes anyway");
+        Ok(())
+      }
+      _ => Err(InvalidRecordValueError::new("BITPIX", &format!("{bitpix}"), &["8"])),
+    }
+  }
+
+  fn read_table(
+    raw: &mut RawFitsReader,
+    header: &Header,
+    scaling: ScalingMode,
+    mode: ParseMode,
+  ) -> Result<Extension, Box<dyn Error>> {
     /*
         To parse a table we need to know the following keywords:
             TFIELDS => #fields in a row
@@ -93,6 +334,9 @@ impl HeaderDataUnit {
             TBCOL{i} => starting index of field i
             TFORM{i} => data format of field i
             TTYPE{i} => name of field i (not required)
+            TUNIT{i} => physical unit of field i (not required)
+            TSCAL{i}, TZERO{i} => linear scaling of field i (not required)
+            TNULL{i} => undefined value of field i (not required)
         In addition, we require the following keywords to have been set to:
             NAXIS == 2
             BITPIX == 8
@@ -110,9 +354,7 @@ impl HeaderDataUnit {
     if naxis != 2 {
       Err(InvalidRecordValueError::new("NAXIS", &format!("{naxis}"), &["2"]))?
     }
-    if bitpix != 8 {
-      Err(InvalidRecordValueError::new("BITPIX", &format!("{bitpix}"), &["8"]))?
-    }
+    Self::check_table_bitpix(bitpix, mode)?;
     if pcount != 0 {
       Err(InvalidRecordValueError::new("PCOUNT", &format!("{pcount}"), &["0"]))?
     }
@@ -144,9 +386,10 @@ impl HeaderDataUnit {
       Some(_) => {
         /*
             This header contains descriptive keywords for the entries
-            in the table. Note that the TTYPE{i} keywords are themselves
-            keywords, and the actual desciptions of the columns are
-            stored in the header behind these keywords.
+            in the table. Some files (HST) use the TTYPE{i} values as
+            keywords themselves, with the actual desciptions of the columns
+            stored in the header behind these keywords. If there is no such
+            keyword, the TTYPE{i} value is the label itself.
         */
         let mut tmp: Vec<String> = Vec::new();
         for i in 1..=nfields {
@@ -156,18 +399,26 @@ impl HeaderDataUnit {
           //Before we return, we query keywords we've found so far
           tmp
             .into_iter()
-            .map(|mut ttype_keyword| {
+            .map(|ttype_keyword| {
               //We still have to strip the keyword of its annoying
               //{'keyword   '} syntax
-              ttype_keyword.remove(0);
-              ttype_keyword.pop();
-              header.get_value_as(ttype_keyword.trim())
+              let ttype = unquote_fits_string(&ttype_keyword);
+              match header.get_value(ttype.trim()) {
+                Some(description) => description.clone(),
+                None => ttype.into_owned(),
+              }
             })
-            .collect::<Result<Vec<String>, Box<dyn Error>>>()?,
+            .collect::<Vec<String>>(),
         )
       }
     };
 
+    let units = Self::read_units(header, nfields);
+    let scales = Self::read_tbl_scales(header, nfields, scaling)?;
+    let nulls = (1..=nfields)
+      .map(|i| header.get_value(&format!("TNULL{i}")).map(|null| unquote_fits_string(null).into()))
+      .collect();
+
     //(3) Decode the image using the table parser
     let tbl = AsciiTblParser::decode_tbl(
       raw,
@@ -177,31 +428,264 @@ impl HeaderDataUnit {
       row_index_col_start,
       field_format,
       labels,
+      units,
+      scales,
+      nulls,
     )?;
 
     //(R) return the completed table
     Ok(tbl)
   }
 
-  fn read_img(raw: &mut RawFitsReader, header: &Header) -> Result<Extension, Box<dyn Error>> {
-    //Let's start by getting the number of axes from the NAXIS keyword
+  fn read_tbl_scales(
+    header: &Header,
+    nfields: usize,
+    scaling: ScalingMode,
+  ) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    //Reads the TSCAL{i} (default 1.0) and TZERO{i} (default 0.0) keywords of
+    //a table. Fields that should not be scaled get (1.0, 0.0)
+    let mut scales = Vec::new();
+    for i in 1..=nfields {
+      let tscal = match header.get_value(&format!("TSCAL{i}")) {
+        None => 1.0,
+        Some(_) => header.get_value_as::<f64>(&format!("TSCAL{i}"))?,
+      };
+      let tzero = match header.get_value(&format!("TZERO{i}")) {
+        None => 0.0,
+        Some(_) => header.get_value_as::<f64>(&format!("TZERO{i}"))?,
+      };
+      match scaling.applies(tscal, tzero) {
+        true => scales.push((tscal, tzero)),
+        false => scales.push((1.0, 0.0)),
+      }
+    }
+    Ok(scales)
+  }
+
+  fn read_units(header: &Header, nfields: usize) -> Vec<Option<String>> {
+    //Reads the (optional) TUNIT{i} keywords of a table
+    (1..=nfields)
+      .map(|i| match header.get_value(&format!("TUNIT{i}")) {
+        None => None,
+        Some(unit) => {
+          //Strip the quotes. Blank units are treated as no unit at all
+          let unit = unquote_fits_string(unit);
+          if unit.trim().is_empty() {
+            None
+          } else {
+            Some(unit.into_owned())
+          }
+        }
+      })
+      .collect()
+  }
+
+  fn read_bintable(
+    raw: &mut RawFitsReader,
+    header: &Header,
+    mode: ParseMode,
+  ) -> Result<Extension, Box<dyn Error>> {
+    //Tile-compressed images are stored in binary tables
+    #[cfg(feature = "rice")]
+    if let Some(compressed) = CompressedImage::from_header(header)? {
+      return compressed.decode(raw, header);
+    }
+
+    /*
+        To parse a binary table we need to know the following keywords:
+            TFIELDS => #fields in a row
+            NAXIS1 => #bytes in a row
+            NAXIS2 => #rows in the table
+            PCOUNT => #bytes in the heap following the table
+            TFORM{i} => data format (and repeat count) of field i
+        And optionally:
+            TTYPE{i} => name of field i
+            TUNIT{i} => physical unit of field i
+            TSCAL{i}, TZERO{i} => linear scaling of field i
+            TDIM{i} => shape of the array in field i
+            THEAP => offset of the heap in the data unit
+        In addition, we require the following keywords to have been set to:
+            NAXIS == 2
+            BITPIX == 8
+            GCOUNT == 1
+    */
+
+    //(1) check that the mandatory keywords have been set properly
     let naxis: usize = header.get_value_as("NAXIS")?;
+    let bitpix: isize = header.get_value_as("BITPIX")?;
+    let gcount: usize = header.get_value_as("GCOUNT")?;
+    if naxis != 2 {
+      Err(InvalidRecordValueError::new("NAXIS", &format!("{naxis}"), &["2"]))?
+    }
+    Self::check_table_bitpix(bitpix, mode)?;
+    if gcount != 1 {
+      Err(InvalidRecordValueError::new("GCOUNT", &format!("{gcount}"), &["1"]))?
+    }
 
-    //Axis sizes are encoded in the NAXIS{i} keywords
-    let mut axes: Vec<usize> = Vec::new();
-    for i in 1..=naxis {
-      axes.push(header.get_value_as(&format!("NAXIS{i}"))?);
+    //(2) Obtain the keywords describing the layout of the table
+    let nfields: usize = header.get_value_as("TFIELDS")?;
+    let row_len: usize = header.get_value_as("NAXIS1")?;
+    let nrows: usize = header.get_value_as("NAXIS2")?;
+    let heap_size: usize = header.get_value_as("PCOUNT")?;
+    let heap_start: usize = header.get_value_as("THEAP").unwrap_or(row_len * nrows);
+    let units = Self::read_units(header, nfields);
+
+    let mut fields = Vec::new();
+    for (i, unit) in (1..=nfields).zip(units) {
+      let tform: String = header.get_value_as(&format!("TFORM{i}"))?;
+      let label =
+        header.get_value(&format!("TTYPE{i}")).map(|val| unquote_fits_string(val).into_owned());
+
+      //Scaling only applies if at least one of the keywords was set
+      let scal = header.get_value_as::<f64>(&format!("TSCAL{i}")).ok();
+      let zero = header.get_value_as::<f64>(&format!("TZERO{i}")).ok();
+      let scale = match (scal, zero) {
+        (None, None) => None,
+        (scal, zero) => Some((scal.unwrap_or(1.0), zero.unwrap_or(0.0))),
+      };
+
+      let dim = header
+        .get_value(&format!("TDIM{i}"))
+        .and_then(|val| BinTblParser::parse_tdim(&unquote_fits_string(val)));
+
+      fields.push(BinFieldMeta {
+        tform: unquote_fits_string(&tform).into_owned(),
+        label,
+        unit,
+        scale,
+        dim,
+      });
     }
 
+    //(3) Decode the table using the binary table parser
+    BinTblParser::decode_tbl(raw, row_len, nrows, heap_size, heap_start, fields)
+  }
+
+  fn read_img(raw: &mut RawFitsReader, header: &Header) -> Result<Extension, Box<dyn Error>> {
+    //Axis sizes are encoded in the NAXIS and NAXIS{i} keywords
+    let axes = header.get_axes()?;
+
     //Datatype is encoded in the BITPIX keyword
-    let bitpix = Bitpix::from_code(&header.get_value_as("BITPIX")?)?;
+    let bitpix = Bitpix::try_from(header.get_value_as::<i64>("BITPIX")?)?;
 
     //Now do the actual decoding of the image:
     Ok(ImgParser::decode_img(raw, &axes, bitpix)?)
   }
 
-  pub(crate) fn encode_hdu(self, writer: &mut RawFitsWriter) -> Result<(), Box<dyn Error>> {
+  fn read_groups(raw: &mut RawFitsReader, header: &Header) -> Result<Extension, Box<dyn Error>> {
+    /*
+        To parse random groups we need to know the following keywords:
+            BITPIX => data type of both the parameters and the arrays
+            NAXIS{i} (i > 1) => shape of the array in each group
+            PCOUNT => #parameters in each group
+            GCOUNT => #groups
+        And optionally:
+            PTYPE{i} => name of parameter i
+            PSCAL{i}, PZERO{i} => linear scaling of parameter i
+            BSCALE, BZERO => linear scaling of the arrays
+    */
+    let bitpix = Bitpix::try_from(header.get_value_as::<i64>("BITPIX")?)?;
+    let shape: Vec<usize> = header.get_axes()?.into_iter().skip(1).collect();
+    let pcount: usize = header.get_value_as("PCOUNT")?;
+    let gcount: usize = header.get_value_as("GCOUNT")?;
+
+    let mut params = Vec::new();
+    for i in 1..=pcount {
+      let name =
+        header.get_value(&format!("PTYPE{i}")).map(|val| unquote_fits_string(val).into_owned());
+      let scal = header.get_value_as::<f64>(&format!("PSCAL{i}")).unwrap_or(1.0);
+      let zero = header.get_value_as::<f64>(&format!("PZERO{i}")).unwrap_or(0.0);
+      params.push((name, (scal, zero)));
+    }
+    let bscale = header.get_value_as::<f64>("BSCALE").unwrap_or(1.0);
+    let bzero = header.get_value_as::<f64>("BZERO").unwrap_or(0.0);
+
+    GroupsParser::decode_groups(raw, bitpix, shape, gcount, params, (bscale, bzero))
+  }
+
+  fn scale_img(
+    img: TypedImage,
+    header: &mut Header,
+    scaling: ScalingMode,
+  ) -> Result<(TypedImage, bool), Box<dyn Error>> {
+    /*
+        The BSCALE (default 1.0) and BZERO (default 0.0) keywords map the
+        stored pixel values to physical values. Scaled images are f64 images,
+        so we have to update the header accordingly: BITPIX becomes -64 and
+        the scaling keywords (and BLANK) no longer apply. Also returns if the
+        image was scaled.
+    */
+    let bscale = match header.get_value("BSCALE") {
+      None => 1.0,
+      Some(_) => header.get_value_as::<f64>("BSCALE")?,
+    };
+    let bzero = match header.get_value("BZERO") {
+      None => 0.0,
+      Some(_) => header.get_value_as::<f64>("BZERO")?,
+    };
+    if !scaling.applies(bscale, bzero) {
+      return Ok((img, false));
+    }
+
+    let blank = header.get_value_as::<i64>("BLANK").ok();
+    let physical = img.to_physical(bscale, bzero, blank);
+
+    header.set_value("BITPIX", Bitpix::Dpf.to_code().to_string());
+    for keyword in ["BSCALE", "BZERO", "BLANK"] {
+      header.remove_record(keyword);
+    }
+
+    Ok((physical, true))
+  }
+
+  pub(crate) fn new(header: Header, data: Option<Extension>) -> Self {
+    //Creates an HDU from a header that matches the data
+    HeaderDataUnit { header, data, read_sums: None, deferred: None }
+  }
+
+  pub(crate) fn new_empty(extname: &str, meta: Option<Header>) -> Self {
+    //Creates a metadata-only IMAGE extension
+    HeaderDataUnit {
+      header: Header::new_empty_extension(extname, meta),
+      data: None,
+      read_sums: None,
+      deferred: None,
+    }
+  }
+
+  pub(crate) fn new_deferred(header: Header, path: &Path, start_block: usize) -> Self {
+    //Creates an HDU of which the data is decoded once it is accessed. The
+    //header must have been decoded without scaling!
+    let deferred = DeferredData { path: path.to_path_buf(), start_block, data: OnceCell::new() };
+    HeaderDataUnit { header, data: None, read_sums: None, deferred: Some(deferred) }
+  }
+
+  fn data(&self) -> Option<&Extension> {
+    match &self.deferred {
+      Some(deferred) => deferred.get(),
+      None => self.data.as_ref(),
+    }
+  }
+
+  fn data_if_loaded(&self) -> Option<Option<&Extension>> {
+    //Like data(), but returns None for deferred data that was not decoded yet
+    match &self.deferred {
+      Some(deferred) => deferred.data.get().map(|data| data.as_ref()),
+      None => Some(self.data.as_ref()),
+    }
+  }
+
+  fn resolve(&mut self) {
+    //Decodes deferred data (if there is any), such that we can move it
+    if let Some(deferred) = self.deferred.take() {
+      self.data = deferred.into_data();
+    }
+  }
+
+  pub(crate) fn encode_hdu(mut self, writer: &mut RawFitsWriter) -> Result<(), Box<dyn Error>> {
     //(1) Write header
+    self.resolve();
+    self.sync_data_keywords()?;
     self.header.encode_header(writer)?;
 
     //(2) If we have data, write the data
@@ -214,8 +698,114 @@ impl HeaderDataUnit {
     Ok(())
   }
 
-  fn not_impl(keyword: &str) -> Box<NotImplementedErr> {
-    Box::new(NotImplementedErr::new(keyword.to_string()))
+  pub(crate) fn encode_hdu_with_checksum(
+    self,
+    writer: &mut RawFitsWriter,
+  ) -> Result<(), Box<dyn Error>> {
+    /*
+        To calculate the checksums, we first have to encode the whole HDU. We
+        do this in memory, since the CHECKSUM keyword has to be added to the
+        header before we can write anything to the actual file.
+    */
+    let (mut header, data_bytes) = self.encode_parts()?;
+    let header_bytes = Self::add_checksums(&mut header, &data_bytes)?;
+
+    //Write everything
+    writer.write_blocks(&header_bytes)?;
+    if !data_bytes.is_empty() {
+      writer.write_blocks(&data_bytes)?;
+    }
+
+    //(R) ok
+    Ok(())
+  }
+
+  fn encode_parts(mut self) -> Result<(Header, Vec<u8>), Box<dyn Error>> {
+    //Returns the (synchronised) header and the encoded data unit
+    self.resolve();
+    self.sync_data_keywords()?;
+    let mut data_buf = RawFitsWriter::in_memory();
+    if let Some(data) = self.data {
+      data.write_to_buffer(&mut data_buf)?;
+    }
+    Ok((self.header, data_buf.into_bytes()))
+  }
+
+  fn add_checksums(header: &mut Header, data_bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    /*
+        Sets the DATASUM and CHECKSUM keywords of the header for the supplied
+        data unit. Returns the encoded header (including both keywords).
+    */
+    //(1) Compute DATASUM and add the CHECKSUM placeholder
+    let data_sum = checksum::compute_datasum(data_bytes);
+    header.set_record("DATASUM", format!("'{data_sum}'"), Some(String::from("data unit checksum")));
+    header.set_record(
+      "CHECKSUM",
+      checksum::CHECKSUM_PLACEHOLDER.to_string(),
+      Some(String::from("HDU checksum")),
+    );
+
+    //(2) Compute the CHECKSUM from the encoded header with the placeholder
+    let mut header_buf = RawFitsWriter::in_memory();
+    header.clone().encode_header(&mut header_buf)?;
+    let hdu_checksum = checksum::compute_checksum(&header_buf.into_bytes(), data_sum);
+    header.set_value("CHECKSUM", format!("'{hdu_checksum}'"));
+
+    //(R) the header, now with the correct CHECKSUM
+    let mut header_buf = RawFitsWriter::in_memory();
+    header.clone().encode_header(&mut header_buf)?;
+    Ok(header_buf.into_bytes())
+  }
+
+  fn describe_data(&mut self) {
+    /*
+        Updates the keywords describing the data unit after the data has been
+        replaced. The scaling and checksum keywords belonged to the old data,
+        so they are removed. Other extensions keep the keywords they have.
+    */
+    self.read_sums = None;
+    let (xtension, bitpix, shape) = match &self.data {
+      None => ("'IMAGE   '", 8, Vec::new()),
+      Some(Extension::Image(img)) => ("'IMAGE   '", img.bpx().to_code(), img.get_shape().to_vec()),
+      Some(Extension::AsciiTable(_)) => ("'TABLE   '", 8, vec![0, 0]),
+      Some(_) => return,
+    };
+
+    let naxis = self.header.get_value_as::<usize>("NAXIS").unwrap_or(0);
+    let old_axes = (1..=naxis).map(|i| format!("NAXIS{i}"));
+    for keyword in
+      old_axes.chain(["BSCALE", "BZERO", "BLANK", "CHECKSUM", "DATASUM"].map(String::from))
+    {
+      self.header.remove_record(&keyword);
+    }
+    if self.header.contains("XTENSION") {
+      self.header.set_value("XTENSION", String::from(xtension));
+    }
+    self.header.set_value("BITPIX", bitpix.to_string());
+    self.header.set_value("NAXIS", shape.len().to_string());
+    for (i, axis) in shape.iter().enumerate() {
+      self.header.set_record(&format!("NAXIS{}", i + 1), axis.to_string(), None);
+    }
+    self.header.sort_mandatory_keywords();
+
+    //The layout of tables follows from their contents. Invalid tables are
+    //reported once they are encoded
+    let _ = self.sync_data_keywords();
+    self.header.update_block_len();
+  }
+
+  fn sync_data_keywords(&mut self) -> Result<(), Box<dyn Error>> {
+    /*
+        The layout of a table depends on its contents, which may have changed
+        since it was read. Hence we have to update the keywords describing the
+        layout before we encode the header. Images are written as they were.
+    */
+    if let Some(Extension::AsciiTable(tbl)) = &self.data {
+      for (keyword, value) in AsciiTblParser::get_tbl_keywords(tbl)? {
+        self.header.set_value(&keyword, value);
+      }
+    }
+    Ok(())
   }
 
   /*
@@ -226,27 +816,202 @@ impl HeaderDataUnit {
   pub fn get_header(&self) -> &Header {
     &self.header
   }
+  pub fn get_header_mut(&mut self) -> &mut Header {
+    //The header may change, so the checksums we read can no longer be used
+    self.read_sums = None;
+    &mut self.header
+  }
   pub fn get_data(&self) -> Option<&Extension> {
-    self.data.as_ref()
+    self.data()
+  }
+  pub fn image<T: Pixel>(&self) -> Result<ArrayViewD<'_, T>, Box<dyn Error>> {
+    //View of the image data, if this HDU contains an image of T's
+    match self.data() {
+      Some(Extension::Image(img)) => Ok(img.view()?),
+      other => Err(Box::new(WrongExtensionErr::new("an image", other))),
+    }
+  }
+  pub fn image_mut<T: Pixel>(&mut self) -> Result<ArrayViewMutD<'_, T>, Box<dyn Error>> {
+    //The data may change, so the checksums we read can no longer be used
+    self.read_sums = None;
+    self.resolve();
+    match &mut self.data {
+      Some(Extension::Image(img)) => Ok(img.view_mut()?),
+      other => Err(Box::new(WrongExtensionErr::new("an image", other.as_ref()))),
+    }
+  }
+  pub fn table(&self) -> Result<&AsciiTable, Box<dyn Error>> {
+    match self.data() {
+      Some(Extension::AsciiTable(tbl)) => Ok(tbl),
+      other => Err(Box::new(WrongExtensionErr::new("an ASCII table", other))),
+    }
+  }
+  pub fn into_table(mut self) -> Result<AsciiTable, Box<dyn Error>> {
+    self.resolve();
+    match self.data {
+      Some(Extension::AsciiTable(tbl)) => Ok(tbl),
+      other => Err(Box::new(WrongExtensionErr::new("an ASCII table", other.as_ref()))),
+    }
+  }
+  pub fn get_extname(&self) -> Option<Cow<'_, str>> {
+    //EXTNAME is a string, so we have to strip the quotes and padding
+    self.header.get_value("EXTNAME").map(|name| unquote_fits_string(name))
+  }
+
+  pub fn inherit_from(&mut self, primary: &HeaderDataUnit) {
+    /*
+        Extensions with INHERIT = T inherit the keywords of the primary HDU.
+        This func copies the primary records that are not in this header yet
+        (keywords describing the primary data unit excepted). Extensions
+        without INHERIT = T are left alone.
+    */
+    if self.header.get_bool("INHERIT") == Some(true) {
+      self.header.inherit(&primary.header);
+    }
+  }
+
+  pub fn get_extver(&self) -> i64 {
+    //Version of extensions that share the same EXTNAME (1 if not set)
+    self.header.get_int("EXTVER").unwrap_or(1)
+  }
+
+  pub fn get_extlevel(&self) -> i64 {
+    //Level of the extension in a hierarchy of extensions (1 if not set)
+    self.header.get_int("EXTLEVEL").unwrap_or(1)
+  }
+
+  pub fn wcs(&self) -> Option<Wcs> {
+    //World Coordinate System of the data, if the header describes one
+    Wcs::from_header(&self.header)
+  }
+
+  pub fn observation(&self) -> Option<Observation> {
+    //Observation keywords of the HDU, if the header contains any of them
+    let observation = Observation::from_header(&self.header);
+    (!observation.is_empty()).then_some(observation)
+  }
+
+  pub fn blank_mask(&self) -> Option<Array<bool, IxDyn>> {
+    /*
+        Returns a mask of the undefined pixels of an (unscaled) integer image.
+        Only HDU's with the BLANK keyword set have a mask.
+    */
+    let blank = self.header.get_value_as::<i64>("BLANK").ok()?;
+    match self.data() {
+      Some(Extension::Image(img)) => img.blank_mask(blank),
+      _ => None,
+    }
+  }
+
+  pub fn update_checksums(&mut self) -> Result<(), Box<dyn Error>> {
+    //Adds (or updates) the CHECKSUM and DATASUM keywords of this HDU
+    let (mut header, data_bytes) = self.clone().encode_parts()?;
+    Self::add_checksums(&mut header, &data_bytes)?;
+    self.header = header;
+
+    //We changed the header, so the checksums we read are no longer valid
+    self.read_sums = None;
+    Ok(())
+  }
+
+  pub fn verify_checksum(&self) -> Result<(), Box<dyn Error>> {
+    /*
+        Verifies the CHECKSUM and DATASUM keywords of this HDU. For HDU's read
+        from a file we use the checksums of the bytes that were actually read.
+        Other HDU's are encoded in memory to compute their checksums.
+    */
+    if self.header.get_value("CHECKSUM").is_none() && self.header.get_value("DATASUM").is_none() {
+      return Ok(()); //nothing to verify
+    }
+
+    let (header_sum, data_sum) = match self.read_sums {
+      Some(sums) => sums,
+      None => {
+        let (header, data_bytes) = self.clone().encode_parts()?;
+        let mut header_buf = RawFitsWriter::in_memory();
+        header.encode_header(&mut header_buf)?;
+        let header_sum = checksum::compute_datasum(&header_buf.into_bytes());
+        (header_sum, checksum::compute_datasum(&data_bytes))
+      }
+    };
+
+    Ok(Self::verify_checksums(&self.header, header_sum, data_sum)?)
   }
 
   //Destructs HDU into parts
-  pub fn to_parts(self) -> (Header, Option<Extension>) {
+  pub fn to_parts(mut self) -> (Header, Option<Extension>) {
+    self.resolve();
     (self.header, self.data)
   }
 
+  /*
+      The metadata of an HDU are the records of its header that do not
+      describe the data unit. The data can be swapped out as long as the
+      header keeps describing it, so those keywords are updated as well.
+  */
+  pub fn has_meta(&self) -> bool {
+    self.header.has_meta()
+  }
+
+  pub fn set_meta(&mut self, meta: Header) {
+    //Replaces the metadata of this HDU, the records describing the data in
+    //meta are ignored
+    self.read_sums = None;
+    self.header.take_meta();
+    self.header.copy_meta(meta);
+  }
+
+  pub fn take_meta(&mut self) -> Option<Header> {
+    //Removes the metadata from this HDU, None if there was none
+    if !self.has_meta() {
+      return None;
+    }
+    self.read_sums = None;
+    Some(self.header.take_meta())
+  }
+
+  pub fn has_data(&self) -> bool {
+    //Does not decode deferred data, its size follows from the header
+    match self.data_if_loaded() {
+      Some(data) => data.is_some(),
+      None => self.header.get_value("NAXIS").map(|val| val.as_str()) != Some("0"),
+    }
+  }
+
+  pub fn take_data(&mut self) -> Option<Extension> {
+    //Removes the data from this HDU, leaving an HDU with NAXIS = 0
+    self.resolve();
+    let data = self.data.take()?;
+    self.describe_data();
+    Some(data)
+  }
+
+  pub fn replace_data(&mut self, data: Extension) -> Result<Option<Extension>, WrongExtensionErr> {
+    //Replaces the data of this HDU, returning the old data. ASCII tables
+    //cannot be stored in the primary HDU
+    if self.header.contains("SIMPLE") && matches!(data, Extension::AsciiTable(_)) {
+      return Err(WrongExtensionErr::new("an image", Some(&data)));
+    }
+    self.resolve();
+    let old = self.data.replace(data);
+    self.describe_data();
+    Ok(old)
+  }
+
   pub fn pretty_print_header(&self) -> String {
     format!(
       "[Header] - #records: {}, size: {}",
-      self.header.get_block_len(),
-      self.header.get_num_records()
+      self.header.get_num_records(),
+      self.header.get_block_len()
     )
   }
 
   pub fn pretty_print_data(&self) -> String {
-    let data_string: Cow<str> = match &self.data {
-      None => "(NO_DATA)".into(),
-      Some(data) => format!("{data}").into(),
+    //Printing should not trigger decoding of deferred data
+    let data_string: Cow<str> = match self.data_if_loaded() {
+      None => "(DEFERRED)".into(),
+      Some(None) => "(NO_DATA)".into(),
+      Some(Some(data)) => format!("{data}").into(),
     };
     format!("[Data] {data_string}")
   }
@@ -254,18 +1019,18 @@ impl HeaderDataUnit {
 
 impl BlockSized for HeaderDataUnit {
   fn get_block_len(&self) -> usize {
-    self.header.get_block_len()
-      + match &self.data {
-        None => 0,
-        Some(data) => data.get_block_len(),
-      }
+    //The size of deferred data follows from the header
+    let data_len = match self.data_if_loaded() {
+      None => self.header.get_data_block_len().unwrap_or(0),
+      Some(data) => data.map_or(0, |data| data.get_block_len()),
+    };
+    self.header.get_block_len() + data_len
   }
 }
 
 impl Display for HeaderDataUnit {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "{}", self.pretty_print_header())?;
-    write!(f, "{}", self.pretty_print_data())?;
+    write!(f, "{} {}", self.pretty_print_header(), self.pretty_print_data())?;
     Ok(())
   }
 }
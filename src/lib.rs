@@ -19,28 +19,75 @@
 
 //Module structure
 mod bitpix;
+mod builder;
 mod err;
 mod extensions;
 mod fits;
 mod header;
 mod header_data_unit;
+mod observation;
+mod options;
 mod raw;
+mod validation;
+mod wcs;
 
 //Constants defined by the FITS standard
 pub(crate) const BLOCK_SIZE: usize = 2880;
 
 //Public api re-exports
+pub use bitpix::Bitpix;
+pub use builder::{FitsBuilder, HduBuilder, HeaderBuilder};
 pub use err::*;
+pub use extensions::groups::RandomGroups;
+pub use extensions::image::{Image, Pixel, ReduceOp, ScalingMode, TypedImage};
+pub use extensions::table::{
+  AsciiTable, BinColumn, BinColumnData, BinElement, BinTable, Column, ColumnEntry, ColumnStats,
+  TableEntry,
+};
 pub use extensions::Extension;
-pub use fits::Fits;
+pub use fits::{ChecksumResult, ChecksumStatus, Fits, HduSummary};
 pub use header::Header;
 pub use header_data_unit::HeaderDataUnit;
+pub use observation::Observation;
+pub use options::{FitsReadOptions, ParseMode};
+pub use raw::checksum::{compute_checksum, compute_datasum, encode_checksum};
+pub use raw::header_write_utils::{format_keyword_record, validate_keyword_name};
+pub use raw::keyword_utils::{parse_fits_datetime, quote_fits_string, unquote_fits_string};
+pub use validation::{FitsValidator, Severity, ValidationIssue};
+pub use wcs::Wcs;
+
+#[cfg(feature = "png")]
+pub use extensions::image::{Colormap, Stretch};
+#[cfg(feature = "tokio")]
+pub use raw::async_io::AsyncFitsReader;
 
 //prelude (kinda pointless rn but whatev)
 pub mod prelude {
+  pub use crate::bitpix::Bitpix;
+  pub use crate::builder::{FitsBuilder, HduBuilder, HeaderBuilder};
   pub use crate::err::*;
+  pub use crate::extensions::groups::RandomGroups;
+  pub use crate::extensions::image::{Image, Pixel, ReduceOp, ScalingMode, TypedImage};
+  pub use crate::extensions::table::{
+    AsciiTable, BinColumn, BinColumnData, BinElement, BinTable, Column, ColumnEntry, ColumnStats,
+    TableEntry,
+  };
   pub use crate::extensions::Extension;
-  pub use crate::fits::Fits;
+  pub use crate::fits::{ChecksumResult, ChecksumStatus, Fits, HduSummary};
   pub use crate::header::Header;
   pub use crate::header_data_unit::HeaderDataUnit;
+  pub use crate::observation::Observation;
+  pub use crate::options::{FitsReadOptions, ParseMode};
+  pub use crate::raw::checksum::{compute_checksum, compute_datasum, encode_checksum};
+  pub use crate::raw::header_write_utils::{format_keyword_record, validate_keyword_name};
+  pub use crate::raw::keyword_utils::{
+    parse_fits_datetime, quote_fits_string, unquote_fits_string,
+  };
+  pub use crate::validation::{FitsValidator, Severity, ValidationIssue};
+  pub use crate::wcs::Wcs;
+
+  #[cfg(feature = "png")]
+  pub use crate::extensions::image::{Colormap, Stretch};
+  #[cfg(feature = "tokio")]
+  pub use crate::raw::async_io::AsyncFitsReader;
 }
@@ -0,0 +1,91 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use chrono::NaiveDateTime;
+
+use crate::{header::Header, raw::keyword_utils::quote_fits_string};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Observation {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      The keywords that describe an astronomical observation. Keywords that
+      are missing from the header (or that have a value of the wrong type)
+      are None. RA and DEC are only read if they are given in degrees, not
+      as sexagesimal strings.
+  */
+  pub telescope: Option<String>,
+  pub instrument: Option<String>,
+  pub object: Option<String>,
+  pub observer: Option<String>,
+  pub date_obs: Option<NaiveDateTime>,
+  pub equinox: Option<f64>,
+  pub ra: Option<f64>,
+  pub dec: Option<f64>,
+  pub exptime: Option<f64>,
+}
+
+impl Observation {
+  pub fn from_header(header: &Header) -> Self {
+    Observation {
+      telescope: header.get_string("TELESCOP"),
+      instrument: header.get_string("INSTRUME"),
+      object: header.get_string("OBJECT"),
+      observer: header.get_string("OBSERVER"),
+      date_obs: header.get_datetime("DATE-OBS"),
+      equinox: header.get_float("EQUINOX"),
+      ra: header.get_float("RA"),
+      dec: header.get_float("DEC"),
+      exptime: header.get_float("EXPTIME"),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    //True if none of the keywords were found
+    *self == Observation::default()
+  }
+
+  pub fn to_header_keywords(&self) -> Vec<(String, String, Option<String>)> {
+    /*
+        Returns the keyword records (keyword, value, comment) of the fields
+        that are set, with the values formatted as they appear in a header.
+        Records may be added to a header with Header::insert_with_comment()
+        or HeaderBuilder::keyword_with_comment().
+    */
+    let string = |val: &Option<String>| val.as_deref().map(quote_fits_string);
+    let real = |val: Option<f64>| val.map(|val| format!("{val:?}"));
+    let date =
+      self.date_obs.map(|date| quote_fits_string(&date.format("%Y-%m-%dT%H:%M:%S%.f").to_string()));
+    [
+      ("TELESCOP", string(&self.telescope), "telescope used for the observation"),
+      ("INSTRUME", string(&self.instrument), "instrument used for the observation"),
+      ("OBJECT", string(&self.object), "name of the observed object"),
+      ("OBSERVER", string(&self.observer), "who acquired the data"),
+      ("DATE-OBS", date, "start of the observation"),
+      ("EQUINOX", real(self.equinox), "equinox of the coordinates"),
+      ("RA", real(self.ra), "right ascension [deg]"),
+      ("DEC", real(self.dec), "declination [deg]"),
+      ("EXPTIME", real(self.exptime), "exposure time [s]"),
+    ]
+    .into_iter()
+    .filter_map(|(keyword, value, comment)| {
+      Some((keyword.to_string(), value?, Some(comment.to_string())))
+    })
+    .collect()
+  }
+}
@@ -0,0 +1,89 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::extensions::image::ScalingMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ParseMode {
+  /*  THIS ENUM IS PART OF THE USER-FACING API
+      Many (older) FITS files bend the rules of the standard a little. In
+      Strict mode (the default) such files cannot be read. In Lenient mode
+      the decoder tries to recover, and reports what it had to fix through a
+      warning emitted with the log crate:
+        - keyword records that cannot be decoded are skipped
+        - string values without (closing) quotes are quoted
+        - tables with a BITPIX other than 8 are decoded anyway
+        - of SIMPLE, BITPIX and NAXIS records that appear twice the last is used
+        - blocks after the last HDU that do not start an extension are ignored
+  */
+  #[default]
+  Strict,
+  Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FitsReadOptions {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      Options that control how FITS files are decoded, used by
+      Fits::open_with_options(). The defaults are those of Fits::open().
+  */
+  pub(crate) parse_mode: ParseMode,
+  pub(crate) scaling: ScalingMode,
+  //Verify checksums while decoding (see Fits::open_with_checksum)
+  pub(crate) verify_checksums: bool,
+}
+
+impl Display for ParseMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      ParseMode::Strict => write!(f, "strict"),
+      ParseMode::Lenient => write!(f, "lenient"),
+    }
+  }
+}
+
+impl Display for FitsReadOptions {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    //Compact single-line representation, meant for log messages
+    write!(f, "FitsReadOptions {{ parse_mode: {}, scaling: {} }}", self.parse_mode, self.scaling)
+  }
+}
+
+impl FitsReadOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn lenient(self) -> Self {
+    self.parse_mode(ParseMode::Lenient)
+  }
+
+  pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+    self.parse_mode = parse_mode;
+    self
+  }
+
+  pub fn scaling(mut self, scaling: ScalingMode) -> Self {
+    self.scaling = scaling;
+    self
+  }
+}
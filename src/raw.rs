@@ -18,9 +18,19 @@
 */
 
 //Module structure
+#[cfg(feature = "tokio")]
+pub(crate) mod async_io;
+pub(crate) mod bin_entry_format;
+pub(crate) mod checksum;
+#[cfg(feature = "gz")]
+pub(crate) mod gz_io;
 pub(crate) mod header_block;
+pub(crate) mod header_write_utils;
 pub(crate) mod keyword_record;
+pub(crate) mod keyword_utils;
 pub(crate) mod raw_io;
+#[cfg(feature = "rice")]
+pub(crate) mod rice;
 pub(crate) mod table_entry_format;
 
 pub(crate) trait BlockSized {
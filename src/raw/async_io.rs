@@ -0,0 +1,146 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::{error::Error, future::Future};
+
+use tokio::io::AsyncReadExt;
+
+use crate::io_err::{self, InvalidFitsFileErr};
+
+//Get block size from root
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
+
+pub trait AsyncFitsReader {
+  /*  THIS TRAIT IS PART OF THE USER-FACING API
+      Asynchronous source of FITS blocks. Implementors fill the buffer (an
+      integer multiple of the FITS block size) with as many blocks as are
+      available, and return the number of blocks read. Zero blocks means that
+      the source is exhausted.
+      Sources that produce their own buffers may implement read_blocks()
+      instead. Each method is implemented in terms of the other, so at least
+      one of them has to be implemented (otherwise the futures returned by
+      these methods contain themselves, which does not compile).
+  */
+  fn read_blocks_into(
+    &mut self,
+    buffer: &mut [u8],
+  ) -> impl Future<Output = Result<usize, Box<dyn Error>>> {
+    async move {
+      //(1) Check if the buffer is an integer multiple of a FITS block
+      if buffer.len() % BLOCK_SIZE != 0 {
+        return Err(Box::new(InvalidFitsFileErr::new(io_err::BUF_BLOCK_DIV)) as Box<dyn Error>);
+      }
+
+      //(2) Copy the blocks to the buffer, there may be less than requested
+      let blocks = self.read_blocks(buffer.len() / BLOCK_SIZE).await?;
+      if blocks.len() % BLOCK_SIZE != 0 || blocks.len() > buffer.len() {
+        return Err(Box::new(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV)));
+      }
+      buffer[..blocks.len()].copy_from_slice(&blocks);
+      Ok(blocks.len() / BLOCK_SIZE)
+    }
+  }
+
+  fn read_blocks(
+    &mut self,
+    n_blocks: usize,
+  ) -> impl Future<Output = Result<Vec<u8>, Box<dyn Error>>> {
+    //Reads (at most) n_blocks FITS blocks into a new buffer. Fewer blocks
+    //are returned at the end of the source
+    async move {
+      let mut buffer = vec![0u8; n_blocks * BLOCK_SIZE];
+      let n_read = self.read_blocks_into(&mut buffer).await?;
+      buffer.truncate(n_read * BLOCK_SIZE);
+      Ok(buffer)
+    }
+  }
+
+  fn source_len_blocks(&self) -> Option<usize> {
+    //Total length of the source in FITS blocks, None if it is not known
+    None
+  }
+
+  fn blocks_remaining(&self) -> Option<usize> {
+    //#blocks that have not been read yet, None if this is not known
+    None
+  }
+}
+
+/*
+    References to and boxes of readers are readers too, so that generic code
+    can hand them on. Note that the trait is not object safe (it returns an
+    impl Future), so there is no impl for Box<dyn AsyncFitsReader>.
+*/
+impl<R: AsyncFitsReader> AsyncFitsReader for &mut R {
+  fn read_blocks_into(
+    &mut self,
+    buffer: &mut [u8],
+  ) -> impl Future<Output = Result<usize, Box<dyn Error>>> {
+    (**self).read_blocks_into(buffer)
+  }
+
+  fn source_len_blocks(&self) -> Option<usize> {
+    (**self).source_len_blocks()
+  }
+
+  fn blocks_remaining(&self) -> Option<usize> {
+    (**self).blocks_remaining()
+  }
+}
+
+impl<R: AsyncFitsReader> AsyncFitsReader for Box<R> {
+  fn read_blocks_into(
+    &mut self,
+    buffer: &mut [u8],
+  ) -> impl Future<Output = Result<usize, Box<dyn Error>>> {
+    (**self).read_blocks_into(buffer)
+  }
+
+  fn source_len_blocks(&self) -> Option<usize> {
+    (**self).source_len_blocks()
+  }
+
+  fn blocks_remaining(&self) -> Option<usize> {
+    (**self).blocks_remaining()
+  }
+}
+
+impl AsyncFitsReader for tokio::fs::File {
+  async fn read_blocks_into(&mut self, buffer: &mut [u8]) -> Result<usize, Box<dyn Error>> {
+    //(1) Check if the buffer is an integer multiple of a FITS block
+    if buffer.len() % BLOCK_SIZE != 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::BUF_BLOCK_DIV)));
+    }
+
+    //(2) Keep reading until the buffer is full, or the file ends
+    let mut filled = 0;
+    while filled < buffer.len() {
+      match self.read(&mut buffer[filled..]).await? {
+        0 => break,
+        n => filled += n,
+      }
+    }
+
+    //(3) Files must consist of whole FITS blocks
+    if filled % BLOCK_SIZE != 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV)));
+    }
+    Ok(filled / BLOCK_SIZE)
+  }
+}
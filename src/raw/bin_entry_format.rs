@@ -0,0 +1,124 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Entries in a binary table are encoded as big-endian binary values. The
+    format of each field is given by the TFORM{i} keyword as rT, where r is
+    the (optional) repeat count and T a single character specifying the data
+    type. Variable-length arrays have the format rPT(max) or rQT(max), with T
+    the type of the elements of the arrays. Their fields contain descriptors
+    (the length and heap offset of the array) rather than the values. This
+    file contains the machinery to convert these codes into the format of
+    the field.
+*/
+
+use std::error::Error;
+
+use crate::tbl_fmt_err::InvalidFFCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinEntryType {
+  Logical,       //L
+  Bit,           //X
+  Byte,          //B
+  Short,         //I
+  Int,           //J
+  Long,          //K
+  Char,          //A
+  Float,         //E
+  Double,        //D
+  Complex,       //C
+  DoubleComplex, //M
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Descriptor {
+  P, //two 32 bit integers
+  Q, //two 64 bit integers
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BinEntryFormat {
+  pub(crate) repeat: usize,
+  pub(crate) dtype: BinEntryType,
+  pub(crate) descriptor: Option<Descriptor>,
+}
+
+impl BinEntryFormat {
+  pub(crate) fn from_tform(tform: &str) -> Result<Self, Box<dyn Error>> {
+    use BinEntryType::*;
+
+    //(1) The repeat count is optional and defaults to 1
+    let code = tform.trim();
+    let split = code.find(|c: char| !c.is_ascii_digit()).unwrap_or(code.len());
+    let repeat = match &code[..split] {
+      "" => 1,
+      count => count.parse::<usize>()?,
+    };
+
+    //(2) Variable-length arrays are marked with a P or Q
+    let mut chars = code[split..].chars().peekable();
+    let descriptor = match chars.peek() {
+      Some('P') => Some(Descriptor::P),
+      Some('Q') => Some(Descriptor::Q),
+      _ => None,
+    };
+    if descriptor.is_some() {
+      chars.next();
+    }
+
+    //(3) The next character is the data type. Anything after that is
+    //reserved (or the maximum length of variable-length arrays), so we
+    //ignore it
+    let dtype = match chars.next() {
+      Some('L') => Logical,
+      Some('X') => Bit,
+      Some('B') => Byte,
+      Some('I') => Short,
+      Some('J') => Int,
+      Some('K') => Long,
+      Some('A') => Char,
+      Some('E') => Float,
+      Some('D') => Double,
+      Some('C') => Complex,
+      Some('M') => DoubleComplex,
+      _ => return Err(Box::new(InvalidFFCode::new(code.to_string()))),
+    };
+
+    Ok(BinEntryFormat { repeat, dtype, descriptor })
+  }
+
+  pub(crate) fn get_field_width(&self) -> usize {
+    //Width of the field in bytes. Bits are packed into whole bytes
+    use BinEntryType::*;
+    match self.descriptor {
+      Some(Descriptor::P) => return 8 * self.repeat,
+      Some(Descriptor::Q) => return 16 * self.repeat,
+      None => {}
+    }
+    match self.dtype {
+      Bit => self.repeat.div_ceil(8),
+      Logical | Byte | Char => self.repeat,
+      Short => 2 * self.repeat,
+      Int | Float => 4 * self.repeat,
+      Long | Double | Complex => 8 * self.repeat,
+      DoubleComplex => 16 * self.repeat,
+    }
+  }
+}
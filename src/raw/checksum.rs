@@ -0,0 +1,124 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    This file implements the checksum algorithm used by the CHECKSUM and
+    DATASUM keywords (see the FITS checksum convention by Seaman, Pence and
+    Rots). The checksum of a HDU is the 32-bit 1's complement sum of all its
+    bytes, interpreted as big-endian 32-bit integers.
+
+    DATASUM contains the checksum of the data unit as an unsigned integer,
+    while CHECKSUM contains the ASCII encoded complement of the checksum of
+    the whole HDU. This way the checksum of a HDU with a valid CHECKSUM
+    keyword is always -0 (all bits set).
+*/
+
+//Value of the CHECKSUM keyword while the actual checksum is being computed
+pub(crate) const CHECKSUM_PLACEHOLDER: &str = "'0000000000000000'";
+
+//Checksum of a HDU with a correct CHECKSUM keyword (= negative zero)
+pub(crate) const VALID_HDU_SUM: u32 = u32::MAX;
+
+//These ASCII characters (punctuation between digits and letters) may not
+//appear in the encoded checksum
+const EXCLUDED: [u8; 13] =
+  [0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40, 0x5b, 0x5c, 0x5d, 0x5e, 0x5f, 0x60];
+
+pub(crate) fn accumulate(bytes: &[u8], sum: u32) -> u32 {
+  /*  Note:
+      We sum the high and low 16 bits seperately. This way 64 bit accumulators
+      cannot overflow for any reasonable input, and the carries can be folded
+      back into the sum at the end. FITS data always comes in 2880 byte blocks,
+      so bytes.len() is always a multiple of four.
+  */
+  let (mut hi, mut lo) = ((sum >> 16) as u64, (sum & 0xFFFF) as u64);
+
+  for word in bytes.chunks_exact(4) {
+    hi += u16::from_be_bytes([word[0], word[1]]) as u64;
+    lo += u16::from_be_bytes([word[2], word[3]]) as u64;
+  }
+
+  //Fold the carries back in (1's complement addition)
+  let (mut hi_carry, mut lo_carry) = (hi >> 16, lo >> 16);
+  while hi_carry != 0 || lo_carry != 0 {
+    hi = (hi & 0xFFFF) + lo_carry;
+    lo = (lo & 0xFFFF) + hi_carry;
+    hi_carry = hi >> 16;
+    lo_carry = lo >> 16;
+  }
+
+  ((hi << 16) | lo) as u32
+}
+
+pub(crate) fn ones_add(a: u32, b: u32) -> u32 {
+  //1's complement addition of two checksums
+  let sum = a as u64 + b as u64;
+  ((sum & 0xFFFF_FFFF) + (sum >> 32)) as u32
+}
+
+pub fn encode_checksum(sum: u32) -> String {
+  /*  Note:
+      The complement of the checksum is encoded as 16 ASCII characters. Each
+      byte is spread out over four characters (of which the first receives the
+      remainder), avoiding the excluded punctuation characters. The resulting
+      characters are interleaved and rotated one place to the right.
+  */
+  let value = !sum;
+  let mut ascii = [0u8; 16];
+
+  for (i, byte) in value.to_be_bytes().into_iter().enumerate() {
+    let mut ch = [byte / 4 + 0x30; 4];
+    ch[0] += byte % 4;
+
+    //Shift pairs of characters away from the excluded ones (keeps their sum)
+    let mut check = true;
+    while check {
+      check = false;
+      for excl in EXCLUDED {
+        for j in [0, 2] {
+          if ch[j] == excl || ch[j + 1] == excl {
+            ch[j] += 1;
+            ch[j + 1] -= 1;
+            check = true;
+          }
+        }
+      }
+    }
+
+    for (j, c) in ch.into_iter().enumerate() {
+      ascii[(4 * j + i + 1) % 16] = c;
+    }
+  }
+
+  String::from_utf8_lossy(&ascii).into_owned()
+}
+
+pub fn compute_datasum(data: &[u8]) -> u32 {
+  //Checksum of an encoded data unit, as stored in the DATASUM keyword
+  accumulate(data, 0)
+}
+
+pub fn compute_checksum(header_bytes: &[u8], datasum: u32) -> String {
+  /*
+      Value of the CHECKSUM keyword (without quotes) for an encoded header
+      with the given DATASUM. The CHECKSUM record in the encoded header must
+      contain the placeholder value while computing the checksum.
+  */
+  encode_checksum(accumulate(header_bytes, datasum))
+}
@@ -0,0 +1,98 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*
+    Reading of GZIP compressed FITS files (.fits.gz). The decompressed stream
+    is cut into FITS blocks while it is being read. GzDecoder cannot seek, so
+    the block offsets of the HDU's are not known in advance: compressed files
+    are always decoded from start to finish.
+*/
+
+use std::{
+  error::Error,
+  fs::File,
+  io::{BufReader, Read},
+  path::Path,
+};
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::io_err::{self, InvalidFitsFileErr};
+
+//Get block size from root
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
+
+pub(crate) fn is_gzip_path(path: &Path) -> bool {
+  //Matches both .gz and .fits.gz (the extension is not case-sensitive)
+  path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+#[derive(Debug)]
+pub(crate) struct GzFitsReader {
+  //Some archives concatenate several gzip members, so we read all of them
+  decoder: MultiGzDecoder<BufReader<File>>,
+}
+
+impl GzFitsReader {
+  pub(crate) fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+    let f = File::open(path)?;
+    Ok(GzFitsReader { decoder: MultiGzDecoder::new(BufReader::new(f)) })
+  }
+
+  pub(crate) fn read_blocks(&mut self, buffer: &mut [u8]) -> Result<usize, Box<dyn Error>> {
+    /*
+        Fills the buffer with as many blocks as the decompressed stream still
+        contains, and returns the number of blocks read. Zero blocks means
+        that the stream is exhausted.
+    */
+    //(1) Check if the buffer is an integer multiple of a FITS block
+    if buffer.len() % BLOCK_SIZE != 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::BUF_BLOCK_DIV)));
+    }
+
+    //(2) Keep decompressing until the buffer is full, or the stream ends
+    let mut filled = 0;
+    while filled < buffer.len() {
+      match self.decoder.read(&mut buffer[filled..])? {
+        0 => break,
+        n => filled += n,
+      }
+    }
+
+    //(3) The decompressed file must consist of whole FITS blocks
+    if filled % BLOCK_SIZE != 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV)));
+    }
+    Ok(filled / BLOCK_SIZE)
+  }
+
+  pub(crate) fn read_to_end(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+    //Decompresses the rest of the file (in chunks of 16 blocks)
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0u8; 16 * BLOCK_SIZE];
+    loop {
+      let n_blocks = self.read_blocks(&mut chunk)?;
+      if n_blocks == 0 {
+        break;
+      }
+      bytes.extend_from_slice(&chunk[..n_blocks * BLOCK_SIZE]);
+    }
+    Ok(bytes)
+  }
+}
@@ -19,7 +19,10 @@
 
 use std::error::Error;
 
-use crate::header_err::{self, HeaderBlockBufferErr as HBBErr};
+use crate::{
+  header_err::{self, HeaderBlockBufferErr as HBBErr},
+  options::ParseMode,
+};
 
 use super::keyword_record::KeywordRecord;
 
@@ -33,7 +36,7 @@ pub(crate) struct HeaderBlock {
 }
 
 impl HeaderBlock {
-  pub(crate) fn decode_from_bytes(bytes: &[u8]) -> Result<(Self, bool), HBBErr> {
+  pub(crate) fn decode_from_bytes(bytes: &[u8], mode: ParseMode) -> Result<(Self, bool), HBBErr> {
     /*  If we're in the last headerblock of the header (denoted by the END
         keyword, then we have to set the return value of is_final to true
     */
@@ -49,8 +52,15 @@ impl HeaderBlock {
     let mut records: Vec<KeywordRecord> = Vec::new();
     for i in 0..36 {
       //36 keywords in a HeaderBlock
-      //Decode
-      let record = KeywordRecord::decode_from_bytes(&bytes[(i * 80)..(i * 80 + 80)])?;
+      //Decode (lenient mode skips records that cannot be decoded)
+      let record = match KeywordRecord::decode_from_bytes(&bytes[(i * 80)..(i * 80 + 80)], mode) {
+        Ok(record) => record,
+        Err(err) if mode == ParseMode::Lenient => {
+          log::warn!("skipped keyword record {} of header block: {err}", i + 1);
+          continue;
+        }
+        Err(err) => Err(err)?,
+      };
       //And parse
       if *record.keyword == String::from("END") {
         //This is the END keyword, which we DON'T append!
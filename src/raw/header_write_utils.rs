@@ -0,0 +1,249 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Utility funcs for encoding keyword records. String values that do not fit
+    in a single 80 byte record are split over multiple records using the
+    CONTINUE long string convention:
+        KEYWORD = 'first part of the string&'
+        CONTINUE  'second part of the string&'
+        CONTINUE  'last part of the string' / comment
+*/
+
+use crate::{
+  keyword_err::{InvalidKeywordErr, KeywordNameErrorReason, KeywordRecordErr},
+  raw::{keyword_record::KeywordRecord, keyword_utils::quote_fits_string},
+};
+
+//Size of a single keyword record
+const RECORD_LEN: usize = 80;
+
+//Fixed-format values other than strings end in column 30
+const FIXED_VALUE_LEN: usize = 20;
+
+//Prefix of all CONTINUE records
+const CONTINUE: &str = "CONTINUE  ";
+
+//HIERARCH keywords are followed by { = } and at least one character of value
+const MAX_HIERARCH_LEN: usize = RECORD_LEN - "HIERARCH ".len() - " = ".len() - 1;
+
+//Indexed keywords (NAXIS1, TFORM3, ...) that are derived from the data
+const INDEXED_KEYWORDS: [&str; 9] =
+  ["NAXIS", "TBCOL", "TFORM", "TTYPE", "TUNIT", "TNULL", "TSCAL", "TZERO", "TDIM"];
+
+pub(crate) fn is_hierarch(keyword: &str) -> bool {
+  //Keywords that do not fit the 8 character limit use HIERARCH
+  keyword.len() > 8 || keyword.contains('.')
+}
+
+pub(crate) fn keyword_prefix(keyword: &str) -> String {
+  //Keyword and padding up to where the value indicator goes
+  match is_hierarch(keyword) {
+    true => format!("HIERARCH {} ", keyword.replace('.', " ")),
+    false => format!("{keyword:<8}"),
+  }
+}
+
+pub fn validate_keyword_name(name: &str) -> Result<(), InvalidKeywordErr> {
+  /*
+      Checks if users may add a keyword record with this keyword:
+        - keywords consist of up to 8 uppercase letters, digits, hyphens and
+          underscores. Longer keywords (or keywords with dots) are written
+          with the HIERARCH convention, see HeaderBuilder::is_valid_keyword()
+        - HIERARCH keywords still have to fit in a single record
+        - keywords that describe the data unit are protected, including the
+          indexed ones (NAXISn, TFORMn, ...)
+  */
+  let invalid = |reason| Err(InvalidKeywordErr::new(name, reason));
+
+  //(1) Character set (empty keywords are blank)
+  if let Some(index) = invalid_keyword_char(name.as_bytes()) {
+    let found = name[index..].chars().next().unwrap_or(' ');
+    return invalid(KeywordNameErrorReason::IllegalCharacter(found));
+  } else if name.is_empty() {
+    return invalid(KeywordNameErrorReason::IllegalCharacter(' '));
+  }
+
+  //(2) Length
+  if is_hierarch(name) && name.len() > MAX_HIERARCH_LEN {
+    return invalid(KeywordNameErrorReason::TooLong);
+  }
+
+  //(3) Protected keywords
+  for prefix in INDEXED_KEYWORDS {
+    let index = name.strip_prefix(prefix);
+    if index.is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())) {
+      return invalid(KeywordNameErrorReason::Protected);
+    }
+  }
+  KeywordRecord::check_protected(name)
+}
+
+pub(crate) const fn invalid_keyword_char(bytes: &[u8]) -> Option<usize> {
+  //Index of the first character that is not allowed in a keyword, or None
+  //if all characters are allowed. Const, see HeaderBuilder::is_valid_keyword
+  let hierarch = bytes.len() > 8 || contains_dot(bytes);
+  let mut i = 0;
+  while i < bytes.len() {
+    let valid = match bytes[i] {
+      b'.' => i != 0 && i != bytes.len() - 1 && bytes[i - 1] != b'.',
+      b'=' => false,
+      byte if hierarch => byte.is_ascii_graphic(),
+      byte => byte.is_ascii_uppercase() || byte.is_ascii_digit() || byte == b'-' || byte == b'_',
+    };
+    if !valid {
+      return Some(i);
+    }
+    i += 1;
+  }
+  None
+}
+
+const fn contains_dot(bytes: &[u8]) -> bool {
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'.' {
+      return true;
+    }
+    i += 1;
+  }
+  false
+}
+
+pub fn format_keyword_record(
+  key: &str,
+  value: &str,
+  comment: Option<&str>,
+) -> Result<[u8; 80], KeywordRecordErr> {
+  /*
+      Encodes a keyword record in the fixed format of the standard:
+        - the keyword in columns 1-8, followed by the value indicator {= }
+        - logicals (T or F), integers, floats and complex numbers right-
+          justified in columns 11-30. Float exponents are written in capitals
+        - strings starting in column 11 with their quotes. Values that are not
+          one of the other types are quoted (and padded to 8 characters)
+        - the comment after a slash
+      The record is padded with spaces to 80 bytes. Records that do not fit
+      are an error, long strings have to be split with write_long_string().
+  */
+  let mut record = fixed_format_prefix(key, value)?;
+  if let Some(com) = comment.filter(|com| !com.is_empty()) {
+    check_chars(com)?;
+    record.push_str(" / ");
+    record.push_str(com);
+  }
+  match record.len() > RECORD_LEN {
+    true => Err(KeywordRecordErr::ValueTooLong { value: value.to_string() }),
+    false => Ok(pad_record(&record)),
+  }
+}
+
+pub(crate) fn fixed_format_prefix(key: &str, value: &str) -> Result<String, KeywordRecordErr> {
+  //Keyword, value indicator and value of a fixed-format record, see
+  //format_keyword_record()
+  if key.len() > 8 {
+    return Err(KeywordRecordErr::KeyTooLong { key: key.to_string() });
+  }
+  let valid_char = |c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_';
+  if let Some(found) = key.chars().find(|&c| !valid_char(c)) {
+    return Err(KeywordRecordErr::IllegalCharacter { found });
+  }
+  check_chars(value)?;
+
+  let value = value.trim();
+  let value = if value.starts_with('\'') {
+    value.to_string()
+  } else if value == "T" || value == "F" || value.starts_with('(') {
+    format!("{value:>FIXED_VALUE_LEN$}")
+  } else if value.replace(['D', 'd'], "E").parse::<f64>().is_ok_and(f64::is_finite) {
+    format!("{:>FIXED_VALUE_LEN$}", value.replace('e', "E").replace('d', "D"))
+  } else {
+    quote_fits_string(&format!("{value:<8}"))
+  };
+
+  let record = format!("{key:<8}= {value}");
+  match record.len() > RECORD_LEN {
+    true => Err(KeywordRecordErr::ValueTooLong { value }),
+    false => Ok(record),
+  }
+}
+
+fn check_chars(text: &str) -> Result<(), KeywordRecordErr> {
+  //Values and comments consist of printable ASCII characters
+  match text.chars().find(|c| !(' '..='~').contains(c)) {
+    Some(found) => Err(KeywordRecordErr::IllegalCharacter { found }),
+    None => Ok(()),
+  }
+}
+
+pub(crate) fn write_long_string(key: &str, value: &str, comment: Option<&str>) -> Vec<[u8; 80]> {
+  /*
+      Encodes a (not yet quoted) string value as one or more keyword records.
+      All parts of the string except the last one end with {&'}. We never
+      split the string between the two quotes of an escaped quote.
+  */
+  let mut records = Vec::new();
+  let mut record = format!("{}= '", keyword_prefix(key));
+
+  //(1) Write the escaped string one character at a time
+  let mut chars = value.chars().peekable();
+  while let Some(c) = chars.next() {
+    let escaped = match c {
+      '\'' => "''".to_string(),
+      c => c.to_string(),
+    };
+
+    //(1a) Start a new CONTINUE record if the character (and the {&'} that
+    //     ends this part) no longer fit. The final part only needs room for
+    //     the closing quote.
+    let is_last = chars.peek().is_none();
+    let reserved = if is_last { 1 } else { 2 };
+    if record.len() + escaped.len() + reserved > RECORD_LEN {
+      record.push_str("&'");
+      records.push(record);
+      record = format!("{CONTINUE}'");
+    }
+    record.push_str(&escaped);
+  }
+  record.push('\'');
+
+  //(2) Add the comment to the last record (on a separate empty CONTINUE
+  //    record if it doesn't fit). Comments that are too long are truncated.
+  if let Some(com) = comment {
+    if record.len() + 3 + com.len() > RECORD_LEN && record.len() > CONTINUE.len() + 2 {
+      record.pop();
+      record.push_str("&'");
+      records.push(record);
+      record = format!("{CONTINUE}''");
+    }
+    record.push_str(" / ");
+    record.push_str(com);
+    record.truncate(RECORD_LEN);
+  }
+  records.push(record);
+
+  //(R) pad all records to 80 bytes
+  records.into_iter().map(|record| pad_record(&record)).collect()
+}
+
+fn pad_record(record: &str) -> [u8; 80] {
+  let mut buf = [b' '; RECORD_LEN];
+  buf[..record.len()].copy_from_slice(record.as_bytes());
+  buf
+}
@@ -24,9 +24,27 @@ use std::{
   str,
 };
 
-use crate::keyword_err::{self, KeywordRecordBufferErr as KRBufErr, ProtectedKeywordErr as PKWErr};
+use crate::{
+  keyword_err::{
+    self, InvalidKeywordErr, KeywordNameErrorReason, KeywordRecordBufferErr as KRBufErr,
+    KeywordRecordErr,
+  },
+  options::ParseMode,
+  raw::{
+    header_write_utils::{self, fixed_format_prefix, keyword_prefix, write_long_string},
+    keyword_utils::{find_comment_start, quote_fits_string, string_len, unquote_fits_string},
+  },
+};
 use rustronomy_core::data_type_traits::io_utils::Encode;
 
+//Prefix of HIERARCH records
+const HIERARCH: &[u8] = b"HIERARCH ";
+
+//Commentary keywords may appear more than once, and have text instead of a
+//value (in columns 9-80 of the record)
+pub(crate) const COMMENTARY: [&str; 2] = ["COMMENT", "HISTORY"];
+pub(crate) const COMMENTARY_LEN: usize = 72;
+
 #[derive(Debug, Clone)]
 pub struct KeywordRecord {
   /*  THIS STRUCT IS PART OF THE USER-FACING API
@@ -107,27 +125,43 @@ impl KeywordRecord {
     keyword: &str,
     value: Option<String>,
     comment: Option<String>,
-  ) -> Result<Self, PKWErr> {
+  ) -> Result<Self, InvalidKeywordErr> {
     // (1) Check if the keyword is protected
-    for kw in Self::RESTRICTED_KEYWORDS {
-      if kw == keyword {
-        return Err(PKWErr::new(kw));
-      }
-    }
+    Self::check_protected(keyword)?;
 
     Ok(KeywordRecord { keyword: Rc::new(keyword.to_string()), value: value, comment: comment })
   }
 
+  pub(crate) fn check_protected(keyword: &str) -> Result<(), InvalidKeywordErr> {
+    match Self::RESTRICTED_KEYWORDS.contains(&keyword) {
+      true => Err(InvalidKeywordErr::new(keyword, KeywordNameErrorReason::Protected)),
+      false => Ok(()),
+    }
+  }
+
   /*
       THE FOLLOWING FUNCS ARE INTERNAL
   */
 
+  fn is_hierarch(&self) -> bool {
+    header_write_utils::is_hierarch(&self.keyword)
+  }
+
+  pub(crate) fn is_commentary(&self) -> bool {
+    COMMENTARY.contains(&self.keyword.as_str())
+  }
+
+  pub(crate) fn new_commentary(keyword: &str, text: &str) -> Self {
+    //The text of commentary records is stored as their comment
+    KeywordRecord { keyword: Rc::new(keyword.to_string()), value: None, comment: Some(text.into()) }
+  }
+
   pub(crate) fn from_string(keyword: Rc<String>, value: String, comment: Option<String>) -> Self {
     KeywordRecord { keyword: keyword, value: Some(value), comment: comment }
   }
 
   //Helper function for decoding. Not part of API
-  pub(crate) fn decode_from_bytes(bytes: &[u8]) -> Result<Self, KRBufErr> {
+  pub(crate) fn decode_from_bytes(bytes: &[u8], mode: ParseMode) -> Result<Self, KRBufErr> {
     //Make sure that we got 80 bytes:
     if bytes.len() != 80 {
       return Err(KRBufErr::new(keyword_err::BUFFER_LEN));
@@ -137,34 +171,61 @@ impl KeywordRecord {
     let mut has_val: bool;
     let has_com: bool;
 
+    //Commentary records contain text rather than a value and a comment
+    let name = str::from_utf8(&bytes[0..8])?.trim_end();
+    if COMMENTARY.contains(&name) {
+      let text = str::from_utf8(&bytes[8..80])?.trim();
+      if !text.is_ascii() {
+        return Err(KRBufErr::new(keyword_err::ILLEGAL_CHAR));
+      }
+      return Ok(Self::new_commentary(name, text));
+    }
+
     //Decode into keyword and record
-    let keyword = String::from(str::from_utf8(&bytes[0..8])?.trim());
-    has_val = match str::from_utf8(&bytes[8..10])? {
-      "= " => true,
-      _ => false,
-    };
-    let record = String::from(str::from_utf8(&bytes[10..80])?.trim());
+    let (keyword, record);
+    if bytes.starts_with(HIERARCH) {
+      /*  HIERARCH convention
+          The keyword consists of all words between HIERARCH and the value
+          indicator. We store it with dots in between the words, so the
+          keyword HIERARCH ESO DET CHIP1 ID becomes ESO.DET.CHIP1.ID
+      */
+      let rest = str::from_utf8(&bytes[HIERARCH.len()..80])?;
+      let (name, value) = match rest.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (rest, None),
+      };
+      keyword = name.split_whitespace().collect::<Vec<_>>().join(".");
+      has_val = value.is_some();
+      record = String::from(value.unwrap_or("").trim());
+    } else {
+      keyword = String::from(str::from_utf8(&bytes[0..8])?.trim());
+      has_val = match str::from_utf8(&bytes[8..10])? {
+        "= " => true,
+        //CONTINUE records have a value, but no value indicator
+        _ => keyword == "CONTINUE",
+      };
+      record = String::from(str::from_utf8(&bytes[10..80])?.trim());
+    }
 
     //Keyword and value should be valid ASCII
     if !keyword.is_ascii() || !record.is_ascii() {
       return Err(KRBufErr::new(keyword_err::ILLEGAL_CHAR));
     }
 
-    //Split record into value and comment
-    let (value, comment);
-    let split = record.split("/").collect::<Vec<_>>();
-
-    match split.len() {
-      1 => {
+    //Split record into value and comment. Slashes inside string values are
+    //part of the value!
+    let (mut value, comment);
+    match find_comment_start(&record) {
+      None => {
         //There was no comment in the record
-        value = String::from(split[0].trim());
+        value = String::from(record.trim());
         comment = String::from("");
         has_com = false;
       }
-      2 => {
+      Some(slash) => {
         //There was a comment in the record, there MAY have been a value
-        value = String::from(split[0].trim());
-        comment = String::from(split[1].trim());
+        value = String::from(record[..slash].trim());
+        comment = String::from(record[slash + 1..].trim());
 
         //Update value and comment flags
         has_com = true;
@@ -172,11 +233,13 @@ impl KeywordRecord {
           has_val = false;
         }
       }
-      _ => {
-        //This makes no sense, just set the value the entire string
-        value = record;
-        comment = String::from("");
-        has_com = false;
+    }
+
+    //In lenient mode we repair strings without (closing) quotes
+    if has_val && mode == ParseMode::Lenient {
+      if let Some(repaired) = Self::repair_string(&value) {
+        log::warn!("{keyword}: value {value} is not a valid FITS string, read as {repaired}");
+        value = repaired;
       }
     }
 
@@ -193,82 +256,125 @@ impl KeywordRecord {
     })
   }
 
+  fn repair_string(value: &str) -> Option<String> {
+    /*
+        Returns a properly quoted version of values that were (probably)
+        meant to be strings: strings without a closing quote and values that
+        are not a number, a logical or a complex number.
+    */
+    if value.starts_with('\'') {
+      return match string_len(value) {
+        Some(_) => None,
+        None => Some(format!("{value}'")),
+      };
+    }
+
+    //Fortran-style exponents (1.0D5) are numbers too
+    let is_number = value.replace(['D', 'd'], "E").parse::<f64>().is_ok();
+    let is_logical = value == "T" || value == "F";
+    match is_number || is_logical || value.starts_with('(') {
+      true => None,
+      false => Some(quote_fits_string(value)),
+    }
+  }
+
+  pub(crate) fn n_records(&self) -> usize {
+    //#80-byte records this record is written as: long strings are continued
+    //over several records. Records that can't be encoded count as one
+    let mut buf = Vec::new();
+    match self.clone().encode_fill_buff(&mut buf) {
+      Ok(()) => buf.len() / 80,
+      Err(_) => 1,
+    }
+  }
+
   pub(crate) fn encode_fill_buff(self, buf: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
     //keep track of how long the last keyword is
     let mut one_rec_buf = Vec::new();
 
-    //(1) Encode keyword and make sure it's 8 bytes long
-    let keyword_len = self.keyword.len();
-    self.keyword.fill_buf(&mut one_rec_buf);
-    for _ in 0..(8 - keyword_len) {
-      one_rec_buf.push(b' ');
+    //(0) Commentary records are just the keyword followed by text
+    if self.is_commentary() {
+      let text = self.comment.unwrap_or_default();
+      if text.len() > COMMENTARY_LEN {
+        return Err(Box::new(KRBufErr::new(keyword_err::VALUE_LEN)));
+      }
+      buf.extend_from_slice(format!("{:<8}{text:<72}", self.keyword).as_bytes());
+      return Ok(());
+    }
+
+    //Empty comments are not worth writing
+    let comment = self.comment.clone().filter(|com| !com.is_empty());
+
+    //(1) Records with a value are written in the fixed format, except for
+    //    HIERARCH records and strings that have to be continued (together
+    //    with their comment). The comments of other values are truncated
+    if let (false, Some(val)) = (self.is_hierarch(), &self.value) {
+      let quoted = val.starts_with('\'');
+      match fixed_format_prefix(&self.keyword, val) {
+        Ok(mut record) if !quoted || record.len() + comment_len(&comment) <= 80 => {
+          if let Some(com) = &comment {
+            record.push_str(" / ");
+            record.push_str(com);
+            record.truncate(80);
+          }
+          buf.extend_from_slice(&format!("{record:<80}").into_bytes());
+          return Ok(());
+        }
+        Err(KeywordRecordErr::ValueTooLong { .. }) if quoted => {}
+        Err(err) => return Err(Box::new(err)),
+        Ok(_) => {}
+      }
     }
 
-    //(2) Encode value
+    //(2) Encode keyword and make sure it's 8 bytes long. Keywords that are
+    //    longer (or contain dots) are encoded with the HIERARCH convention
+    keyword_prefix(&self.keyword).fill_buf(&mut one_rec_buf);
+    if self.is_hierarch() && one_rec_buf.len() > 75 {
+      //There has to be room for the value indicator and a value
+      return Err(Box::new(KRBufErr::new(keyword_err::KEYWORD_LEN)));
+    }
+
+    //(3) Encode value
     match self.value {
-      None => {} //do nothing
-      Some(mut val) => {
-        //(2a) add the value indicator
+      //Records without value have blanks instead of the value indicator, the
+      //comment starts after those
+      None => String::from("  ").fill_buf(&mut one_rec_buf),
+      Some(val) => {
+        //(3a) add the value indicator
         String::from("= ").fill_buf(&mut one_rec_buf);
 
-        //(2b) check if the value spans multiple keywordrecords
-        if val.len() < 70 {
+        //(3b) check if the value (and its comment) spans multiple records
+        let room = 80 - one_rec_buf.len();
+        if val.len() + comment_len(&comment) <= room
+          || (val.len() <= room && !val.starts_with('\''))
+        {
           val.fill_buf(&mut one_rec_buf);
-        } else {
-          //Write first string to the record with the keyword
-          let mut remainder = val.split_off(68);
-          val += "&'";
-          val.truncate(70);
-          val.fill_buf(&mut one_rec_buf);
-
-          //Write to the header buffer
-          assert!(one_rec_buf.len() == 80);
-          buf.append(&mut one_rec_buf);
-
-          //Write the remaining part of the string to CONTINUE records
-          let mut continue_buf = Vec::new();
-          String::from("CONTINUE= '").fill_buf(&mut continue_buf);
-
-          while remainder.len() > 0 {
-            if continue_buf.len() == 78 {
-              continue_buf.push(b'&');
-              continue_buf.push(b"'"[0]);
-
-              //Write to the header buffer
-              assert!(continue_buf.len() == 80);
-              buf.append(&mut continue_buf);
-            }
-            match remainder.pop() {
-              Some(ch) => continue_buf.push(ch as u8),
-              None => {} //Loop will break
-            }
+        } else if val.starts_with('\'') {
+          //Long strings are split over CONTINUE records
+          let long = unquote_fits_string(&val);
+          for record in write_long_string(&self.keyword, &long, comment.as_deref()) {
+            buf.extend_from_slice(&record);
           }
 
-          //Last keyword record may not have full length value
-          for _ in 0..(80 - continue_buf.len()) {
-            continue_buf.push(b' ');
-          }
-
-          //Write to the header buffer
-          assert!(continue_buf.len() == 80);
-          buf.append(&mut continue_buf);
-
           //we're done
           return Ok(());
+        } else {
+          //Only strings can be continued
+          return Err(Box::new(KRBufErr::new(keyword_err::VALUE_LEN)));
         }
       }
     }
 
-    //(3) Encode comment
-    match self.comment {
+    //(4) Encode comment
+    match comment {
       None => {} //do nothing
       Some(com) => {
-        String::from("/").fill_buf(&mut one_rec_buf);
+        String::from(" / ").fill_buf(&mut one_rec_buf);
         com.fill_buf(&mut one_rec_buf);
       }
     }
 
-    //(4) Make sure the keywordrecord is 80 bytes long
+    //(5) Make sure the keywordrecord is 80 bytes long
     for _ in 0..(80 - one_rec_buf.len()) {
       one_rec_buf.push(b' ');
     }
@@ -281,6 +387,11 @@ impl KeywordRecord {
   }
 }
 
+fn comment_len(comment: &Option<String>) -> usize {
+  //Length of a comment including the separating slash
+  comment.as_ref().map_or(0, |com| com.len() + 3)
+}
+
 impl Display for KeywordRecord {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     //keyword
@@ -0,0 +1,125 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Utility funcs for dealing with the values of keyword records. String
+    values in FITS headers are wrapped in single quotes, with quotes inside
+    the string escaped by doubling them: 'It''s a trap'. Trailing spaces
+    inside the quotes are not significant, leading spaces are.
+*/
+
+use std::borrow::Cow;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+pub fn quote_fits_string(s: &str) -> String {
+  //Adds the outer quotes and escapes the inner ones
+  format!("'{}'", s.replace('\'', "''"))
+}
+
+pub fn unquote_fits_string(s: &str) -> Cow<'_, str> {
+  /*
+      Strips the outer quotes and trailing padding from a FITS string value
+      and unescapes doubled quotes. We only allocate a new string if there
+      actually were escaped quotes. Values without outer quotes are left as
+      they are (apart from the padding).
+  */
+  let s = s.trim();
+  let inner = match s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+    Some(inner) => inner.trim_end(),
+    None => s,
+  };
+
+  if inner.contains("''") {
+    Cow::Owned(inner.replace("''", "'"))
+  } else {
+    Cow::Borrowed(inner)
+  }
+}
+
+pub fn parse_fits_datetime(value: &str) -> Option<NaiveDateTime> {
+  /*
+      Parses the value of a date keyword (DATE, DATE-OBS, ...). Two formats
+      are in use:
+          YYYY-MM-DD[Thh:mm:ss[.s...]] => current standard (ISO-8601)
+          DD/MM/YY => original standard, the century is not recorded
+      For old-style dates we pivot at 70: years 00-69 are 2000-2069, years
+      70-99 are 1970-1999. Old files may put a time zone behind the date
+      ('30/04/97 GMT'), which we ignore. Dates without time are at midnight.
+  */
+  let value = unquote_fits_string(value);
+  let value = value.split_whitespace().next()?;
+  let field = |field: Option<&str>| field?.parse::<u32>().ok();
+
+  //(1) Old-style dates
+  if value.contains('/') {
+    let mut fields = value.split('/');
+    let (day, month, year) = (field(fields.next())?, field(fields.next())?, field(fields.next())?);
+    if fields.next().is_some() || year > 99 {
+      return None;
+    }
+    let year = if year < 70 { 2000 + year } else { 1900 + year };
+    return NaiveDate::from_ymd_opt(year as i32, month, day)?.and_hms_opt(0, 0, 0);
+  }
+
+  //(2) ISO-8601 dates, with optional (fractional) time
+  let (date, time) = value.split_once('T').unwrap_or((value, "00:00:00"));
+  let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+  let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+  let mut fields = time.split(':');
+  let (hour, min, sec) = (field(fields.next())?, field(fields.next())?, field(fields.next())?);
+  if fields.next().is_some() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+
+  //Fractions are given in up to nanosecond precision
+  let nanos = format!("{fraction:0<9}")[..9].parse::<u32>().ok()?;
+  date.and_hms_nano_opt(hour, min, sec, nanos)
+}
+
+pub(crate) fn find_comment_start(record: &str) -> Option<usize> {
+  /*
+      Finds the slash that separates the value of a record from its comment.
+      Slashes inside string values do not start a comment, so we skip over
+      the string first (doubled quotes do not end it). Strings without a
+      closing quote have no comment.
+  */
+  let start = record.len() - record.trim_start().len();
+  let value_end = match record[start..].starts_with('\'') {
+    true => start + string_len(&record[start..])?,
+    false => start,
+  };
+
+  record[value_end..].find('/').map(|pos| value_end + pos)
+}
+
+pub(crate) fn string_len(value: &str) -> Option<usize> {
+  //Length of the quoted string at the start of value (including both quotes),
+  //None if the string has no closing quote
+  let bytes = value.as_bytes();
+  let mut i = 1;
+  loop {
+    match bytes.get(i) {
+      None => return None,
+      Some(b'\'') if bytes.get(i + 1) == Some(&b'\'') => i += 2,
+      Some(b'\'') => return Some(i + 1),
+      Some(_) => i += 1,
+    }
+  }
+}
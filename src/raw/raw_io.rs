@@ -19,13 +19,19 @@
 
 use std::{
   error::Error,
+  fmt,
   fs::{File, Metadata},
-  io::{self, Read, Write},
+  io::{self, Cursor, Read, Seek, SeekFrom, Write},
   path::Path,
 };
 
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
 use crate::io_err::{self, InvalidFitsFileErr};
 
+use super::checksum;
+
 //Get block size from root
 const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
 
@@ -35,17 +41,51 @@ const BLOCK_SIZE: usize = crate::BLOCK_SIZE;
     though none of their methods are public.
 
     NOTE: the file_meta field for file metadata *is* publicly accesible!
+    (it is None for readers and writers that do not operate on a file)
 */
 
 #[derive(Debug)]
-pub struct RawFitsReader {
-  pub file_meta: Metadata,
+enum ReadSource<'a> {
+  //Readers either read from a file on disk, or from a buffer in memory
+  File(File),
+  Memory(Cursor<&'a [u8]>),
+  //Files mapped into memory are read like buffers, without read syscalls
+  #[cfg(feature = "mmap")]
+  Mapped(Cursor<Mmap>),
+}
+
+impl Read for ReadSource<'_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match self {
+      ReadSource::File(handle) => handle.read(buf),
+      ReadSource::Memory(mem) => mem.read(buf),
+      #[cfg(feature = "mmap")]
+      ReadSource::Mapped(map) => map.read(buf),
+    }
+  }
+}
+
+impl Seek for ReadSource<'_> {
+  fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+    match self {
+      ReadSource::File(handle) => handle.seek(pos),
+      ReadSource::Memory(mem) => mem.seek(pos),
+      #[cfg(feature = "mmap")]
+      ReadSource::Mapped(map) => map.seek(pos),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct RawFitsReader<'a> {
+  pub file_meta: Option<Metadata>,
   block_index: usize,
   n_fits_blocks: usize,
-  reader_handle: File,
+  reader_handle: ReadSource<'a>,
+  running_sum: Option<u32>,
 }
 
-impl RawFitsReader {
+impl<'a> RawFitsReader<'a> {
   pub(crate) fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
     //(1) Open the file
     let f = File::open(path)?;
@@ -60,7 +100,58 @@ impl RawFitsReader {
     let n_blocks = meta.len() as usize / BLOCK_SIZE;
 
     //Return file as raw FITS
-    Ok(RawFitsReader { file_meta: meta, block_index: 0, n_fits_blocks: n_blocks, reader_handle: f })
+    Ok(RawFitsReader {
+      file_meta: Some(meta),
+      block_index: 0,
+      n_fits_blocks: n_blocks,
+      reader_handle: ReadSource::File(f),
+      running_sum: None,
+    })
+  }
+
+  #[cfg(feature = "mmap")]
+  pub(crate) fn new_mapped(path: &Path) -> Result<Self, Box<dyn Error>> {
+    /*
+        Same as new(), but the file is mapped into memory instead of being
+        read with read syscalls. The OS loads (and prefetches) the pages of
+        the file as we read them.
+
+        SAFETY: the map is only valid as long as no other process truncates
+        or modifies the file while we're reading it. We cannot guarantee
+        this, just like any other FITS reader that uses memory maps.
+    */
+    let f = File::open(path)?;
+    let meta = f.metadata()?;
+
+    if meta.len() as usize % BLOCK_SIZE != 0 {
+      return Err(Box::new(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV)));
+    }
+    let n_blocks = meta.len() as usize / BLOCK_SIZE;
+    let map = unsafe { Mmap::map(&f)? };
+
+    Ok(RawFitsReader {
+      file_meta: Some(meta),
+      block_index: 0,
+      n_fits_blocks: n_blocks,
+      reader_handle: ReadSource::Mapped(Cursor::new(map)),
+      running_sum: None,
+    })
+  }
+
+  pub(crate) fn from_bytes(bytes: &'a [u8]) -> Result<Self, InvalidFitsFileErr> {
+    //Creates a reader for a FITS file that is already resident in memory.
+    //The same block size requirement as for files applies
+    if bytes.len() % BLOCK_SIZE != 0 {
+      return Err(InvalidFitsFileErr::new(io_err::FILE_BLOCK_DIV));
+    }
+
+    Ok(RawFitsReader {
+      file_meta: None,
+      block_index: 0,
+      n_fits_blocks: bytes.len() / BLOCK_SIZE,
+      reader_handle: ReadSource::Memory(Cursor::new(bytes)),
+      running_sum: None,
+    })
   }
 
   pub(crate) fn read_blocks(&mut self, buffer: &mut [u8]) -> Result<usize, InvalidFitsFileErr> {
@@ -74,34 +165,104 @@ impl RawFitsReader {
 
     //(3) Check if the number of header blocks we need to read does not exceed
     //the number of header blocks still left in the file
-    if n_blocks > (self.n_fits_blocks - self.block_index) {
-      return Err(InvalidFitsFileErr::new(io_err::FILE_END));
+    let remaining = self.n_fits_blocks - self.block_index;
+    if n_blocks > remaining {
+      return Err(InvalidFitsFileErr::end_of_file(remaining, n_blocks));
     }
 
     //(4) Read the data (panic if this fails, since it fucks up the indexing)
     self.reader_handle.read_exact(buffer).unwrap();
 
-    //(5) Update the block index
+    //(5) Update the block index (and the checksum if we're keeping track)
     self.block_index += n_blocks;
+    if let Some(sum) = self.running_sum.as_mut() {
+      *sum = checksum::accumulate(buffer, *sum);
+    }
 
     Ok(n_blocks) //return the number of blocks read
   }
 
+  pub(crate) fn skip_blocks(&mut self, n_blocks: usize) -> Result<(), InvalidFitsFileErr> {
+    //Skips over FITS blocks without reading them. Note that skipped blocks
+    //do not contribute to the running checksum!
+    let remaining = self.n_fits_blocks - self.block_index;
+    if n_blocks > remaining {
+      return Err(InvalidFitsFileErr::end_of_file(remaining, n_blocks));
+    }
+
+    //Panic if this fails, since it fucks up the indexing
+    self.reader_handle.seek(SeekFrom::Current((n_blocks * BLOCK_SIZE) as i64)).unwrap();
+    self.block_index += n_blocks;
+    Ok(())
+  }
+
+  pub(crate) fn seek_to_block(&mut self, block: usize) -> Result<(), InvalidFitsFileErr> {
+    //Jumps to the start of a FITS block (seeking to the end of the file is
+    //allowed). The running checksum is reset, since we may skip data!
+    if block > self.n_fits_blocks {
+      return Err(InvalidFitsFileErr::new(io_err::SEEK_END));
+    }
+
+    //Panic if this fails, since it fucks up the indexing
+    self.reader_handle.seek(SeekFrom::Start((block * BLOCK_SIZE) as u64)).unwrap();
+    self.block_index = block;
+    if let Some(sum) = self.running_sum.as_mut() {
+      *sum = 0;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn enable_checksums(&mut self) {
+    //From now on, keep track of the checksum of all bytes we read
+    self.running_sum = Some(0);
+  }
+
+  pub(crate) fn take_checksum(&mut self) -> Option<u32> {
+    //Returns the checksum of all bytes read since the last call to this func
+    //(None if checksums are not enabled)
+    self.running_sum.as_mut().map(std::mem::take)
+  }
+
   pub(crate) fn get_block_len(&self) -> usize {
     self.n_fits_blocks
   }
   pub(crate) fn get_block_index(&self) -> usize {
     self.block_index
   }
+  pub(crate) fn get_blocks_remaining(&self) -> usize {
+    //#blocks between the current position and the end of the file
+    self.get_block_len().saturating_sub(self.block_index)
+  }
 }
 
 #[derive(Debug)]
-pub struct RawFitsWriter {
-  pub file_meta: Metadata,
-  writer_handle: File,
+enum WriteTarget {
+  //Writers either write to a file on disk, or to a buffer in memory
+  File(File),
+  Memory(Vec<u8>),
 }
 
-impl RawFitsWriter {
+pub struct RawFitsWriter<'a> {
+  pub file_meta: Option<Metadata>,
+  writer_handle: WriteTarget,
+  blocks_written: usize,
+  //Called with the total #blocks written so far, after each write
+  progress: Option<Box<dyn FnMut(usize) + 'a>>,
+}
+
+impl fmt::Debug for RawFitsWriter<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    //Closures cannot be printed, so we only say whether there is one
+    f.debug_struct("RawFitsWriter")
+      .field("file_meta", &self.file_meta)
+      .field("writer_handle", &self.writer_handle)
+      .field("blocks_written", &self.blocks_written)
+      .field("progress", &self.progress.is_some())
+      .finish()
+  }
+}
+
+impl<'a> RawFitsWriter<'a> {
   pub(crate) fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
     //(1) Open the file if it exists, create it if it doesn't
     let out = File::create(path)?;
@@ -110,7 +271,27 @@ impl RawFitsWriter {
     let meta = out.metadata()?;
 
     //(R)
-    Ok(RawFitsWriter { file_meta: meta, writer_handle: out })
+    Ok(RawFitsWriter {
+      file_meta: Some(meta),
+      writer_handle: WriteTarget::File(out),
+      blocks_written: 0,
+      progress: None,
+    })
+  }
+
+  pub(crate) fn in_memory() -> Self {
+    //Creates a writer that writes to an (initially empty) buffer in memory
+    RawFitsWriter {
+      file_meta: None,
+      writer_handle: WriteTarget::Memory(Vec::new()),
+      blocks_written: 0,
+      progress: None,
+    }
+  }
+
+  pub(crate) fn with_progress<F: FnMut(usize) + 'a>(mut self, callback: F) -> Self {
+    self.progress = Some(Box::new(callback));
+    self
   }
 
   pub(crate) fn write_blocks(&mut self, buffer: &[u8]) -> Result<usize, Box<dyn Error>> {
@@ -120,13 +301,60 @@ impl RawFitsWriter {
     }
 
     //(2) Write the thing
-    self.writer_handle.write_all(buffer)?;
+    match &mut self.writer_handle {
+      WriteTarget::File(handle) => handle.write_all(buffer)?,
+      WriteTarget::Memory(mem) => mem.extend_from_slice(buffer),
+    }
+
+    //(3) Keep track of our progress
+    self.blocks_written += buffer.len() / BLOCK_SIZE;
+    if let Some(callback) = &mut self.progress {
+      callback(self.blocks_written);
+    }
 
     //(R) the number of FITS blocks that we wrote
     Ok(buffer.len() / BLOCK_SIZE)
   }
 
+  pub(crate) fn get_blocks_written(&self) -> usize {
+    self.blocks_written
+  }
+
   pub(crate) fn flush(&mut self) -> io::Result<()> {
-    Ok(self.writer_handle.flush()?)
+    match &mut self.writer_handle {
+      WriteTarget::File(handle) => handle.flush(),
+      WriteTarget::Memory(_) => Ok(()), //nothing to flush
+    }
+  }
+
+  pub(crate) fn flush_and_sync(&mut self) -> io::Result<()> {
+    /*
+        flush() only hands the data to the OS, which may keep it in memory
+        for a while. This func also waits until the data and the metadata of
+        the file (size, modification time) have reached the disk.
+    */
+    self.flush()?;
+    match &mut self.writer_handle {
+      WriteTarget::File(handle) => handle.sync_all(),
+      WriteTarget::Memory(_) => Ok(()), //nothing to sync
+    }
+  }
+
+  pub(crate) fn flush_and_sync_data(&mut self) -> io::Result<()> {
+    //Same as flush_and_sync(), but metadata that is not needed to read the
+    //data back (such as the modification time) is not synced
+    self.flush()?;
+    match &mut self.writer_handle {
+      WriteTarget::File(handle) => handle.sync_data(),
+      WriteTarget::Memory(_) => Ok(()), //nothing to sync
+    }
+  }
+
+  pub(crate) fn into_bytes(self) -> Vec<u8> {
+    //Returns the bytes written to an in-memory writer (empty for files)
+    match self.writer_handle {
+      WriteTarget::File(_) => Vec::new(),
+      WriteTarget::Memory(mem) => mem,
+    }
   }
 }
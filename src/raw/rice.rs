@@ -0,0 +1,152 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Decoder for the Rice compression algorithm as it is used by the FITS tiled
+    image convention (ZCMPTYPE = 'RICE_1'). The pixels of a tile are stored as
+    differences between consecutive pixels, in blocks of BLOCKSIZE pixels. Each
+    block starts with a code (fs) that tells us how the differences in that
+    block were encoded:
+        fs < 0       => all differences are zero
+        fs == FSMAX  => differences are stored verbatim (high entropy)
+        otherwise    => differences are Rice coded: the top bits of each
+                        difference in unary, followed by its lowest fs bits
+    The number of bits used for the codes depends on the size of the pixels.
+    This is a port of the decoder in cfitsio (ricecomp.c).
+*/
+
+use crate::img_err::{self, CompressedImgErr};
+
+struct BitReader<'a> {
+  //Reads the compressed stream one byte at a time
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl BitReader<'_> {
+  fn next_byte(&mut self) -> Result<u32, CompressedImgErr> {
+    let byte = self.bytes.get(self.pos).ok_or(CompressedImgErr::new(img_err::TILE_TRUNCATED))?;
+    self.pos += 1;
+    Ok(*byte as u32)
+  }
+}
+
+pub(crate) fn decompress(
+  compressed: &[u8], //the compressed tile
+  n_pixels: usize,   //#pixels in the tile
+  block_size: usize, //#pixels per block (BLOCKSIZE)
+  bytepix: usize,    //#bytes per pixel (BYTEPIX)
+) -> Result<Vec<i32>, CompressedImgErr> {
+  /*
+      Returns the pixels of the tile. Pixels of one byte are unsigned, pixels
+      of two and four bytes are signed (just like FITS images)
+  */
+  //(1) Code sizes depend on the size of the pixels
+  let (fsbits, fsmax) = match bytepix {
+    1 => (3, 6),
+    2 => (4, 14),
+    4 => (5, 25),
+    _ => return Err(CompressedImgErr::new(img_err::BYTEPIX)),
+  };
+  let bbits = 8 * bytepix as i32;
+
+  //(2) The tile starts with the first pixel, stored verbatim (big-endian)
+  if compressed.len() < bytepix || block_size == 0 {
+    return Err(CompressedImgErr::new(img_err::TILE_TRUNCATED));
+  }
+  let mut lastpix = compressed[..bytepix].iter().fold(0u32, |pix, &byte| (pix << 8) | byte as u32);
+  let mut reader = BitReader { bytes: compressed, pos: bytepix };
+
+  //(3) Decode the blocks (b holds the nbits bits we haven't used yet)
+  let mut pixels = Vec::with_capacity(n_pixels);
+  let mut b = reader.next_byte()?;
+  let mut nbits: i32 = 8;
+
+  while pixels.len() < n_pixels {
+    //(3a) Read the code of this block
+    nbits -= fsbits;
+    while nbits < 0 {
+      b = (b << 8) | reader.next_byte()?;
+      nbits += 8;
+    }
+    let fs = (b >> nbits) as i32 - 1;
+    b &= (1 << nbits) - 1;
+    let block_end = n_pixels.min(pixels.len() + block_size);
+
+    //(3b) Decode the differences
+    while pixels.len() < block_end {
+      let diff = if fs < 0 {
+        //Low entropy: all differences are zero
+        0
+      } else if fs == fsmax {
+        //High entropy: the differences are stored in bbits bits
+        let mut k = bbits - nbits;
+        let mut diff = (b as u64) << k;
+        k -= 8;
+        while k >= 0 {
+          diff |= (reader.next_byte()? as u64) << k;
+          k -= 8;
+        }
+        if nbits > 0 {
+          b = reader.next_byte()?;
+          diff |= (b >> -k) as u64;
+          b &= (1 << nbits) - 1;
+        } else {
+          b = 0;
+        }
+        diff as u32
+      } else {
+        //Rice coded: count the leading zeros (the top bits in unary)
+        while b == 0 {
+          nbits += 8;
+          b = reader.next_byte()?;
+        }
+        let nzero = nbits - (32 - b.leading_zeros() as i32);
+        nbits -= nzero + 1;
+        b ^= 1 << nbits; //flip the one-bit that ends the unary code
+
+        //...followed by the lowest fs bits
+        nbits -= fs;
+        while nbits < 0 {
+          b = (b << 8) | reader.next_byte()?;
+          nbits += 8;
+        }
+        let diff = ((nzero as u32) << fs) | (b >> nbits);
+        b &= (1 << nbits) - 1;
+        diff
+      };
+
+      //(3c) Differences are mapped onto unsigned integers like this:
+      //0, -1, 1, -2, 2... => 0, 1, 2, 3, 4... We have to undo this
+      let diff = match diff & 1 {
+        0 => diff >> 1,
+        _ => !(diff >> 1),
+      };
+      lastpix = lastpix.wrapping_add(diff);
+      pixels.push(lastpix);
+    }
+  }
+
+  //(R) interpret the pixels as integers of the right size
+  Ok(match bytepix {
+    1 => pixels.into_iter().map(|pix| pix as u8 as i32).collect(),
+    2 => pixels.into_iter().map(|pix| pix as u16 as i16 as i32).collect(),
+    _ => pixels.into_iter().map(|pix| pix as i32).collect(),
+  })
+}
@@ -35,6 +35,10 @@ pub(crate) enum TableEntryFormat {
   Char(usize),
   Int(usize),
   Float((usize, usize)),
+  Double((usize, usize)),
+  General((usize, usize)),
+  Logical(usize),
+  Bit(usize),
   Invalid(String),
 }
 
@@ -58,15 +62,22 @@ impl TableEntryFormat {
 
     if rem.len() == 2 {
       //These format types have both a {w} and a {d} value
+      let (w, d) = (str::parse::<usize>(rem[0])?, str::parse::<usize>(rem[1])?);
       match dtype {
-        'F' | 'E' | 'D' => Ok(Float((str::parse::<usize>(rem[0])?, str::parse::<usize>(rem[1])?))),
+        'F' | 'E' => Ok(Float((w, d))),
+        'D' => Ok(Double((w, d))),
+        'G' => Ok(General((w, d))),
         _ => Ok(Invalid(String::from(parsed_code))),
       }
     } else if rem.len() == 1 {
       //These format types only have a w value
+      //Unsigned bytes (B) are just (positive) integers
+      let w = str::parse::<usize>(rem[0])?;
       match dtype {
-        'A' => Ok(Char(str::parse::<usize>(rem[0])?)),
-        'I' => Ok(Int(str::parse::<usize>(rem[0])?)),
+        'A' => Ok(Char(w)),
+        'I' | 'B' => Ok(Int(w)),
+        'L' => Ok(Logical(w)),
+        'X' => Ok(Bit(w)),
         _ => Ok(Invalid(String::from(parsed_code))),
       }
     } else {
@@ -81,6 +92,10 @@ impl TableEntryFormat {
       Char(w) => format!("A{w}"),
       Int(w) => format!("I{w}"),
       Float((w, d)) => format!("E{w}.{d}"),
+      Double((w, d)) => format!("D{w}.{d}"),
+      General((w, d)) => format!("G{w}.{d}"),
+      Logical(w) => format!("L{w}"),
+      Bit(w) => format!("X{w}"),
       Invalid(val) => return Err(IFFCErr::new(val.to_string())),
     })
   }
@@ -91,6 +106,10 @@ impl TableEntryFormat {
       Char(w) => *w,
       Int(w) => *w,
       Float((w, _d)) => *w,
+      Double((w, _d)) => *w,
+      General((w, _d)) => *w,
+      Logical(w) => *w,
+      Bit(w) => *w,
       Invalid(string) => string.len(),
     }
   }
@@ -105,7 +124,9 @@ impl Display for TableEntryFormat {
       match self {
         Char(_) => "string",
         Int(_) => "integer",
-        Float(_) => "float",
+        Float(_) | Double(_) | General(_) => "float",
+        Logical(_) => "logical",
+        Bit(_) => "bits",
         Invalid(_) => "INVALID",
       }
     )?;
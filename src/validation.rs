@@ -0,0 +1,358 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*  Description:
+    Checks FITS files against the requirements that the FITS standard puts on
+    their headers. The decoder accepts some files that bend these rules, the
+    validator reports them. Violations of the standard are errors, things
+    that are allowed but probably unintended are warnings. Things that are
+    fine but discouraged by the standard (deprecated keywords) are reported
+    for information only.
+*/
+
+use std::{
+  error::Error,
+  fmt::{self, Display, Formatter},
+  path::Path,
+};
+
+use crate::{
+  bitpix::Bitpix,
+  fits::Fits,
+  header::Header,
+  header_data_unit::HeaderDataUnit,
+  header_err::InvalidHeaderErr,
+  io_err::InvalidFitsFileErr,
+  options::ParseMode,
+  raw::{keyword_utils::unquote_fits_string, raw_io::RawFitsReader},
+};
+
+const BLOCK_SIZE: usize = crate::BLOCK_SIZE; // = 2880B
+
+//Checks the value of PCOUNT or GCOUNT
+type CountCheck = fn(i64) -> bool;
+
+//Checks the type of the value of a reserved keyword
+type TypeCheck = fn(&Header, &str) -> bool;
+
+//Reserved keywords that are not mandatory, and the type of their value
+const RESERVED_TYPES: [(&str, &str, TypeCheck); 14] = [
+  ("EXTEND", "logical", |hdr, key| hdr.get_bool(key).is_some()),
+  ("INHERIT", "logical", |hdr, key| hdr.get_bool(key).is_some()),
+  ("BLANK", "an integer", |hdr, key| hdr.get_int(key).is_some()),
+  ("EXTVER", "an integer", |hdr, key| hdr.get_int(key).is_some()),
+  ("EXTLEVEL", "an integer", |hdr, key| hdr.get_int(key).is_some()),
+  ("BSCALE", "a real number", |hdr, key| hdr.get_float(key).is_some()),
+  ("BZERO", "a real number", |hdr, key| hdr.get_float(key).is_some()),
+  ("DATAMIN", "a real number", |hdr, key| hdr.get_float(key).is_some()),
+  ("DATAMAX", "a real number", |hdr, key| hdr.get_float(key).is_some()),
+  ("EQUINOX", "a real number", |hdr, key| hdr.get_float(key).is_some()),
+  ("XTENSION", "a string", |hdr, key| hdr.get_string(key).is_some()),
+  ("EXTNAME", "a string", |hdr, key| hdr.get_string(key).is_some()),
+  ("BUNIT", "a string", |hdr, key| hdr.get_string(key).is_some()),
+  ("DATE-OBS", "a string", |hdr, key| hdr.get_string(key).is_some()),
+];
+
+//Keywords that the standard no longer recommends, and their replacement
+const DEPRECATED: [(&str, &str); 2] =
+  [("EPOCH", "EQUINOX"), ("BLOCKED", "nothing (it has no effect)")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  //THIS ENUM IS PART OF THE USER-FACING API
+  Info,
+  Warning,
+  Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      A single violation of the standard. The keyword is None for issues that
+      do not concern a single keyword. Issues found in a whole file have the
+      index of the HDU they were found in (which also starts the description).
+      Issues that do not belong to any HDU (such as a file that is not a
+      whole number of FITS blocks) have no index.
+  */
+  pub hdu_index: Option<usize>,
+  pub severity: Severity,
+  pub keyword: Option<String>,
+  pub description: String,
+}
+
+impl ValidationIssue {
+  fn new(severity: Severity, keyword: Option<&str>, description: String) -> Self {
+    ValidationIssue { hdu_index: None, severity, keyword: keyword.map(str::to_string), description }
+  }
+
+  fn error(keyword: &str, description: String) -> Self {
+    Self::new(Severity::Error, Some(keyword), description)
+  }
+
+  fn warning(keyword: &str, description: String) -> Self {
+    Self::new(Severity::Warning, Some(keyword), description)
+  }
+
+  fn info(keyword: &str, description: String) -> Self {
+    Self::new(Severity::Info, Some(keyword), description)
+  }
+
+  fn in_hdu(mut self, index: usize) -> Self {
+    self.hdu_index = Some(index);
+    self.description = format!("HDU {index}: {}", self.description);
+    self
+  }
+}
+
+impl Display for ValidationIssue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Info => "info",
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    match &self.keyword {
+      Some(keyword) => write!(f, "[{severity}] {keyword}: {}", self.description),
+      None => write!(f, "[{severity}] {}", self.description),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FitsValidator {}
+
+impl FitsValidator {
+  pub fn new() -> Self {
+    FitsValidator {}
+  }
+
+  pub fn validate_fits(&self, fits: &Fits) -> Vec<ValidationIssue> {
+    //Validates all HDU's, the first one is the primary HDU
+    fits
+      .iter()
+      .enumerate()
+      .flat_map(|(index, hdu)| {
+        self.validate_hdu(hdu, index == 0).into_iter().map(move |issue| issue.in_hdu(index))
+      })
+      .collect()
+  }
+
+  pub fn validate_hdu(&self, hdu: &HeaderDataUnit, is_primary: bool) -> Vec<ValidationIssue> {
+    self.validate_header(hdu.get_header(), is_primary)
+  }
+
+  pub fn validate_header(&self, header: &Header, is_primary: bool) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    //(1) Primary headers start with SIMPLE = T, extensions with XTENSION
+    let first = header.iter().next().map(|(keyword, _, _)| keyword);
+    let expected = if is_primary { "SIMPLE" } else { "XTENSION" };
+    if first != Some(expected) {
+      let found = first.unwrap_or("nothing");
+      issues.push(ValidationIssue::error(expected, format!("header starts with {found}")));
+    }
+    if is_primary && header.contains("SIMPLE") && header.get_bool("SIMPLE") != Some(true) {
+      issues.push(ValidationIssue::error("SIMPLE", String::from("value must be T")));
+    }
+
+    //(2) The mandatory keywords describing the data
+    match header.get_int("BITPIX") {
+      Some(bitpix) if Bitpix::try_from(bitpix).is_ok() => {}
+      Some(bitpix) => {
+        issues.push(ValidationIssue::error("BITPIX", format!("invalid value {bitpix}")))
+      }
+      None => issues.push(Self::missing_int(header, "BITPIX")),
+    }
+    let naxis = match header.get_int("NAXIS") {
+      Some(naxis) if (0..=999).contains(&naxis) => naxis as usize,
+      Some(naxis) => {
+        issues.push(ValidationIssue::error("NAXIS", format!("invalid value {naxis}")));
+        0
+      }
+      None => {
+        issues.push(Self::missing_int(header, "NAXIS"));
+        0
+      }
+    };
+    issues.extend(Self::check_axes(header, naxis));
+    issues.extend(Self::check_counts(header, is_primary));
+
+    //(3) Keywords consist of (at most 8) uppercase letters, digits, hyphens
+    //    and underscores. HIERARCH keywords (stored with dots) are exempt
+    for (keyword, _, _) in header.iter() {
+      let valid_char =
+        |c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_';
+      if !keyword.contains('.') && (keyword.len() > 8 || !keyword.chars().all(valid_char)) {
+        let description = String::from("keywords must be uppercase ASCII without spaces");
+        issues.push(ValidationIssue::error(keyword, description));
+      }
+    }
+
+    //(4) Reserved keywords that are not mandatory still need the right type
+    for (keyword, type_name, has_type) in RESERVED_TYPES {
+      if header.contains(keyword) && !has_type(header, keyword) {
+        let description = format!("value must be {type_name}");
+        issues.push(ValidationIssue::warning(keyword, description));
+      }
+    }
+    for (keyword, replacement) in DEPRECATED {
+      if header.contains(keyword) {
+        let description = format!("keyword is deprecated, use {replacement}");
+        issues.push(ValidationIssue::info(keyword, description));
+      }
+    }
+
+    issues
+  }
+
+  pub fn validate_file(&self, path: &Path) -> Result<Vec<ValidationIssue>, Box<dyn Error>> {
+    /*
+        Same as validate_fits(), but for a file that has not been decoded.
+        Only the headers are read, so this also works for files of which the
+        data cannot be decoded. A header without END record ends the file.
+        Files that are not a whole number of FITS blocks are validated up to
+        their last whole block.
+    */
+    let file_len = std::fs::metadata(path)?.len() as usize;
+    if file_len % BLOCK_SIZE == 0 {
+      return self.validate_reader(&mut RawFitsReader::new(path)?, Vec::new());
+    }
+
+    //(R) The last block is incomplete, only the part before it is validated
+    let mut bytes = std::fs::read(path)?;
+    bytes.truncate(file_len - file_len % BLOCK_SIZE);
+    let description = format!("file size is not a multiple of {BLOCK_SIZE} bytes");
+    let issues = vec![ValidationIssue::new(Severity::Error, None, description)];
+    self.validate_reader(&mut RawFitsReader::from_bytes(&bytes)?, issues)
+  }
+
+  fn validate_reader(
+    &self,
+    reader: &mut RawFitsReader,
+    mut issues: Vec<ValidationIssue>,
+  ) -> Result<Vec<ValidationIssue>, Box<dyn Error>> {
+    let mut index = 0;
+    while reader.get_blocks_remaining() > 0 {
+      //(1) Decode the header, the file ends before END if reading fails
+      let header = match Header::decode_header(reader, ParseMode::Strict) {
+        Ok(header) => header,
+        Err(err) => {
+          let issue = match (err.downcast_ref(), err.downcast_ref::<InvalidFitsFileErr>()) {
+            (Some(InvalidHeaderErr::DuplicateKeyword { key }), _) => {
+              ValidationIssue::error(key, err.to_string())
+            }
+            (_, Some(_)) => ValidationIssue::error("END", String::from("header has no END record")),
+            _ => {
+              let description = format!("header cannot be decoded ({err})");
+              ValidationIssue::new(Severity::Error, None, description)
+            }
+          };
+          issues.push(issue.in_hdu(index));
+          break;
+        }
+      };
+      issues
+        .extend(self.validate_header(&header, index == 0).into_iter().map(|iss| iss.in_hdu(index)));
+
+      //(2) Skip the data unit
+      let remaining = reader.get_blocks_remaining();
+      match header.get_data_block_len() {
+        Ok(data_blocks) if data_blocks <= remaining => reader.skip_blocks(data_blocks)?,
+        _ => {
+          let description = String::from("data unit does not match the size given by the header");
+          let issue = ValidationIssue::new(Severity::Error, None, description);
+          issues.push(issue.in_hdu(index));
+          break;
+        }
+      }
+      index += 1;
+    }
+
+    Ok(issues)
+  }
+
+  fn missing(keyword: &str) -> ValidationIssue {
+    ValidationIssue::error(keyword, String::from("mandatory keyword is missing"))
+  }
+
+  fn missing_int(header: &Header, keyword: &str) -> ValidationIssue {
+    //Mandatory integer keywords are either missing, or have the wrong type
+    if header.contains(keyword) {
+      ValidationIssue::error(keyword, String::from("value must be an integer"))
+    } else {
+      Self::missing(keyword)
+    }
+  }
+
+  fn check_axes(header: &Header, naxis: usize) -> Vec<ValidationIssue> {
+    //NAXIS1 up to NAXISn describe the shape of the data, there should be
+    //exactly NAXIS of them
+    let mut issues = Vec::new();
+    for i in 1..=naxis {
+      let keyword = format!("NAXIS{i}");
+      match header.get_int(&keyword) {
+        Some(len) if len >= 0 => {}
+        Some(len) => issues.push(ValidationIssue::error(&keyword, format!("invalid value {len}"))),
+        None => issues.push(Self::missing_int(header, &keyword)),
+      }
+    }
+    let Err(err) = header.check_naxis() else {
+      return issues;
+    };
+    for (keyword, _, _) in header.iter() {
+      let axis = keyword.strip_prefix("NAXIS").and_then(|axis| axis.parse::<usize>().ok());
+      if axis.is_some_and(|axis| axis > naxis) {
+        issues.push(ValidationIssue::warning(keyword, err.to_string()));
+      }
+    }
+    issues
+  }
+
+  fn check_counts(header: &Header, is_primary: bool) -> Vec<ValidationIssue> {
+    /*
+        PCOUNT and GCOUNT are mandatory for extensions and random groups:
+            IMAGE and TABLE => PCOUNT = 0, GCOUNT = 1
+            BINTABLE => PCOUNT >= 0 (size of the heap), GCOUNT = 1
+            random groups => PCOUNT >= 0, GCOUNT >= 1
+        Other extensions only need non-negative values.
+    */
+    let xtension =
+      header.get_value("XTENSION").map(|val| unquote_fits_string(val).trim().to_string());
+    let groups = header.get_bool("GROUPS") == Some(true);
+    let (pcount, gcount): (CountCheck, CountCheck) = match xtension.as_deref() {
+      _ if is_primary && !groups => return Vec::new(),
+      None => (|p| p >= 0, |g| g >= 1),
+      Some("IMAGE" | "TABLE") => (|p| p == 0, |g| g == 1),
+      Some("BINTABLE") => (|p| p >= 0, |g| g == 1),
+      Some(_) => (|p| p >= 0, |g| g >= 0),
+    };
+
+    let mut issues = Vec::new();
+    for (keyword, valid) in [("PCOUNT", pcount), ("GCOUNT", gcount)] {
+      match header.get_int(keyword) {
+        Some(count) if valid(count) => {}
+        Some(count) => {
+          issues.push(ValidationIssue::error(keyword, format!("invalid value {count}")))
+        }
+        None => issues.push(Self::missing_int(header, keyword)),
+      }
+    }
+    issues
+  }
+}
@@ -0,0 +1,172 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use crate::header::Header;
+
+//Default equinox of celestial coordinates, if EQUINOX (or EPOCH) is missing
+const DEFAULT_EQUINOX: f64 = 2000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wcs {
+  /*  THIS STRUCT IS PART OF THE USER-FACING API
+      World Coordinate System of an image, as described by the WCS keywords
+      in its header. The first two axes are the celestial axes, all other
+      axes are linear. Pixel coordinates follow the FITS convention: the
+      centre of the first pixel is at 1.0.
+  */
+  pub ctypes: Vec<String>,
+  pub crpix: Vec<f64>,
+  pub crval: Vec<f64>,
+  pub cdelt: Vec<f64>,
+  pub cd_matrix: Option<[[f64; 2]; 2]>,
+  pub equinox: f64,
+}
+
+impl Wcs {
+  pub fn from_header(header: &Header) -> Option<Self> {
+    /*
+        Reads the WCS keywords from the header. CTYPE, CRPIX and CRVAL are
+        mandatory for the first two axes, as is either CDELT or the CD matrix
+        (which takes precedence over CDELT). Returns None if any of these is
+        missing.
+    */
+
+    //(1) Number of WCS axes (at least the two celestial axes)
+    let naxis = header.get_int("WCSAXES").or_else(|| header.get_int("NAXIS")).unwrap_or(2);
+    let naxis = naxis.max(2) as usize;
+
+    //(2) Per-axis keywords, with the FITS defaults for the optional axes
+    let (mut ctypes, mut crpix, mut crval, mut cdelt) = (vec![], vec![], vec![], vec![]);
+    for i in 1..=naxis {
+      let mandatory = i <= 2;
+      let ctype = header.get_string(&format!("CTYPE{i}"));
+      let pix = header.get_float(&format!("CRPIX{i}"));
+      let val = header.get_float(&format!("CRVAL{i}"));
+      if mandatory && (ctype.is_none() || pix.is_none() || val.is_none()) {
+        return None;
+      }
+      ctypes.push(ctype.unwrap_or_default());
+      crpix.push(pix.unwrap_or(0.0));
+      crval.push(val.unwrap_or(0.0));
+      cdelt.push(header.get_float(&format!("CDELT{i}")).unwrap_or(1.0));
+    }
+
+    //(3) The CD matrix is only used if it is complete
+    let cd = |i, j| header.get_float(&format!("CD{i}_{j}"));
+    let cd_matrix = match (cd(1, 1), cd(1, 2), cd(2, 1), cd(2, 2)) {
+      (Some(cd11), Some(cd12), Some(cd21), Some(cd22)) => Some([[cd11, cd12], [cd21, cd22]]),
+      _ => None,
+    };
+    let has_cdelt = header.contains("CDELT1") && header.contains("CDELT2");
+    if cd_matrix.is_none() && !has_cdelt {
+      return None;
+    }
+
+    //(4) EPOCH is the deprecated predecessor of EQUINOX
+    let equinox =
+      header.get_float("EQUINOX").or_else(|| header.get_float("EPOCH")).unwrap_or(DEFAULT_EQUINOX);
+
+    Some(Wcs { ctypes, crpix, crval, cdelt, cd_matrix, equinox })
+  }
+
+  pub fn projection(&self) -> Option<&str> {
+    //Projection code of the celestial axes ("TAN" for RA---TAN), if any
+    let code = self.ctypes[0].get(5..8)?;
+    match self.ctypes[0].as_bytes()[4] == b'-' {
+      true => Some(code),
+      false => None,
+    }
+  }
+
+  pub fn pixel_to_world(&self, pixel: &[f64]) -> Vec<f64> {
+    /*
+        Converts pixel coordinates into world coordinates (in degrees for the
+        celestial axes). Celestial axes with a projection that is not
+        supported yet are returned as NaN.
+    */
+
+    //(1) Intermediate world coordinates of the celestial axes
+    let (dx, dy) = (pixel[0] - self.crpix[0], pixel[1] - self.crpix[1]);
+    let (x, y) = match self.cd_matrix {
+      Some([[cd11, cd12], [cd21, cd22]]) => (cd11 * dx + cd12 * dy, cd21 * dx + cd22 * dy),
+      None => (self.cdelt[0] * dx, self.cdelt[1] * dy),
+    };
+
+    //(2) Deproject the celestial axes
+    let (lon, lat) = match self.projection() {
+      None => (self.crval[0] + x, self.crval[1] + y),
+      Some("TAN") => self.tan_deproject(x, y),
+      Some(_) => (f64::NAN, f64::NAN),
+    };
+
+    //(3) All other axes are linear
+    let linear = (2..self.crpix.len().min(pixel.len()))
+      .map(|i| self.crval[i] + self.cdelt[i] * (pixel[i] - self.crpix[i]));
+    [lon, lat].into_iter().chain(linear).collect()
+  }
+
+  pub fn world_to_pixel(&self, world: &[f64]) -> Vec<f64> {
+    //Inverse of pixel_to_world
+    //(1) Project the celestial axes
+    let (x, y) = match self.projection() {
+      None => (world[0] - self.crval[0], world[1] - self.crval[1]),
+      Some("TAN") => self.tan_project(world[0], world[1]),
+      Some(_) => (f64::NAN, f64::NAN),
+    };
+
+    //(2) Invert the CD matrix (or CDELT) to get pixel offsets
+    let (dx, dy) = match self.cd_matrix {
+      Some([[cd11, cd12], [cd21, cd22]]) => {
+        let det = cd11 * cd22 - cd12 * cd21;
+        ((cd22 * x - cd12 * y) / det, (cd11 * y - cd21 * x) / det)
+      }
+      None => (x / self.cdelt[0], y / self.cdelt[1]),
+    };
+
+    //(3) All other axes are linear
+    let linear = (2..self.crpix.len().min(world.len()))
+      .map(|i| self.crpix[i] + (world[i] - self.crval[i]) / self.cdelt[i]);
+    [self.crpix[0] + dx, self.crpix[1] + dy].into_iter().chain(linear).collect()
+  }
+
+  /*
+      Gnomonic (TAN) projection, with the native pole at the default position
+      for zenithal projections. Intermediate coordinates (x, y) are in
+      degrees, just like the world coordinates.
+  */
+  fn tan_deproject(&self, x: f64, y: f64) -> (f64, f64) {
+    let (xi, eta) = (x.to_radians(), y.to_radians());
+    let (lon0, lat0) = (self.crval[0].to_radians(), self.crval[1].to_radians());
+
+    let denom = lat0.cos() - eta * lat0.sin();
+    let lon = lon0 + xi.atan2(denom);
+    let lat = (lat0.sin() + eta * lat0.cos()).atan2(xi.hypot(denom));
+    (lon.to_degrees().rem_euclid(360.0), lat.to_degrees())
+  }
+
+  fn tan_project(&self, lon: f64, lat: f64) -> (f64, f64) {
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    let (lon0, lat0) = (self.crval[0].to_radians(), self.crval[1].to_radians());
+
+    let cos_c = lat.sin() * lat0.sin() + lat.cos() * lat0.cos() * (lon - lon0).cos();
+    let xi = lat.cos() * (lon - lon0).sin() / cos_c;
+    let eta = (lat.sin() * lat0.cos() - lat.cos() * lat0.sin() * (lon - lon0).cos()) / cos_c;
+    (xi.to_degrees(), eta.to_degrees())
+  }
+}
@@ -0,0 +1,775 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use rustronomy_fits as rsf;
+
+//All bundled files that only contain images and ASCII tables
+static ROUND_TRIP_FILES: [&str; 8] = [
+  "Astro_UIT.fits",
+  "Hubble_FGS.fits",
+  "Hubble_FOC.fits",
+  "Hubble_FOS.fits",
+  "Hubble_HRS.fits",
+  "Hubble_NICMOS.fits",
+  "Hubble_WFPC2_1.fits",
+  "Hubble_WFPC2_2.fits",
+];
+
+fn assert_same_data(original: &rsf::Extension, tested: &rsf::Extension) {
+  match (original, tested) {
+    (rsf::Extension::Image(a), rsf::Extension::Image(b)) => {
+      //Compare as f64, since the type is checked by the bitpix anyway
+      assert_eq!(a.sum(), b.sum());
+      assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+    (rsf::Extension::AsciiTable(a), rsf::Extension::AsciiTable(b)) => {
+      assert_eq!(a.get_shape(), b.get_shape());
+      let (n_cols, n_rows) = a.get_shape();
+      for col in 0..n_cols {
+        for row in 0..n_rows {
+          match (a.get_entry(col, row).unwrap(), b.get_entry(col, row).unwrap()) {
+            //Floats are written with 15 digits after the decimal point
+            (rsf::TableEntry::Float(x), rsf::TableEntry::Float(y)) => {
+              assert!((x - y).abs() <= 1e-14 * x.abs())
+            }
+            (x, y) => assert_eq!(x, y),
+          }
+        }
+      }
+    }
+    _ => panic!("data changed type during the round trip"),
+  }
+}
+
+fn assert_same_hdus(original: &rsf::Fits, tested: &rsf::Fits, file: &str) {
+  let mut index = 0;
+  while let Some(hdu) = original.get_hdu(index) {
+    let copy = tested.get_hdu(index).unwrap();
+    assert_eq!(hdu.get_extname(), copy.get_extname(), "{file}");
+    match (hdu.get_data(), copy.get_data()) {
+      (None, None) => {}
+      (Some(a), Some(b)) => assert_same_data(a, b),
+      _ => panic!("{file}: HDU #{index} lost or gained data"),
+    }
+    index += 1;
+  }
+  assert!(tested.get_hdu(index).is_none(), "{file}");
+}
+
+#[test]
+fn round_trip_test() {
+  for file in ROUND_TRIP_FILES {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+
+    //(1) Read the file and write a copy
+    let original = rsf::Fits::open(&path).unwrap();
+    let mut copy_path = dirs::cache_dir().unwrap();
+    copy_path.push(format!("round_trip_{file}"));
+    original.clone().write(&copy_path).unwrap();
+
+    //(2) Read back the copy and compare all HDU's
+    let tested = rsf::Fits::open(&copy_path).unwrap();
+    assert_same_hdus(&original, &tested, file);
+  }
+}
+
+#[test]
+fn from_bytes_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+
+  //Reading from memory should give the same result as reading the file
+  let bytes = std::fs::read(&path).unwrap();
+  let from_file = rsf::Fits::open(&path).unwrap();
+  let from_mem = rsf::Fits::from_bytes(&bytes).unwrap();
+  assert_same_hdus(&from_file, &from_mem, "Hubble_NICMOS.fits");
+
+  //Buffers must consist of whole FITS blocks
+  assert!(rsf::Fits::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn to_bytes_test() {
+  for file in ROUND_TRIP_FILES {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let original = rsf::Fits::open(&path).unwrap();
+
+    //(1) The buffer is the same as the file written by write()
+    let bytes = original.to_bytes().unwrap();
+    let mut copy_path = dirs::cache_dir().unwrap();
+    copy_path.push(format!("to_bytes_{file}"));
+    original.clone().write(&copy_path).unwrap();
+    assert_eq!(bytes, std::fs::read(&copy_path).unwrap(), "{file}");
+
+    //(2) Reading it back gives the same HDU's, which encode to the same bytes
+    let tested = rsf::Fits::from_bytes(&bytes).unwrap();
+    assert_same_hdus(&original, &tested, file);
+    assert_eq!(tested.to_bytes().unwrap(), bytes, "{file}");
+  }
+}
+
+#[test]
+#[cfg(feature = "mmap")]
+fn mmap_test() {
+  //Memory-mapped files should decode exactly like files that are read
+  for file in ROUND_TRIP_FILES {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let read = rsf::Fits::open(&path).unwrap();
+    let mapped = rsf::Fits::open_mmap(&path).unwrap();
+    assert_same_hdus(&read, &mapped, file);
+  }
+}
+
+#[test]
+#[cfg(feature = "gz")]
+fn gz_test() {
+  //Compressed files should decode exactly like their uncompressed originals
+  for file in ["Astro_UIT.fits", "Hubble_FOS.fits", "Hubble_NICMOS.fits"] {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let original = rsf::Fits::open(&path).unwrap();
+    path.set_file_name(format!("{file}.gz"));
+    let decompressed = rsf::Fits::open(&path).unwrap();
+    assert_same_hdus(&original, &decompressed, file);
+
+    //Compressed files cannot be seeked in
+    assert!(rsf::Fits::open_lazy(&path).is_err());
+    assert!(rsf::Fits::scan_headers_only(&path).is_err());
+  }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_read_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+
+  //Reading asynchronously should give the same result as reading the file
+  let mut file = tokio::fs::File::open(&path).await.unwrap();
+  let from_async = rsf::Fits::read_from_async(&mut file).await.unwrap();
+  let from_file = rsf::Fits::open(&path).unwrap();
+  assert_same_hdus(&from_file, &from_async, "Hubble_NICMOS.fits");
+
+  //Boxed readers and references to readers are readers too
+  let mut boxed = Box::new(tokio::fs::File::open(&path).await.unwrap());
+  let from_boxed = rsf::Fits::read_from_async(&mut boxed).await.unwrap();
+  assert_same_hdus(&from_file, &from_boxed, "Hubble_NICMOS.fits");
+  let mut file = tokio::fs::File::open(&path).await.unwrap();
+  let from_ref = rsf::Fits::read_from_async(&mut &mut file).await.unwrap();
+  assert_same_hdus(&from_file, &from_ref, "Hubble_NICMOS.fits");
+
+  //Sources that hand out their own buffers only implement read_blocks
+  struct Chunks(std::collections::VecDeque<Vec<u8>>);
+  impl rsf::AsyncFitsReader for Chunks {
+    async fn read_blocks(
+      &mut self,
+      n_blocks: usize,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+      //one block at a time, whatever the number of requested blocks
+      assert!(n_blocks > 0);
+      Ok(self.0.pop_front().unwrap_or_default())
+    }
+
+    fn blocks_remaining(&self) -> Option<usize> {
+      Some(self.0.len())
+    }
+  }
+  let bytes = std::fs::read(&path).unwrap();
+  let mut chunks = Chunks(bytes.chunks(2880).map(<[u8]>::to_vec).collect());
+  assert_eq!(rsf::AsyncFitsReader::blocks_remaining(&chunks), Some(bytes.len() / 2880));
+  assert_eq!(rsf::AsyncFitsReader::source_len_blocks(&&mut chunks), None);
+  let from_chunks = rsf::Fits::read_from_async(&mut chunks).await.unwrap();
+  assert_same_hdus(&from_file, &from_chunks, "Hubble_NICMOS.fits");
+  assert_eq!(rsf::AsyncFitsReader::blocks_remaining(&chunks), Some(0));
+}
+
+#[test]
+fn lazy_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+
+  //Nothing is decoded until the data is accessed
+  let lazy = rsf::Fits::open_lazy(&path).unwrap();
+  let hdu = lazy.get_hdu(1).unwrap();
+  assert!(hdu.pretty_print_data().contains("DEFERRED"));
+  assert_eq!(hdu.get_extname().as_deref(), Some("SCI"));
+  assert!(hdu.get_data().is_some());
+  assert!(!hdu.pretty_print_data().contains("DEFERRED"));
+
+  //Deferred data is decoded without scaling
+  for file in ROUND_TRIP_FILES {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let eager = rsf::Fits::open_with_scaling(&path, rsf::ScalingMode::Raw).unwrap();
+    let lazy = rsf::Fits::open_lazy(&path).unwrap();
+    assert_same_hdus(&eager, &lazy, file);
+
+    //Lazy files can be written like any other file
+    let mut copy_path = dirs::cache_dir().unwrap();
+    copy_path.push(format!("lazy_{file}"));
+    rsf::Fits::open_lazy(&path).unwrap().write(&copy_path).unwrap();
+    let copy = rsf::Fits::open_with_scaling(&copy_path, rsf::ScalingMode::Raw).unwrap();
+    assert_same_hdus(&eager, &copy, file);
+  }
+}
+
+#[test]
+fn iter_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+
+  //Iterating by reference leaves the file intact
+  let names = fits.iter().map(|hdu| hdu.get_extname().map(|n| n.into_owned())).collect::<Vec<_>>();
+  assert_eq!(names.len(), 6);
+  assert_eq!(names[0], None);
+  assert_eq!(names[3].as_deref(), Some("DQ"));
+  assert_eq!((&fits).into_iter().count(), 6);
+  assert!(fits.primary().unwrap().get_data().is_none());
+
+  //Consuming the file yields the HDU's in order
+  let hdus = fits.into_iter().collect::<Vec<_>>();
+  assert_eq!(hdus[3].get_extname().as_deref(), Some("DQ"));
+}
+
+#[test]
+fn random_groups_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/RandomGroups.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+
+  //The primary HDU contains the groups, followed by a binary table
+  assert_eq!(fits.iter().count(), 2);
+  let groups = match fits.primary().unwrap().get_data() {
+    Some(rsf::Extension::RandomGroups(groups)) => groups,
+    _ => panic!("primary HDU should contain random groups"),
+  };
+  assert_eq!(groups.get_n_groups(), 7956);
+  assert_eq!(groups.get_shape(), &vec![3, 4, 1, 1, 1]);
+  assert_eq!(groups.get_param_names().len(), 6);
+  assert_eq!(groups.get_param_names()[3].as_deref(), Some("BASELINE"));
+  assert_eq!(groups.get_group(0).unwrap().shape(), &[3, 4, 1, 1, 1]);
+  assert!(groups.get_group(7956).is_none());
+
+  //Both DATE parameters are added together, the first one has PZERO set
+  let date = groups.get_param(0, "DATE").unwrap();
+  assert!(date > 2445728.5 && date < 2445730.5);
+  let params = groups.get_params(0).unwrap();
+  assert_eq!(date, params[4] + params[5]);
+  assert!(groups.get_param(0, "FOO").is_none());
+}
+
+fn block(records: &[&[u8]]) -> Vec<u8> {
+  //Pads records to 80 bytes and blocks to 2880 bytes
+  let mut block = Vec::new();
+  for record in records {
+    block.extend_from_slice(record);
+    block.resize(block.len().next_multiple_of(80), b' ');
+  }
+  block.resize(2880, b' ');
+  block
+}
+
+#[test]
+fn lenient_test() {
+  //A primary header with sloppy values, a table with the wrong BITPIX and
+  //some trailing garbage
+  let mut bytes = block(&[
+    b"SIMPLE  =                    T",
+    b"BITPIX  =                    8",
+    b"NAXIS   =                    0",
+    b"OBJECT  = NGC1234",
+    b"TELESCOP= 'HST      / unterminated",
+    b"NOTE    = 'caf\xc3\xa9'",
+    b"EXPTIME =                 0010",
+    b"END",
+  ]);
+  bytes.extend(block(&[
+    b"XTENSION= 'TABLE   '",
+    b"BITPIX  =                   16",
+    b"NAXIS   =                    2",
+    b"NAXIS1  =                    4",
+    b"NAXIS2  =                    2",
+    b"PCOUNT  =                    0",
+    b"GCOUNT  =                    1",
+    b"TFIELDS =                    1",
+    b"TBCOL1  =                    1",
+    b"TFORM1  = 'I4      '",
+    b"END",
+  ]));
+  bytes.extend(block(&[b"   1   2"]));
+  bytes.extend(vec![0u8; 2880]);
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("lenient_test.fits");
+  std::fs::write(&path, &bytes).unwrap();
+
+  //Strict mode (the default) rejects the file
+  assert!(rsf::Fits::open(&path).is_err());
+  let strict = rsf::FitsReadOptions::new().parse_mode(rsf::ParseMode::Strict);
+  assert!(rsf::Fits::open_with_options(&path, strict).is_err());
+
+  //Lenient mode repairs what it can
+  let fits = rsf::Fits::open_with_options(&path, rsf::FitsReadOptions::new().lenient()).unwrap();
+  assert_eq!(fits.iter().count(), 2);
+  let header = fits.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_string("OBJECT").as_deref(), Some("NGC1234"));
+  assert_eq!(header.get_string("TELESCOP").as_deref(), Some("HST      / unterminated"));
+  assert!(!header.contains("NOTE"));
+  assert_eq!(header.get_int("EXPTIME"), Some(10));
+  match fits.get_hdu(1).unwrap().get_data() {
+    Some(rsf::Extension::AsciiTable(tbl)) => assert_eq!(tbl.get_shape(), (1, 2)),
+    other => panic!("expected an ASCII table, got {other:?}"),
+  }
+}
+
+#[test]
+fn options_display_test() {
+  let opts = rsf::FitsReadOptions::new();
+  assert_eq!(format!("{opts}"), "FitsReadOptions { parse_mode: strict, scaling: auto }");
+  let opts = opts.lenient().scaling(rsf::ScalingMode::Raw);
+  assert_eq!(format!("{opts}"), "FitsReadOptions { parse_mode: lenient, scaling: raw }");
+
+  //HDU summaries show the shape and data type of the HDU
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let summary = rsf::Fits::scan_headers_only(&path).unwrap();
+  assert_eq!(
+    format!("{}", summary[3]),
+    "HduSummary { slot: 3, extname: DQ, data_type: IMAGE, dim: 2, shape: [270, 263], bitpix: 16 }"
+  );
+  assert_eq!(
+    format!("{}", summary[0]),
+    "HduSummary { slot: 0, extname: -, data_type: -, dim: 0, shape: [], bitpix: 16 }"
+  );
+}
+
+#[test]
+fn extname_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //Lookup by name also returns the index of the HDU
+  let (index, hdu) = fits.get_hdu_by_extname("DQ").unwrap();
+  assert_eq!(index, 3);
+  assert_eq!((hdu.get_extver(), hdu.get_extlevel()), (1, 1));
+  assert!(fits.get_hdu_by_extname("dq").is_none());
+  assert!(fits.get_all_hdus_by_extname("EVENTS").is_empty());
+
+  //HDU's may share a name
+  fits.append_empty_hdu("SCI");
+  let indices = fits.get_all_hdus_by_extname("SCI").iter().map(|(i, _)| *i).collect::<Vec<_>>();
+  assert_eq!(indices, [1, 6]);
+
+  //...in which case they should differ in EXTVER
+  let mut bytes =
+    block(&[b"SIMPLE  =                    T", b"BITPIX  = 8", b"NAXIS   = 0", b"END"]);
+  for extver in [b"EXTVER  = 1", b"EXTVER  = 2"] {
+    bytes.extend(block(&[
+      b"XTENSION= 'IMAGE   '",
+      b"BITPIX  = 8",
+      b"NAXIS   = 0",
+      b"PCOUNT  = 0",
+      b"GCOUNT  = 1",
+      b"EXTNAME = 'EVENTS  '",
+      extver,
+      b"EXTLEVEL= 2",
+      b"END",
+    ]));
+  }
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let events = fits.get_all_hdus_by_extname("EVENTS");
+  let versions = events.iter().map(|(i, hdu)| (*i, hdu.get_extver())).collect::<Vec<_>>();
+  assert_eq!(versions, [(1, 1), (2, 2)]);
+  assert_eq!(events[1].1.get_extlevel(), 2);
+  assert_eq!(fits.get_hdu_by_extname("EVENTS").unwrap().0, 1);
+  assert_eq!(fits.get_hdu(0).unwrap().get_extname(), None);
+}
+
+#[test]
+fn builder_test() {
+  use ndarray::{Array, IxDyn};
+
+  //An image with metadata and a named table extension
+  let image = Array::from_shape_fn(IxDyn(&[4, 3]), |idx| (idx[0] * 10 + idx[1]) as i16);
+  let ids = rsf::Column::from_vec(Some("ID".into()), None, vec![1, 2, 3]);
+  let tbl = rsf::AsciiTable::new().with_column(ids);
+  let meta = rsf::HeaderBuilder::new()
+    .string("OBSERVER", "Hubble")
+    .keyword_with_comment("EXPTIME", "1.5", "seconds")
+    .string("ESO.DET.CHIP1.ID", "ccd");
+  let fits = rsf::FitsBuilder::new()
+    .primary(rsf::HduBuilder::image(image.clone()).header(meta))
+    .extension(rsf::HduBuilder::table(tbl).extname("EVENTS"))
+    .extension(rsf::HduBuilder::empty().extname("META"))
+    .build()
+    .unwrap();
+  assert!(fits.validate().is_empty());
+
+  //Write and read back the file
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("builder_test.fits");
+  fits.write(&path).unwrap();
+  let tested = rsf::Fits::open(&path).unwrap();
+  assert!(tested.validate().is_empty());
+  assert_eq!(tested.iter().count(), 3);
+
+  let primary = tested.get_hdu(0).unwrap();
+  let header = primary.get_header();
+  let mandatory = header.iter().take(5).map(|(keyword, _, _)| keyword).collect::<Vec<_>>();
+  assert_eq!(mandatory, ["SIMPLE", "BITPIX", "NAXIS", "NAXIS1", "NAXIS2"]);
+  assert_eq!(header.get_int("BITPIX"), Some(16));
+  assert_eq!(header.get_bool("EXTEND"), Some(true));
+  assert_eq!(header.get_string("OBSERVER").as_deref(), Some("Hubble"));
+  assert_eq!(header.get_comment("EXPTIME"), Some(&String::from("seconds")));
+  assert_eq!(header.get_string("ESO.DET.CHIP1.ID").as_deref(), Some("ccd"));
+  assert_eq!(primary.image::<i16>().unwrap(), image.view());
+
+  let (index, events) = tested.get_hdu_by_extname("EVENTS").unwrap();
+  assert_eq!(index, 1);
+  let tbl = events.table().unwrap();
+  assert_eq!(tbl.get_shape(), (1, 3));
+  assert_eq!(events.get_header().get_string("TTYPE1").as_deref(), Some("ID"));
+  assert!(tested.get_hdu_by_extname("META").unwrap().1.get_data().is_none());
+
+  //Without primary HDU an empty one is added
+  let fits = rsf::FitsBuilder::new().build().unwrap();
+  assert_eq!(fits.get_hdu(0).unwrap().get_header().get_bool("SIMPLE"), Some(true));
+
+  //Keywords are checked when the header is built
+  assert!(rsf::HeaderBuilder::is_valid_keyword("DATE-OBS"));
+  assert!(!rsf::HeaderBuilder::is_valid_keyword("date"));
+  assert!(!rsf::HeaderBuilder::is_valid_keyword("ESO..ID"));
+  const _: () = assert!(rsf::HeaderBuilder::is_valid_keyword("OBSERVER"));
+  use rsf::keyword_err::{InvalidKeywordErr, KeywordNameErrorReason};
+  let err = rsf::HeaderBuilder::new().keyword("BITPIX", "8").build().unwrap_err();
+  let err = err.downcast_ref::<InvalidKeywordErr>().unwrap();
+  assert_eq!(err.get_reason(), &KeywordNameErrorReason::Protected);
+  let err = rsf::HeaderBuilder::new().keyword("NAXIS3", "8").keyword("A", "1").build().unwrap_err();
+  let err = err.downcast_ref::<InvalidKeywordErr>().unwrap();
+  assert_eq!((err.get_name(), err.get_reason()), ("NAXIS3", &KeywordNameErrorReason::Protected));
+  assert!(rsf::HeaderBuilder::new().keyword("lower", "1").build().is_err());
+
+  //Tables cannot be stored in the primary HDU
+  let tbl = rsf::AsciiTable::new().with_column(rsf::Column::from_vec(None, None, vec![1]));
+  assert!(rsf::FitsBuilder::new().primary(rsf::HduBuilder::table(tbl)).build().is_err());
+}
+
+#[test]
+fn merge_test() {
+  let open = |name: &str| {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push(format!("resources/{name}"));
+    rsf::Fits::open(&path).unwrap()
+  };
+  let (foc, nicmos) = (open("Hubble_FOC.fits"), open("Hubble_NICMOS.fits"));
+  assert_eq!((foc.iter().count(), nicmos.iter().count()), (2, 6));
+
+  //The primary HDU of the second file becomes an IMAGE extension
+  let merged = foc.clone().merge(nicmos.clone());
+  assert_eq!(merged.iter().count(), 8);
+  let header = merged.get_hdu(2).unwrap().get_header();
+  assert!(!header.contains("SIMPLE") && !header.contains("EXTEND"));
+  assert_eq!(header.get_value("XTENSION").unwrap(), "'IMAGE   '");
+  assert_eq!(merged.primary().unwrap().get_header().get_value("EXTEND").unwrap(), "T");
+  for (i, hdu) in nicmos.iter().enumerate().skip(1) {
+    let merged_hdu = merged.get_hdu(i + 2).unwrap();
+    assert_eq!(merged_hdu.get_extname(), hdu.get_extname());
+    assert_same_data(hdu.get_data().unwrap(), merged_hdu.get_data().unwrap());
+  }
+
+  //The merged file is still valid FITS
+  let bytes = merged.to_bytes().unwrap();
+  let read = rsf::Fits::from_bytes(&bytes).unwrap();
+  assert_eq!(read.iter().count(), 8);
+  //(the FOC table header has text values for DATAMIN and DATAMAX)
+  let issues = read.validate();
+  assert!(issues.iter().all(|issue| issue.severity == rsf::Severity::Warning));
+  assert_eq!(issues.len(), 2);
+
+  //Single HDU's can be copied too, the primary image becomes an extension
+  let mut target = nicmos.clone();
+  foc.clone_hdu(0, &mut target).unwrap();
+  foc.clone_hdu(1, &mut target).unwrap();
+  assert_eq!(target.iter().count(), 8);
+  assert_same_data(
+    foc.get_hdu(0).unwrap().get_data().unwrap(),
+    target.get_hdu(6).unwrap().get_data().unwrap(),
+  );
+  assert!(target.get_hdu(6).unwrap().get_header().contains("XTENSION"));
+  assert!(foc.clone_hdu(2, &mut target).is_err());
+  let issues = rsf::Fits::from_bytes(&target.to_bytes().unwrap()).unwrap().validate();
+  assert!(issues.iter().all(|issue| issue.severity == rsf::Severity::Warning));
+  assert_eq!(issues.len(), 2);
+}
+
+#[test]
+fn reorder_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+  let names = |fits: &rsf::Fits| {
+    fits.iter().skip(1).map(|hdu| hdu.get_extname().unwrap().into_owned()).collect::<Vec<_>>()
+  };
+  assert_eq!(names(&fits), ["SCI", "ERR", "DQ", "SAMP", "TIME"]);
+
+  //Permutations map new positions to old ones
+  fits.reorder_hdus(&[0, 5, 4, 3, 2, 1]).unwrap();
+  assert_eq!(names(&fits), ["TIME", "SAMP", "DQ", "ERR", "SCI"]);
+  fits.swap_hdus(1, 5);
+  assert_eq!(names(&fits), ["SCI", "SAMP", "DQ", "ERR", "TIME"]);
+
+  //Invalid permutations leave the file untouched
+  for permutation in
+    [&[0, 1, 2, 3, 4][..], &[0, 1, 2, 3, 4, 4], &[0, 1, 2, 3, 4, 6], &[1, 0, 2, 3, 4, 5]]
+  {
+    assert!(fits.reorder_hdus(permutation).is_err());
+  }
+  let err = fits.reorder_hdus(&[0, 1, 1, 3, 4, 5]).unwrap_err();
+  assert_eq!(err.to_string(), "Error while reordering HDU's: index 1 appears twice");
+  assert_eq!(names(&fits), ["SCI", "SAMP", "DQ", "ERR", "TIME"]);
+
+  //Inserted HDU's shift the HDU's behind them
+  let dq = fits.get_hdu(3).unwrap().clone();
+  fits.insert_hdu(1, dq);
+  assert_eq!(names(&fits), ["DQ", "SCI", "SAMP", "DQ", "ERR", "TIME"]);
+  let primary = fits.get_hdu(0).unwrap().clone();
+  fits.insert_hdu(7, primary);
+  assert!(fits.get_hdu(7).unwrap().get_header().contains("XTENSION"));
+  assert!(rsf::Fits::from_bytes(&fits.to_bytes().unwrap()).unwrap().validate().is_empty());
+}
+
+#[test]
+#[should_panic]
+fn insert_primary_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+  let hdu = fits.get_hdu(1).unwrap().clone();
+  fits.insert_hdu(0, hdu);
+}
+
+#[test]
+fn index_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //Indexing is the same as get_hdu, but panics on missing HDU's
+  assert_eq!(fits[3].get_extname().as_deref(), Some("DQ"));
+  assert_eq!(format!("{}", fits[0]), format!("{}", fits.get(0).unwrap()));
+  assert!(fits.get(6).is_none());
+
+  //HDU's can be replaced and modified in place
+  fits[1] = fits[3].clone();
+  assert_eq!(fits[1].get_extname().as_deref(), Some("DQ"));
+  fits[2].get_header_mut().insert_with_comment("OBSERVER", "'me'", "").unwrap();
+  assert_eq!(fits.get_hdu(2).unwrap().get_header().get_string("OBSERVER").unwrap(), "me");
+}
+
+#[test]
+#[should_panic(expected = "HDU index 6 out of range for FITS file with 6 HDU's")]
+fn index_out_of_range_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let _ = &fits[6];
+}
+
+#[test]
+fn write_with_progress_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("progress_Hubble_NICMOS.fits");
+
+  //The callback sees the #blocks written so far, which only goes up, and it
+  //ends at 100%
+  let mut calls = Vec::new();
+  fits
+    .clone()
+    .write_with_progress(&copy_path, |written, total| calls.push((written, total)))
+    .unwrap();
+  let n_blocks = std::fs::metadata(&copy_path).unwrap().len() as usize / 2880;
+  assert!(calls.len() > 6); //at least one header and data unit per HDU
+  assert!(calls.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+  assert!(calls.iter().all(|&(written, total)| written <= total));
+  assert_eq!(calls.last(), Some(&(n_blocks, n_blocks)));
+
+  //The file itself is the same as the one written by write()
+  assert_eq!(std::fs::read(&copy_path).unwrap(), fits.to_bytes().unwrap());
+}
+
+#[test]
+fn write_synced_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_FOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let bytes = fits.to_bytes().unwrap();
+
+  //Syncing does not change what is written
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("synced_Hubble_FOS.fits");
+  fits.clone().write_synced(&copy_path).unwrap();
+  assert_eq!(std::fs::read(&copy_path).unwrap(), bytes);
+  fits.write_synced_data(&copy_path).unwrap();
+  assert_eq!(std::fs::read(&copy_path).unwrap(), bytes);
+}
+
+#[test]
+fn end_of_file_test() {
+  //The primary header of EUVE.fits spans two blocks, but we only supply one
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/EUVE.fits");
+  let bytes = std::fs::read(&path).unwrap();
+  let err = rsf::Fits::from_bytes(&bytes[..2880]).unwrap_err();
+  let err = err.downcast_ref::<rsf::io_err::InvalidFitsFileErr>().unwrap();
+  assert!(err.is_eof());
+  assert_eq!(
+    err.to_string(),
+    "Error while accessing FITS file: tried to read 1 FITS block(s), but only 0 remain"
+  );
+
+  //Streams that end halfway through a data unit report what was left
+  let summary = rsf::Fits::peek(&mut &bytes[..]).unwrap();
+  let last = summary.last().unwrap();
+  let err = rsf::Fits::peek(&mut &bytes[..bytes.len() - 2880]).unwrap_err();
+  let err = err.downcast_ref::<rsf::io_err::InvalidFitsFileErr>().unwrap();
+  assert!(err.is_eof());
+  assert!(err.to_string().ends_with(&format!(
+    "tried to read {} FITS block(s), but only {} remain",
+    last.data_blocks,
+    last.data_blocks - 1
+  )));
+
+  //Other errors are not end-of-file errors
+  let err = rsf::Fits::from_bytes(&bytes[..100]).unwrap_err();
+  assert!(!err.downcast_ref::<rsf::io_err::InvalidFitsFileErr>().unwrap().is_eof());
+}
+
+#[test]
+fn replace_data_test() {
+  use ndarray::{Array, IxDyn};
+
+  let image = Array::from_shape_fn(IxDyn(&[4, 3]), |idx| (idx[0] * 10 + idx[1]) as i16);
+  let meta = rsf::HeaderBuilder::new().string("OBSERVER", "Hubble");
+  let mut fits = rsf::FitsBuilder::new()
+    .primary(rsf::HduBuilder::image(image).header(meta))
+    .extension(rsf::HduBuilder::empty().extname("META"))
+    .build()
+    .unwrap();
+
+  //Move the metadata from the primary HDU to the extension
+  let primary = fits.get_hdu_mut(0).unwrap();
+  assert!(primary.has_meta() && primary.has_data());
+  let meta = primary.take_meta().unwrap();
+  assert!(!primary.has_meta() && primary.take_meta().is_none());
+  assert!(primary.get_header().contains("NAXIS2"));
+  let extension = fits.get_hdu_mut(1).unwrap();
+  assert!(!extension.has_data());
+  extension.set_meta(meta);
+  assert_eq!(extension.get_header().get_string("OBSERVER").as_deref(), Some("Hubble"));
+
+  //Replace the image of the primary HDU, the header follows the data
+  let bigger = Array::from_shape_fn(IxDyn(&[2, 3, 4]), |idx| idx[2] as f32);
+  let primary = fits.get_hdu_mut(0).unwrap();
+  let old =
+    primary.replace_data(rsf::Extension::Image(rsf::TypedImage::SpfImg(rsf::Image::new(bigger))));
+  assert!(matches!(old, Ok(Some(rsf::Extension::Image(_)))));
+  assert_eq!(primary.get_header().get_int("BITPIX"), Some(-32));
+  assert_eq!(primary.get_header().get_int("NAXIS3"), Some(4));
+
+  //Tables cannot be stored in the primary HDU
+  let tbl = rsf::AsciiTable::new().with_column(rsf::Column::from_vec(None, None, vec![1, 2]));
+  assert!(primary.replace_data(rsf::Extension::AsciiTable(tbl.clone())).is_err());
+
+  //...but they can replace the data of an extension
+  let extension = fits.get_hdu_mut(1).unwrap();
+  assert!(extension.replace_data(rsf::Extension::AsciiTable(tbl)).unwrap().is_none());
+  assert_eq!(extension.get_header().get_string("XTENSION").as_deref(), Some("TABLE"));
+  assert_eq!(extension.get_header().get_int("NAXIS2"), Some(2));
+  assert!(fits.validate().is_empty());
+
+  //Write and read back the file, without data the image is gone
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("replace_data_test.fits");
+  fits.write(&path).unwrap();
+  let mut tested = rsf::Fits::open(&path).unwrap();
+  assert_eq!(tested.get_hdu(1).unwrap().table().unwrap().get_shape(), (1, 2));
+  let primary = tested.get_hdu_mut(0).unwrap();
+  assert_eq!(primary.image::<f32>().unwrap().shape(), &[2, 3, 4]);
+  assert!(primary.take_data().is_some() && !primary.has_data());
+  assert_eq!(primary.get_header().get_int("NAXIS"), Some(0));
+  assert!(!primary.get_header().contains("NAXIS1"));
+}
+
+#[test]
+fn split_hdus_test() {
+  let dir = dirs::cache_dir().unwrap();
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+
+  //Every HDU ends up in a valid file of its own, images become primary HDU's
+  let paths = fits.split_hdus(&dir, "nicmos_split").unwrap();
+  assert_eq!(paths.len(), 6);
+  assert_eq!(paths[0], dir.join("nicmos_split.fits"));
+  assert_eq!(paths[5], dir.join("nicmos_split_5.fits"));
+  for (index, path) in paths.iter().enumerate() {
+    let single = rsf::Fits::open(path).unwrap();
+    assert_eq!(single.iter().count(), 1);
+    assert!(single.validate().is_empty(), "{}", path.display());
+    let (original, tested) = (fits[index].get_data(), single[0].get_data());
+    assert_eq!(original.map(|data| data.to_string()), tested.map(|data| data.to_string()));
+  }
+
+  //...and they can be combined again
+  let combined = rsf::Fits::from_hdus(&paths).unwrap();
+  assert_eq!(combined.iter().count(), 6);
+  assert!(combined.validate().is_empty());
+  assert_eq!(combined[3].get_extname(), fits[3].get_extname());
+
+  //Tables cannot be primary HDU's, so they follow an empty primary HDU
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_HRS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let paths = fits.split_hdus(&dir, "hrs_split").unwrap();
+  let table = rsf::Fits::open(&paths[1]).unwrap();
+  assert_eq!(table.iter().count(), 2);
+  assert!(table[0].get_data().is_none());
+  let issues = table.validate();
+  assert!(issues.iter().all(|issue| issue.severity == rsf::Severity::Warning));
+  assert_eq!(issues.len(), 2);
+
+  //The empty primary HDU is left out when the files are combined
+  let combined = rsf::Fits::from_hdus(&paths).unwrap();
+  assert_eq!(combined.iter().count(), 2);
+  assert_eq!(combined[1].table().unwrap(), fits[1].table().unwrap());
+}
@@ -0,0 +1,1025 @@
+/*
+    Copyright (C) 2022 Raúl Wolters
+
+    This file is part of rustronomy-fits.
+
+    rustronomy is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    rustronomy is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with rustronomy.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::{borrow::Cow, path::PathBuf};
+
+use rustronomy_fits as rsf;
+
+#[test]
+fn quote_test() {
+  //No, one and multiple apostrophes
+  assert_eq!(rsf::quote_fits_string("HST"), "'HST'");
+  assert_eq!(rsf::quote_fits_string("It's a trap"), "'It''s a trap'");
+  assert_eq!(rsf::quote_fits_string("'quoted' twice'"), "'''quoted'' twice'''");
+}
+
+#[test]
+fn unquote_test() {
+  //Strings without escaped quotes should not be copied
+  assert!(matches!(rsf::unquote_fits_string("'IMAGE   '"), Cow::Borrowed("IMAGE")));
+  assert!(matches!(rsf::unquote_fits_string("'  lead'"), Cow::Borrowed("  lead")));
+  assert_eq!(rsf::unquote_fits_string("'It''s a trap'"), "It's a trap");
+  assert_eq!(rsf::unquote_fits_string("'''quoted'' twice'''"), "'quoted' twice'");
+  assert_eq!(rsf::unquote_fits_string("''"), "");
+
+  //Quoting and unquoting should round-trip
+  for s in ["", "HST", "It's", "''''"] {
+    assert_eq!(rsf::unquote_fits_string(&rsf::quote_fits_string(s)), s);
+  }
+}
+
+#[test]
+fn scan_headers_test() {
+  //(file, #HDU's) for all bundled FITS files
+  let known = [
+    ("Astro_UIT.fits", 1),
+    ("EUVE.fits", 9),
+    ("Hubble_FGS.fits", 2),
+    ("Hubble_FOC.fits", 2),
+    ("Hubble_FOS.fits", 2),
+    ("Hubble_HRS.fits", 2),
+    ("Hubble_NICMOS.fits", 6),
+    ("Hubble_WFPC2_1.fits", 2),
+    ("Hubble_WFPC2_2.fits", 1),
+    ("IUE_LWP.fits", 2),
+    ("RandomGroups.fits", 2),
+  ];
+
+  for (file, n_hdus) in known {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let summary = rsf::Fits::scan_headers_only(&path).unwrap();
+    assert_eq!(summary.len(), n_hdus, "{file}");
+  }
+
+  //Check the details of a multi-extension file
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let summary = rsf::Fits::scan_headers_only(&path).unwrap();
+  assert_eq!(summary[0].data_type, None);
+  assert_eq!(summary[0].data_blocks, 0);
+  assert_eq!(summary[3].slot, 3);
+  assert_eq!(summary[3].extname.as_deref(), Some("DQ"));
+  assert_eq!(summary[3].data_type.as_deref(), Some("IMAGE"));
+  assert_eq!(summary[3].shape, vec![270, 263]);
+  assert_eq!(summary[3].data_blocks, 50);
+  assert_eq!(summary[0].start_block, 0);
+  assert!(summary[3].start_block > summary[2].start_block + summary[2].data_blocks);
+
+  //Single HDU's can be read directly, without reading the HDU's in front
+  let fits = rsf::Fits::open(&path).unwrap();
+  let hdu = rsf::Fits::read_hdu(&path, &summary[3]).unwrap();
+  assert_eq!(hdu.get_extname().as_deref(), Some("DQ"));
+  assert_eq!(format!("{hdu}"), format!("{}", fits.get_hdu(3).unwrap()));
+  let past_end = rsf::HduSummary { start_block: 1 << 20, ..summary[3].clone() };
+  assert!(rsf::Fits::read_hdu(&path, &past_end).is_err());
+
+  //...or into an existing HDU. Images of the same type and shape are
+  //decoded into the pixels of the HDU, reusing its array and the buffer
+  let mut hdu = rsf::Fits::read_hdu(&path, &summary[0]).unwrap();
+  let mut buffer = Vec::new();
+  rsf::Fits::read_hdu_into(&path, &summary[1], &mut hdu, &mut buffer).unwrap();
+  assert_eq!(format!("{hdu}"), format!("{}", fits.get_hdu(1).unwrap()));
+  let (pixels, bytes) = (hdu.image::<f32>().unwrap().as_ptr(), buffer.as_ptr());
+  rsf::Fits::read_hdu_into(&path, &summary[2], &mut hdu, &mut buffer).unwrap();
+  assert_eq!(hdu.get_extname().as_deref(), Some("ERR"));
+  assert_eq!(hdu.image::<f32>().unwrap(), fits.get_hdu(2).unwrap().image::<f32>().unwrap());
+  assert_ne!(hdu.image::<f32>().unwrap(), fits.get_hdu(1).unwrap().image::<f32>().unwrap());
+  assert_eq!((hdu.image::<f32>().unwrap().as_ptr(), buffer.as_ptr()), (pixels, bytes));
+
+  //Other data replaces the image, and errors leave the HDU untouched
+  rsf::Fits::read_hdu_into(&path, &summary[3], &mut hdu, &mut buffer).unwrap();
+  assert_eq!(format!("{hdu}"), format!("{}", fits.get_hdu(3).unwrap()));
+  assert_eq!(hdu.image::<i16>().unwrap(), fits.get_hdu(3).unwrap().image::<i16>().unwrap());
+  assert!(rsf::Fits::read_hdu_into(&path, &past_end, &mut hdu, &mut buffer).is_err());
+  assert_eq!(hdu.get_extname().as_deref(), Some("DQ"));
+
+  //Random groups are recognized as such
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/RandomGroups.fits");
+  let summary = rsf::Fits::scan_headers_only(&path).unwrap();
+  assert_eq!(summary[0].data_type.as_deref(), Some("GROUPS"));
+  assert_eq!(summary[1].data_type.as_deref(), Some("BINTABLE"));
+}
+
+#[test]
+fn copy_file_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let original = std::fs::read(&path).unwrap();
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("copy_Hubble_NICMOS.fits");
+
+  //Plain copies are identical, files cannot be copied onto themselves
+  rsf::Fits::copy_file(&path, &copy_path).unwrap();
+  assert_eq!(std::fs::read(&copy_path).unwrap(), original);
+  assert!(rsf::Fits::copy_file(&copy_path, &copy_path).is_err());
+  assert_eq!(std::fs::read(&copy_path).unwrap(), original);
+
+  //Patching a header may grow it, the data units are copied unchanged
+  let huge = rsf::quote_fits_string(&"A".repeat(10_000));
+  let patches = [("OBSERVER", huge.as_str()), ("EXTVER2", "5")];
+  rsf::Fits::copy_file_with_header_patch(&path, &copy_path, 3, &patches).unwrap();
+  let fits = rsf::Fits::open(&path).unwrap();
+  let patched = rsf::Fits::open(&copy_path).unwrap();
+  let header = patched.get_hdu(3).unwrap().get_header();
+  assert_eq!(header.get_value("OBSERVER"), Some(&huge));
+  assert_eq!(header.get_int("EXTVER2"), Some(5));
+  for (original, copy) in fits.iter().zip(patched.iter()) {
+    let data = |hdu: &rsf::HeaderDataUnit| hdu.get_data().map(|data| data.to_string());
+    assert_eq!(data(original), data(copy));
+  }
+
+  //...and so are all other blocks
+  let data_start = |path: &PathBuf| {
+    let summary = rsf::Fits::scan_headers_only(path).unwrap();
+    (summary[4].start_block - summary[3].data_blocks) * 2880
+  };
+  let (old_start, new_start) = (data_start(&path), data_start(&copy_path));
+  assert!(new_start > old_start);
+  let copy = std::fs::read(&copy_path).unwrap();
+  let header_start = rsf::Fits::scan_headers_only(&path).unwrap()[3].start_block * 2880;
+  assert_eq!(copy[..header_start], original[..header_start]);
+  assert_eq!(copy[new_start..], original[old_start..]);
+
+  //Restricted keywords and missing HDU's are rejected
+  assert!(rsf::Fits::copy_file_with_header_patch(&path, &copy_path, 3, &[("NAXIS", "3")]).is_err());
+  assert!(rsf::Fits::copy_file_with_header_patch(&path, &copy_path, 9, &patches).is_err());
+}
+
+#[test]
+fn rewrite_header_in_place_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("in_place_Hubble_NICMOS.fits");
+  std::fs::copy(&path, &copy_path).unwrap();
+  let original = std::fs::read(&path).unwrap();
+  let summary = rsf::Fits::scan_headers_only(&path).unwrap();
+  let header_range =
+    summary[3].start_block * 2880..(summary[4].start_block - summary[3].data_blocks) * 2880;
+  let unchanged = |bytes: &[u8]| {
+    bytes[..header_range.start] == original[..header_range.start]
+      && bytes[header_range.end..] == original[header_range.end..]
+  };
+
+  //Rewriting a keyword changes nothing but the header itself
+  let long = rsf::quote_fits_string(&"A".repeat(500));
+  rsf::Fits::rewrite_header_in_place(&copy_path, 3, "OBSERVER", &long).unwrap();
+  rsf::Fits::rewrite_header_in_place(&copy_path, 3, "EXTVER2", "5").unwrap();
+  assert!(unchanged(&std::fs::read(&copy_path).unwrap()));
+  let rewritten = rsf::Fits::open(&copy_path).unwrap();
+  let header = rewritten.get_hdu(3).unwrap().get_header();
+  assert_eq!(header.get_value("OBSERVER"), Some(&long));
+  assert_eq!(header.get_int("EXTVER2"), Some(5));
+
+  //Shrinking the header pads it up to its original size
+  rsf::Fits::rewrite_header_in_place(&copy_path, 3, "OBSERVER", "'me'").unwrap();
+  let rewritten = rsf::Fits::open(&copy_path).unwrap();
+  assert_eq!(rewritten.get_hdu(3).unwrap().get_header().get_string("OBSERVER").unwrap(), "me");
+  assert!(unchanged(&std::fs::read(&copy_path).unwrap()));
+
+  //Headers may not grow, restricted keywords and missing HDU's are rejected
+  let huge = rsf::quote_fits_string(&"A".repeat(10_000));
+  let err = rsf::Fits::rewrite_header_in_place(&copy_path, 3, "OBSERVER", &huge).unwrap_err();
+  assert!(err.is::<rsf::header_err::HeaderGrewErr>());
+  assert!(rsf::Fits::rewrite_header_in_place(&copy_path, 3, "NAXIS", "3").is_err());
+  assert!(rsf::Fits::rewrite_header_in_place(&copy_path, 9, "OBSERVER", "'me'").is_err());
+  let rewritten = rsf::Fits::open(&copy_path).unwrap();
+  assert_eq!(rewritten.get_hdu(3).unwrap().get_header().get_string("OBSERVER").unwrap(), "me");
+}
+
+#[test]
+fn commentary_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //Each HISTORY record is kept, in the order in which they appear
+  let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+  let history = header.get_all("HISTORY");
+  assert_eq!(history.len(), 5);
+  assert_eq!(history[0], "Jul 22,1996 13:13:21 ASTROM FROM BDR STREAM FLIGHT21");
+  assert_eq!(history[2], "Jul 22,1996 13:13:21       VERSION 2.5");
+  assert_eq!(header.get_all("SIMPLE"), ["T"]);
+  assert!(header.get_all("OBSERVER").is_empty());
+
+  //New records are appended, long texts are spread over several records
+  header.insert_history("flat-fielded / with 1/2 of the frames");
+  header.insert_comment(&"x".repeat(100));
+  header.insert_comment("");
+  assert_eq!(header.get_all("HISTORY").len(), 6);
+  assert_eq!(header.get_all("COMMENT"), ["x".repeat(72), "x".repeat(28), String::new()]);
+  assert!(header.iter().any(|(keyword, _, text)| (keyword, text) == ("COMMENT", Some(""))));
+
+  //...and survive a round trip
+  let read = rsf::Fits::from_bytes(&fits.to_bytes().unwrap()).unwrap();
+  let header = read.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_all("HISTORY")[5], "flat-fielded / with 1/2 of the frames");
+  assert_eq!(header.get_all("COMMENT").len(), 3);
+
+  //Removing a commentary keyword removes all of its records
+  let mut header = header.clone();
+  assert!(header.contains("HISTORY"));
+  header.remove("HISTORY").unwrap();
+  assert!(!header.contains("HISTORY"));
+  assert_eq!(header.get_all("COMMENT").len(), 3);
+
+  //The size of the header counts every record that will be written
+  header.insert_with_comment("LONGSTR", &rsf::quote_fits_string(&"y".repeat(2000)), "").unwrap();
+  let n_blocks = header.to_keyword_records().unwrap().len().div_ceil(36);
+  assert!(n_blocks > 1);
+  assert!(header.to_string().contains(&format!(">Size in FITS blocks: {n_blocks}\n")));
+}
+
+#[test]
+fn keyword_records_test() {
+  for file in
+    ["EUVE.fits", "Hubble_NICMOS.fits", "RandomGroups.fits", "Astro_UIT.fits", "IUE_LWP.fits"]
+  {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let bytes = std::fs::read(&path).unwrap();
+    let original = rsf::Header::read_from(&mut &bytes[..]).unwrap();
+
+    //Records after the END keyword are ignored
+    let records: Vec<[u8; 80]> = bytes.chunks_exact(80).map(|r| r.try_into().unwrap()).collect();
+    let header = rsf::Header::from_keyword_records(&records).unwrap();
+    assert!(header.iter().eq(original.iter()), "{file}");
+
+    //Values are not aligned the way the original writer aligned them (and
+    //empty comments are not written), so we compare the decoded records.
+    //The mandatory keywords are moved to the front
+    let encoded = header.to_keyword_records().unwrap();
+    assert!(encoded.last().unwrap().starts_with(b"END "), "{file}");
+    assert!(encoded[0].starts_with(b"SIMPLE  = "), "{file}");
+    let decoded = rsf::Header::from_keyword_records(&encoded).unwrap();
+    let normalize = |(key, value, comment): (&str, &str, Option<&str>)| {
+      (key.to_string(), value.to_string(), comment.filter(|c| !c.is_empty()).map(String::from))
+    };
+    let (mut decoded, mut original): (Vec<_>, Vec<_>) =
+      (decoded.iter().map(normalize).collect(), original.iter().map(normalize).collect());
+    (decoded.sort(), original.sort());
+    assert_eq!(decoded, original, "{file}");
+
+    //The records are the header written by write_to(), without the padding
+    let mut written = Vec::new();
+    header.write_to(&mut written).unwrap();
+    assert!(written.starts_with(&encoded.concat()), "{file}");
+    assert!(written[encoded.len() * 80..].iter().all(|&b| b == b' '), "{file}");
+  }
+}
+
+#[test]
+fn peek_test() {
+  for file in ["EUVE.fits", "Hubble_NICMOS.fits", "RandomGroups.fits", "Hubble_FOC.fits"] {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+
+    //Peeking at a stream gives the same summaries as scanning the file
+    let mut stream = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+    let summary = rsf::Fits::peek(&mut stream).unwrap();
+    assert_eq!(summary, rsf::Fits::scan_headers_only(&path).unwrap(), "{file}");
+
+    //The shapes match those of the decoded images
+    let fits = rsf::Fits::open(&path).unwrap();
+    for (hdu, summary) in fits.iter().zip(&summary) {
+      let header = hdu.get_header();
+      assert_eq!(summary.n_keywords, header.iter().count(), "{file}");
+      assert_eq!(summary.bitpix, header.get_value_as::<i8>("BITPIX").unwrap(), "{file}");
+      if let Some(rsf::Extension::Image(_)) = hdu.get_data() {
+        assert_eq!(Some(summary.shape.as_slice()), hdu.get_data().unwrap().shape(), "{file}");
+      }
+    }
+  }
+
+  //Streams that end halfway through a data unit are rejected
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_FOC.fits");
+  let bytes = std::fs::read(&path).unwrap();
+  assert_eq!(rsf::Fits::peek(&mut &bytes[..]).unwrap()[1].bitpix, 8);
+  assert!(rsf::Fits::peek(&mut &bytes[..bytes.len() - 2880]).is_err());
+  assert!(rsf::Fits::peek(&mut &bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn mandatory_order_test() {
+  let data = ndarray::Array::from_elem(vec![4, 2], 7i32);
+  let mut bytes = rsf::TypedImage::I32Img(rsf::Image::new(data)).to_fits_bytes().unwrap();
+
+  //Scramble the header by swapping the BITPIX and NAXIS2 records
+  let (bitpix, naxis2) = (80..160, 320..400);
+  let record = bytes[bitpix.clone()].to_vec();
+  bytes.copy_within(naxis2.clone(), bitpix.start);
+  bytes[naxis2].copy_from_slice(&record);
+
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("scrambled.fits");
+  std::fs::write(&path, bytes).unwrap();
+
+  //Writing the file should restore the canonical order
+  let fits = rsf::Fits::open(&path).unwrap();
+  path.set_file_name("unscrambled.fits");
+  fits.write(&path).unwrap();
+  let bytes = std::fs::read(&path).unwrap();
+  let keywords = bytes[..400].chunks(80).map(|rec| std::str::from_utf8(&rec[..8]).unwrap());
+  assert_eq!(
+    keywords.collect::<Vec<_>>(),
+    vec!["SIMPLE  ", "BITPIX  ", "NAXIS   ", "NAXIS1  ", "NAXIS2  "]
+  );
+}
+
+#[test]
+fn hierarch_test() {
+  let data = ndarray::Array::from_elem(vec![2, 2], 1u8);
+  let mut bytes = rsf::TypedImage::ByteImg(rsf::Image::new(data)).to_fits_bytes().unwrap();
+
+  //Replace the END keyword by some HIERARCH records (and a long string)
+  let long = "x".repeat(150);
+  let records = [
+    String::from("HIERARCH ESO DET CHIP1 ID = 'CCD-44' / detector chip"),
+    String::from("HIERARCH ESO TEL AIRM START = 1.234"),
+    format!("LONGSTR = '{}&'", &long[..67]),
+    format!("CONTINUE  '{}&'", &long[67..134]),
+    format!("CONTINUE  '{}'", &long[134..]),
+    String::from("END"),
+  ];
+  for (i, record) in records.iter().enumerate() {
+    let start = 400 + 80 * i;
+    bytes[start..start + 80].copy_from_slice(format!("{record:80}").as_bytes());
+  }
+
+  //HIERARCH keywords are stored with dots
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let check = |header: &rsf::Header| {
+    assert_eq!(header.get_value("ESO.DET.CHIP1.ID"), Some(&String::from("'CCD-44'")));
+    assert_eq!(header.get_comment("ESO.DET.CHIP1.ID"), Some(&String::from("detector chip")));
+    assert_eq!(header.get_value_as::<f64>("ESO.TEL.AIRM.START").unwrap(), 1.234);
+    assert_eq!(header.get_value("LONGSTR"), Some(&format!("'{long}'")));
+  };
+  check(fits.get_hdu(0).unwrap().get_header());
+
+  //...and are written back in the HIERARCH format
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("hierarch.fits");
+  fits.write(&path).unwrap();
+  let written = std::fs::read(&path).unwrap();
+  let header = String::from_utf8_lossy(&written[..2880]);
+  assert!(header.contains("HIERARCH ESO DET CHIP1 ID = 'CCD-44'"));
+  check(rsf::Fits::open(&path).unwrap().get_hdu(0).unwrap().get_header());
+}
+
+#[test]
+fn typed_getters_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let header = fits.get_hdu(0).unwrap().get_header();
+
+  assert!(header.contains("TELESCOP"));
+  assert!(!header.contains("NONEXISTENT"));
+  assert_eq!(header.get_int("NEXTEND"), Some(5));
+  assert_eq!(header.get_float("RA_TARG"), Some(182.63625));
+  assert_eq!(header.get_float("ORIENTAT"), Some(-97.7898));
+  assert_eq!(header.get_bool("EXTEND"), Some(true));
+  assert_eq!(header.get_string("FILENAME").as_deref(), Some("n4hk12010_mos.fits"));
+
+  //Values of the wrong type
+  assert_eq!(header.get_int("TELESCOP"), None);
+  assert_eq!(header.get_bool("NEXTEND"), None);
+  assert_eq!(header.get_string("NEXTEND"), None);
+
+  //Iteration follows the order of the header
+  let (keyword, value, comment) = header.iter().next().unwrap();
+  assert_eq!((keyword, value, comment), ("SIMPLE", "T", Some("Fits standard")));
+  assert_eq!(header.iter().count(), header.get_num_records());
+  let telescope = header.iter().find(|(kw, _, _)| *kw == "TELESCOP").unwrap();
+  assert_eq!(telescope.1, "'HST'");
+}
+
+#[test]
+fn insert_remove_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //Add, replace and remove some records in the primary header
+  let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+  header.insert_with_comment("OBSERVER", &rsf::quote_fits_string("Hubble"), "who").unwrap();
+  header.insert_with_comment("TELESCOP", "'JWST'", "not really").unwrap();
+  assert_eq!(header.remove("FILENAME").unwrap(), Some(String::from("'n4hk12010_mos.fits'")));
+  assert_eq!(header.remove("FILENAME").unwrap(), None);
+
+  //Records describing the data cannot be changed
+  assert!(header.insert_with_comment("NAXIS", "3", "").is_err());
+  assert!(header.remove("BITPIX").is_err());
+
+  //Write the file and check the records
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("insert_remove.fits");
+  fits.write(&copy_path).unwrap();
+  let bytes = std::fs::read(&copy_path).unwrap();
+  assert!(String::from_utf8_lossy(&bytes).contains("OBSERVER= 'Hubble' / who"));
+
+  let tested = rsf::Fits::open(&copy_path).unwrap();
+  let header = tested.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_string("OBSERVER").as_deref(), Some("Hubble"));
+  assert_eq!(header.get_comment("OBSERVER"), Some(&String::from("who")));
+  assert_eq!(header.get_string("TELESCOP").as_deref(), Some("JWST"));
+  assert!(!header.contains("FILENAME"));
+}
+
+#[test]
+fn long_string_test() {
+  let data = ndarray::Array::from_elem(vec![2, 2], 1u8);
+  let bytes = rsf::TypedImage::ByteImg(rsf::Image::new(data)).to_fits_bytes().unwrap();
+
+  //Strings longer than 68 characters are split over CONTINUE records
+  for (len, n_records) in [(0, 1), (67, 1), (68, 1), (135, 2), (500, 8)] {
+    let long = "x".repeat(len);
+    let mut fits = rsf::Fits::from_bytes(&bytes).unwrap();
+    let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+    header.insert_with_comment("LONGSTR", &rsf::quote_fits_string(&long), "").unwrap();
+
+    let mut path = dirs::cache_dir().unwrap();
+    path.push(format!("long_string_{len}.fits"));
+    fits.write(&path).unwrap();
+    let written = std::fs::read(&path).unwrap();
+    let records = written[..2880].chunks(80).map(String::from_utf8_lossy).collect::<Vec<_>>();
+    let first = records.iter().position(|rec| rec.starts_with("LONGSTR = '")).unwrap();
+    let n_continue =
+      records[first + 1..].iter().take_while(|rec| rec.starts_with("CONTINUE")).count();
+    assert_eq!(n_continue + 1, n_records, "{len} characters");
+
+    let tested = rsf::Fits::open(&path).unwrap();
+    assert_eq!(tested.get_hdu(0).unwrap().get_header().get_string("LONGSTR"), Some(long));
+  }
+
+  //Escaped quotes are never split and the comment ends up on the last record
+  let long = "'".repeat(40);
+  let mut fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+  header.insert_with_comment("QUOTES", &rsf::quote_fits_string(&long), "all quotes").unwrap();
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("long_string_quotes.fits");
+  fits.write(&path).unwrap();
+  let tested = rsf::Fits::open(&path).unwrap();
+  let header = tested.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_string("QUOTES"), Some(long));
+  assert_eq!(header.get_comment("QUOTES"), Some(&String::from("all quotes")));
+}
+
+#[test]
+fn slash_in_string_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let header = fits.get_hdu(0).unwrap().get_header();
+
+  //Slashes inside string values do not start the comment
+  assert_eq!(header.get_value("ORIGIN"), Some(&String::from("'UIT/GSFC'")));
+  assert_eq!(header.get_comment("ORIGIN"), Some(&String::from("WHERE TAPE WRITTEN")));
+  assert_eq!(header.get_string("DATE-OBS").as_deref(), Some("13/03/95"));
+  assert_eq!(header.get_string("BUNIT").as_deref(), Some("ERGS/CM**2/S/ANGSTRM"));
+  assert_eq!(header.get_comment("BUNIT"), None);
+
+  //...and comments may contain slashes too
+  assert_eq!(header.get_float("NOMSCALE"), Some(56.8325));
+  assert_eq!(header.get_comment("NOMSCALE"), Some(&String::from("NOMINAL PLATE SCL (ARCSEC/MM)")));
+  assert_eq!(header.get_comment("CD1_1"), Some(&String::from("SDAS-COMPATIBLE:  DL/DX")));
+  assert_eq!(header.get_string("FILTER").as_deref(), Some("B1"));
+  assert_eq!(header.get_comment("FILTER"), Some(&String::from("CAMERA/FILTER IDENTIFIER")));
+
+  //Escaped quotes do not end the string
+  let mut fits = fits;
+  let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+  let value = rsf::quote_fits_string("it's 1/2 o'clock");
+  header.insert_with_comment("ESCAPED", &value, "a/b").unwrap();
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("slash_in_string.fits");
+  fits.write(&copy_path).unwrap();
+  let tested = rsf::Fits::open(&copy_path).unwrap();
+  let header = tested.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_string("ESCAPED").as_deref(), Some("it's 1/2 o'clock"));
+  assert_eq!(header.get_comment("ESCAPED"), Some(&String::from("a/b")));
+  assert_eq!(header.get_value("ORIGIN"), Some(&String::from("'UIT/GSFC'")));
+}
+
+#[test]
+fn wcs_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //The primary HDU has no WCS, the science image uses a TAN projection
+  assert!(fits.get_hdu(0).unwrap().wcs().is_none());
+  let wcs = fits.get_hdu(1).unwrap().wcs().unwrap();
+  assert_eq!(wcs.ctypes, vec!["RA---TAN", "DEC--TAN"]);
+  assert_eq!(wcs.projection(), Some("TAN"));
+  assert_eq!(wcs.cd_matrix.unwrap()[0], [2.86146E-06, -2.07292E-05]);
+  assert_eq!(wcs.equinox, 2000.0);
+
+  //The reference pixel maps onto the reference value, and back
+  assert_eq!(wcs.pixel_to_world(&wcs.crpix), wcs.crval);
+  let world = wcs.pixel_to_world(&[1.0, 263.0]);
+  let pixel = wcs.world_to_pixel(&world);
+  assert!((pixel[0] - 1.0).abs() < 1e-6 && (pixel[1] - 263.0).abs() < 1e-6);
+
+  //One radian along the equator in the tangent plane is atan(1) = 45 degrees
+  let header = fits.get_hdu_mut(0).unwrap().get_header_mut();
+  for (keyword, value) in [
+    ("CTYPE1", "'RA---TAN'"),
+    ("CTYPE2", "'DEC--TAN'"),
+    ("CRPIX1", "1.0"),
+    ("CRPIX2", "1.0"),
+    ("CRVAL1", "350.0"),
+    ("CRVAL2", "0.0"),
+    ("CDELT1", "57.29577951308232"),
+    ("CDELT2", "57.29577951308232"),
+  ] {
+    header.insert_with_comment(keyword, value, "").unwrap();
+  }
+  let wcs = fits.get_hdu(0).unwrap().wcs().unwrap();
+  let world = wcs.pixel_to_world(&[2.0, 1.0]);
+  assert!((world[0] - 35.0).abs() < 1e-9 && world[1].abs() < 1e-9);
+  let pixel = wcs.world_to_pixel(&world);
+  assert!((pixel[0] - 2.0).abs() < 1e-9 && (pixel[1] - 1.0).abs() < 1e-9);
+  assert!(wcs.cd_matrix.is_none());
+  assert_eq!(wcs.equinox, 2000.0);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+  let header = fits.get_hdu(1).unwrap().get_header().clone();
+
+  //Headers become JSON objects of {value, comment} records, in order
+  let json = header.to_json().unwrap();
+  assert!(json.starts_with(r#"{"XTENSION":{"value":"'IMAGE   '","comment":"Image extension"}"#));
+  let tested = rsf::Header::from_json(&json).unwrap();
+  assert!(header.iter().eq(tested.iter()));
+
+  //The deserialized records can be encoded again
+  let slot = fits.append_empty_hdu_with_meta("JSON", tested);
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("serde.fits");
+  fits.write(&copy_path).unwrap();
+  let copy = rsf::Fits::open(&copy_path).unwrap();
+  let copied = copy.get_hdu(slot).unwrap().get_header();
+  assert_eq!(copied.get_value("CRVAL1"), header.get_value("CRVAL1"));
+
+  //Images are serialized as their array
+  let data = ndarray::Array::from_shape_vec(vec![2, 2], vec![1i16, 2, 3, 4]).unwrap();
+  let img = rsf::TypedImage::I16Img(rsf::Image::new(data));
+  let json = serde_json::to_string(&img).unwrap();
+  let tested: rsf::TypedImage = serde_json::from_str(&json).unwrap();
+  assert_eq!(tested.as_i16_array().unwrap(), img.as_i16_array().unwrap());
+}
+
+fn fits_block(records: &[&str]) -> Vec<u8> {
+  //Pads each record to 80 bytes and the header to a full FITS block
+  let mut block: Vec<u8> =
+    records.iter().flat_map(|rec| format!("{rec:<80}").into_bytes()).collect();
+  block.resize(2880, b' ');
+  block
+}
+
+#[test]
+fn validate_test() {
+  //The bundled files follow the standard
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/EUVE.fits");
+  assert!(rsf::Fits::validate_file(&path).unwrap().is_empty());
+  assert!(rsf::Fits::open(&path).unwrap().validate().is_empty());
+
+  //Unknown axes are suspicious, lowercase keywords are not allowed
+  let bytes = fits_block(&[
+    "SIMPLE  =                    T",
+    "BITPIX  =                    8",
+    "NAXIS   =                    0",
+    "NAXIS1  =                    5",
+    "lower   =                    1",
+    "END",
+  ]);
+  let issues = rsf::Fits::from_bytes(&bytes).unwrap().validate();
+  assert_eq!(issues.len(), 2);
+  assert_eq!(issues[0].severity, rsf::Severity::Warning);
+  assert_eq!(issues[0].keyword.as_deref(), Some("NAXIS1"));
+  assert!(issues[0].description.starts_with("HDU 0: "));
+  assert_eq!(issues[1].severity, rsf::Severity::Error);
+  assert_eq!(issues[1].keyword.as_deref(), Some("lower"));
+
+  //Invalid values and missing keywords, the data unit size is unknown
+  let mut bytes =
+    fits_block(&["SIMPLE  =                    F", "BITPIX  =   12", "NAXIS   =    0", "END"]);
+  bytes.extend(fits_block(&[
+    "XTENSION= 'IMAGE   '",
+    "BITPIX  =  -32",
+    "NAXIS   =    1",
+    "PCOUNT  =    2",
+    "END",
+  ]));
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("validate_test.fits");
+  std::fs::write(&path, &bytes).unwrap();
+  let issues = rsf::Fits::validate_file(&path).unwrap();
+  assert!(issues.iter().all(|issue| issue.severity == rsf::Severity::Error));
+  let keywords = issues.iter().map(|issue| issue.keyword.as_deref()).collect::<Vec<_>>();
+  let expected =
+    [Some("SIMPLE"), Some("BITPIX"), Some("NAXIS1"), Some("PCOUNT"), Some("GCOUNT"), None];
+  assert_eq!(keywords, expected);
+  assert!(issues[2].description.starts_with("HDU 1: "));
+
+  //Headers without END end the file
+  bytes.extend(fits_block(&["XTENSION= 'IMAGE   '", "BITPIX  =  -32", "NAXIS   =    0"]));
+  bytes.drain(2880..5760);
+  std::fs::write(&path, &bytes).unwrap();
+  let issues = rsf::Fits::validate_file(&path).unwrap();
+  assert_eq!(issues.last().unwrap().keyword.as_deref(), Some("END"));
+  assert!(rsf::Fits::open(&path).is_err());
+}
+
+#[test]
+fn validate_all_test() {
+  use rsf::Severity;
+
+  //None of the bundled files violate the standard
+  let resources = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+  for entry in std::fs::read_dir(&resources).unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().and_then(|ext| ext.to_str()) != Some("fits") {
+      continue;
+    }
+    let issues = rsf::Fits::validate_file(&path).unwrap();
+    assert!(issues.iter().all(|issue| issue.severity < Severity::Error), "{}", path.display());
+    let issues = rsf::Fits::open(&path).unwrap().validate();
+    assert!(issues.iter().all(|issue| issue.severity < Severity::Error), "{}", path.display());
+  }
+
+  //Deprecated keywords are reported for information only
+  let issues = rsf::Fits::validate_file(&resources.join("RandomGroups.fits")).unwrap();
+  assert!(!issues.is_empty());
+  assert!(issues.iter().all(|issue| issue.severity == Severity::Info));
+  assert!(issues.iter().any(|issue| issue.keyword.as_deref() == Some("EPOCH")));
+  assert!(issues.iter().all(|issue| issue.hdu_index == Some(0)));
+
+  //Values of the wrong type
+  let mut bytes = fits_block(&[
+    "SIMPLE  =                    T",
+    "BITPIX  =                    8",
+    "NAXIS   =                    0",
+    "END",
+  ]);
+  bytes.extend(fits_block(&[
+    "XTENSION= 'IMAGE   '",
+    "BITPIX  =                  -32",
+    "NAXIS   =                    0",
+    "PCOUNT  =                  0.5",
+    "GCOUNT  =                    1",
+    "BSCALE  = 'one     '",
+    "END",
+  ]));
+  let issues = rsf::Fits::from_bytes(&bytes).unwrap().validate();
+  let found = issues
+    .iter()
+    .map(|issue| (issue.hdu_index, issue.severity, issue.keyword.as_deref()))
+    .collect::<Vec<_>>();
+  let expected =
+    [(Some(1), Severity::Error, Some("PCOUNT")), (Some(1), Severity::Warning, Some("BSCALE"))];
+  assert_eq!(found, expected);
+  assert!(issues[0].description.ends_with("value must be an integer"));
+
+  //An incomplete last block is reported, the rest of the file is validated
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("validate_all_test.fits");
+  bytes.truncate(2880 + 100);
+  std::fs::write(&path, &bytes).unwrap();
+  let issues = rsf::Fits::validate_file(&path).unwrap();
+  assert_eq!(issues[0].severity, Severity::Error);
+  assert_eq!(issues[0].hdu_index, None);
+  assert!(issues[0].description.contains("multiple of 2880"));
+  assert_eq!(issues.len(), 1);
+}
+
+#[test]
+fn observation_test() {
+  use chrono::NaiveDate;
+
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+
+  //Keywords that are missing are None
+  let observation = fits.primary().unwrap().observation().unwrap();
+  assert_eq!(observation.telescope.as_deref(), Some("HST"));
+  assert_eq!(observation.instrument.as_deref(), Some("NICMOS"));
+  let date = NaiveDate::from_ymd_opt(1998, 5, 22).unwrap().and_hms_opt(0, 0, 0).unwrap();
+  assert_eq!(observation.date_obs, Some(date));
+  assert_eq!(observation.observer, None);
+
+  //The keywords can be written into another header
+  let observation = rsf::Observation {
+    object: Some(String::from("M31")),
+    ra: Some(10.684708),
+    dec: Some(41.26875),
+    exptime: Some(1.5e3),
+    ..observation
+  };
+  let records = observation.to_header_keywords();
+  assert_eq!(records.len(), 8);
+  assert_eq!(records[0], (String::from("TELESCOP"), String::from("'HST'"), records[0].2.clone()));
+  let meta = records.iter().fold(rsf::HeaderBuilder::new(), |builder, (key, value, comment)| {
+    builder.keyword_with_comment(key, value, comment.as_deref().unwrap_or_default())
+  });
+  let image = ndarray::Array::<f32, _>::zeros(vec![2, 2]).into_dyn();
+  let built = rsf::FitsBuilder::new()
+    .primary(rsf::HduBuilder::image(image.clone()).header(meta))
+    .build()
+    .unwrap();
+  let read = rsf::Fits::from_bytes(&built.to_bytes().unwrap()).unwrap();
+  assert_eq!(read.primary().unwrap().observation(), Some(observation));
+
+  //HDU's without any of the keywords have no observation
+  let built = rsf::FitsBuilder::new().primary(rsf::HduBuilder::image(image)).build().unwrap();
+  assert_eq!(built.primary().unwrap().observation(), None);
+}
+
+#[test]
+fn datetime_test() {
+  use chrono::{NaiveDate, NaiveDateTime};
+  let datetime = |y, mo, d, h, mi, s, nano| -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(y, mo, d)?.and_hms_nano_opt(h, mi, s, nano)
+  };
+
+  //Old-style dates pivot at 70
+  assert_eq!(rsf::parse_fits_datetime("'13/03/95'"), datetime(1995, 3, 13, 0, 0, 0, 0));
+  assert_eq!(rsf::parse_fits_datetime("'01/01/69'"), datetime(2069, 1, 1, 0, 0, 0, 0));
+  assert_eq!(rsf::parse_fits_datetime("'01/01/70'"), datetime(1970, 1, 1, 0, 0, 0, 0));
+  assert_eq!(rsf::parse_fits_datetime("'30/04/97 GMT'"), datetime(1997, 4, 30, 0, 0, 0, 0));
+  assert_eq!(rsf::parse_fits_datetime("' 2/07/96          '"), datetime(1996, 7, 2, 0, 0, 0, 0));
+
+  //ISO dates, with fractional seconds
+  assert_eq!(rsf::parse_fits_datetime("'2000-11-02'"), datetime(2000, 11, 2, 0, 0, 0, 0));
+  assert_eq!(rsf::parse_fits_datetime("'2004-01-09T03:27:48'"), datetime(2004, 1, 9, 3, 27, 48, 0));
+  let parsed = rsf::parse_fits_datetime("'2023-03-13T10:15:30.456'");
+  assert_eq!(parsed, datetime(2023, 3, 13, 10, 15, 30, 456_000_000));
+  let parsed = rsf::parse_fits_datetime("2023-03-13T10:15:30.1234567891");
+  assert_eq!(parsed, datetime(2023, 3, 13, 10, 15, 30, 123_456_789));
+
+  //Invalid dates
+  for value in
+    ["'32/01/95'", "'13/03/1995'", "'2023-13-01'", "'10:15:30'", "''", "'2023-03-13T10:15'"]
+  {
+    assert_eq!(rsf::parse_fits_datetime(value), None, "{value}");
+  }
+
+  //Header getter, the bundled files use both formats
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let header = fits.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_datetime("DATE-OBS"), datetime(1995, 3, 13, 0, 0, 0, 0));
+  path.set_file_name("Hubble_WFPC2_2.fits");
+  let fits = rsf::Fits::open(&path).unwrap();
+  let header = fits.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_datetime("DATE"), datetime(2003, 7, 24, 18, 28, 58, 0));
+  assert_eq!(header.get_datetime("BITPIX"), None);
+}
+
+#[test]
+fn inherit_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_NICMOS.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+
+  //The SCI extension has INHERIT = T
+  let sci = fits.get_hdu(1).unwrap().get_header();
+  let (n_records, rootname) = (sci.get_num_records(), sci.get_comment("ROOTNAME").cloned());
+  assert!(!sci.contains("TELESCOP"));
+
+  fits.resolve_inheritance();
+  let sci = fits.get_hdu(1).unwrap().get_header();
+  assert!(sci.get_num_records() > n_records);
+  assert_eq!(sci.get_string("TELESCOP").as_deref(), Some("HST"));
+  assert_eq!(
+    sci.get_string("TARGNAME"),
+    fits.get_hdu(0).unwrap().get_header().get_string("TARGNAME")
+  );
+
+  //Records of the extension itself and structural records stay as they were
+  assert_eq!(sci.get_comment("ROOTNAME").cloned(), rootname);
+  assert_eq!(sci.get_int("BITPIX"), Some(-32));
+  assert!(!sci.contains("SIMPLE") && !sci.contains("EXTEND"));
+  assert!(fits.validate().is_empty());
+
+  //Extensions with INHERIT = F do not inherit anything
+  path.set_file_name("EUVE.fits");
+  let mut fits = rsf::Fits::open(&path).unwrap();
+  let primary = fits.get_hdu(0).unwrap().clone();
+  let hdu = fits.get_hdu_mut(1).unwrap();
+  let n_records = hdu.get_header().get_num_records();
+  hdu.inherit_from(&primary);
+  assert_eq!(hdu.get_header().get_num_records(), n_records);
+}
+
+#[test]
+fn header_io_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push("resources/Hubble_FOC.fits");
+  let fits = rsf::Fits::open(&real).unwrap();
+  let records = |header: &rsf::Header| {
+    //Empty comments are not written
+    let records = header.iter().map(|(kw, val, com)| (kw, val, com.filter(|com| !com.is_empty())));
+    records.map(|(kw, val, com)| format!("{kw}{val}{com:?}")).collect::<Vec<_>>()
+  };
+
+  //Headers can be read straight from a file, the data follows the header
+  let mut file = std::fs::File::open(&real).unwrap();
+  let header = rsf::Header::read_from(&mut file).unwrap();
+  assert_eq!(records(&header), records(fits.primary().unwrap().get_header()));
+  let next = rsf::Header::read_from(&mut file);
+  assert!(next.is_err(), "the data unit is not a header");
+
+  //Writing pads the header to whole blocks, and reads back the same records
+  let mut bytes = Vec::new();
+  header.write_to(&mut bytes).unwrap();
+  assert_eq!(bytes.len() % 2880, 0);
+  let read = rsf::Header::read_from(&mut bytes.as_slice()).unwrap();
+  assert_eq!(records(&read), records(&header));
+  let mut again = Vec::new();
+  read.write_to(&mut again).unwrap();
+  assert_eq!(again, bytes);
+
+  //Headers without END are incomplete
+  assert!(rsf::Header::read_from(&mut &bytes[..2880 * (bytes.len() / 2880 - 1)]).is_err());
+}
+
+#[test]
+fn invalid_header_test() {
+  use rsf::header_err::InvalidHeaderErr;
+
+  //Mandatory keywords may only appear once
+  let bytes = fits_block(&[
+    "SIMPLE  =                    T",
+    "BITPIX  =                    8",
+    "NAXIS   =                    1",
+    "NAXIS   =                    2",
+    "NAXIS1  =                    5",
+    "END",
+  ]);
+  let err = rsf::Fits::from_bytes(&bytes).unwrap_err();
+  let expected = InvalidHeaderErr::DuplicateKeyword { key: "NAXIS" };
+  assert_eq!(err.downcast_ref::<InvalidHeaderErr>(), Some(&expected));
+
+  //...unless we're lenient, then the last one is used
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("invalid_header_test.fits");
+  std::fs::write(&path, &bytes).unwrap();
+  let err = rsf::Fits::open_with_options(&path, rsf::FitsReadOptions::new().lenient()).unwrap_err();
+  let expected = InvalidHeaderErr::NaxisShapeMismatch { declared: 2, found: 1 };
+  assert_eq!(err.downcast_ref::<InvalidHeaderErr>(), Some(&expected));
+
+  //The validator reports the duplicate record
+  let issues = rsf::Fits::validate_file(&path).unwrap();
+  assert_eq!(issues[0].keyword.as_deref(), Some("NAXIS"));
+  assert!(issues[0].description.contains("appears more than once"));
+
+  //Superfluous axes do not change the size of the data unit
+  let mut bytes = fits_block(&[
+    "SIMPLE  =                    T",
+    "BITPIX  =                    8",
+    "NAXIS   =                    1",
+    "NAXIS1  =                    5",
+    "NAXIS2  =                    5",
+    "END",
+  ]);
+  bytes.extend(vec![0u8; 2880]);
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let issues = fits.validate();
+  assert_eq!(issues.len(), 1);
+  assert_eq!(issues[0].keyword.as_deref(), Some("NAXIS2"));
+  assert!(issues[0].description.contains("NAXIS = 1, but the header has 2 NAXISn keyword(s)"));
+}
+
+#[test]
+fn keyword_name_test() {
+  use rsf::keyword_err::KeywordNameErrorReason as Reason;
+  let reason = |name: &str| rsf::validate_keyword_name(name).unwrap_err().get_reason().clone();
+
+  //Standard and HIERARCH keywords are fine, protected ones are not
+  assert!(rsf::validate_keyword_name("DATE-OBS").is_ok());
+  assert!(rsf::validate_keyword_name("ESO.DET.CHIP1.ID").is_ok());
+  assert_eq!(reason("my_key"), Reason::IllegalCharacter('m'));
+  assert_eq!(reason("ESO..ID"), Reason::IllegalCharacter('.'));
+  assert_eq!(reason("ESO.T\u{e9}MP"), Reason::IllegalCharacter('\u{e9}'));
+  assert_eq!(reason(""), Reason::IllegalCharacter(' '));
+  assert_eq!(reason(&"LONG.".repeat(14)[..69]), Reason::TooLong);
+  assert_eq!(reason("SIMPLE"), Reason::Protected);
+  assert_eq!(reason("TFORM12"), Reason::Protected);
+  assert!(rsf::validate_keyword_name("TFORMAT").is_ok());
+
+  //Headers only accept valid keywords
+  let mut header = rsf::HeaderBuilder::new().build().unwrap();
+  let err = header.insert_with_comment("my_key", "1", "").unwrap_err();
+  assert_eq!(err.get_name(), "my_key");
+  assert!(!header.contains("my_key"));
+  assert!(header.insert_with_comment("NAXIS2", "1", "").is_err());
+  header.insert_with_comment("MY_KEY", "1", "").unwrap();
+  assert_eq!(header.get_int("MY_KEY"), Some(1));
+}
+
+#[test]
+fn image_header_test() {
+  let validator = rsf::FitsValidator::new();
+
+  //Only the mandatory keywords, in the right order
+  let header = rsf::Header::for_primary_image(&[100, 200], -32).unwrap();
+  let keywords = header.iter().map(|(keyword, _, _)| keyword).collect::<Vec<_>>();
+  assert_eq!(keywords, ["SIMPLE", "BITPIX", "NAXIS", "NAXIS1", "NAXIS2"]);
+  assert_eq!(header.get_int("NAXIS2"), Some(200));
+  assert!(validator.validate_header(&header, true).is_empty());
+
+  let header = rsf::Header::for_extension_image("SCI", &[4], 16).unwrap();
+  let keywords = header.iter().map(|(keyword, _, _)| keyword).collect::<Vec<_>>();
+  assert_eq!(keywords, ["XTENSION", "BITPIX", "NAXIS", "NAXIS1", "PCOUNT", "GCOUNT", "EXTNAME"]);
+  assert_eq!(header.get_string("EXTNAME").as_deref(), Some("SCI"));
+  assert!(validator.validate_header(&header, false).is_empty());
+
+  //BITPIX has to be one of the codes of the standard
+  let err = rsf::Header::for_primary_image(&[2, 2], 12).unwrap_err();
+  assert!(err.to_string().contains("12"));
+  assert!(rsf::Header::for_extension_image("SCI", &[2], 0).is_err());
+}
+
+#[test]
+fn format_keyword_record_test() {
+  use rsf::keyword_err::KeywordRecordErr;
+  let record = |key, value, comment| {
+    let bytes = rsf::format_keyword_record(key, value, comment).unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+  };
+
+  //Logicals, integers and floats end in column 30
+  assert_eq!(record("SIMPLE", "T", None), format!("{:<80}", "SIMPLE  =                    T"));
+  assert_eq!(
+    record("NAXIS1", "1024", Some("length of axis 1")),
+    format!("{:<80}", "NAXIS1  =                 1024 / length of axis 1")
+  );
+  assert_eq!(record("BSCALE", "1.5e-3", None), format!("{:<80}", "BSCALE  =               1.5E-3"));
+  assert_eq!(
+    record("CRVAL1", "-2.0D+02", None),
+    format!("{:<80}", "CRVAL1  =             -2.0D+02")
+  );
+  assert_eq!(
+    record("ZVAL", "(1.0, -2.0)", None),
+    format!("{:<80}", "ZVAL    =          (1.0, -2.0)")
+  );
+
+  //Strings start in column 11, unquoted text is quoted and padded
+  assert_eq!(record("XTENSION", "'IMAGE   '", None), format!("{:<80}", "XTENSION= 'IMAGE   '"));
+  assert_eq!(record("OBSERVER", "O'Hara", None), format!("{:<80}", "OBSERVER= 'O''Hara  '"));
+  assert_eq!(record("DATE-OBS", "NaN", None).len(), 80);
+  assert!(record("DATE-OBS", "NaN", None).starts_with("DATE-OBS= 'NaN     '"));
+
+  //Records that do not fit are an error
+  let err =
+    |key, value: &str, comment| rsf::format_keyword_record(key, value, comment).unwrap_err();
+  assert_eq!(err("EXPOSURE1", "1", None), KeywordRecordErr::KeyTooLong { key: "EXPOSURE1".into() });
+  assert_eq!(err("lower", "1", None), KeywordRecordErr::IllegalCharacter { found: 'l' });
+  assert_eq!(
+    err("OBJECT", "M\u{e9}", None),
+    KeywordRecordErr::IllegalCharacter { found: '\u{e9}' }
+  );
+  let long = "x".repeat(70);
+  assert!(matches!(err("OBJECT", &long, None), KeywordRecordErr::ValueTooLong { .. }));
+  let comment = "y".repeat(60);
+  assert!(matches!(err("NAXIS", "2", Some(&comment)), KeywordRecordErr::ValueTooLong { .. }));
+
+  //The header writer uses the fixed format too
+  let data = ndarray::Array::from_elem(vec![3, 2], 0u8);
+  let bytes = rsf::TypedImage::ByteImg(rsf::Image::new(data)).to_fits_bytes().unwrap();
+  let header = String::from_utf8(bytes[..2880].to_vec()).unwrap();
+  assert!(header.contains(&format!("{:<80}", "NAXIS1  =                    3")));
+}
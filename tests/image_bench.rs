@@ -90,3 +90,36 @@ fn read_write_benchmark() {
     write_times.iter().sum::<u128>() as usize / write_times.len()
   );
 }
+
+#[test]
+fn skip_benchmark() {
+  /*  Description:
+      Summarizes a file with several large images twice: once by seeking
+      past the data units and once by reading them from a plain reader.
+  */
+  use ndarray::{Array, IxDyn};
+
+  //(1) Write a file with a handful of ~4MB images
+  let image = Array::from_shape_fn(IxDyn(&[1024, 1024]), |idx| (idx[0] + idx[1]) as f32);
+  let mut builder = rfs::FitsBuilder::new().primary(rfs::HduBuilder::image(image.clone()));
+  for i in 0..4 {
+    builder = builder.extension(rfs::HduBuilder::image(image.clone()).extname(&format!("IMG{i}")));
+  }
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("skip_bench.fits");
+  builder.build().unwrap().write(&path).unwrap();
+
+  //(2) Skip the data units
+  let now = Instant::now();
+  let skipped = rfs::Fits::scan_headers_only(&path).unwrap();
+  let skip_time = now.elapsed().as_micros();
+
+  //(3) Read the data units
+  let now = Instant::now();
+  let read = rfs::Fits::peek(&mut fs::File::open(&path).unwrap()).unwrap();
+  let read_time = now.elapsed().as_micros();
+
+  assert_eq!(skipped.len(), 5);
+  assert_eq!(skipped, read);
+  println!("Summary by skipping data: {skip_time}us, by reading data: {read_time}us");
+}
@@ -67,3 +67,714 @@ fn write_correctness_test() {
   print!("{original}");
   print!("{tested}");
 }
+
+#[test]
+fn checksum_test() {
+  let mut real_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real_path.push(REAL_FILE);
+
+  //Write a copy of the FITS file with checksums
+  let fits = rsf::Fits::open(&real_path).unwrap();
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("checksum.fits");
+  fits.write_with_checksum(&copy_path).unwrap();
+
+  //Reading it back with verification should work
+  let tested = rsf::Fits::open_with_checksum(&copy_path).unwrap();
+  let header = tested.get_hdu(1).unwrap().get_header();
+  assert!(header.get_value("CHECKSUM").is_some());
+  assert!(header.get_value("DATASUM").is_some());
+
+  //Flipping a single bit in the data should make verification fail
+  let mut bytes = std::fs::read(&copy_path).unwrap();
+  let last = bytes.len() - 1;
+  bytes[last] ^= 1;
+  std::fs::write(&copy_path, bytes).unwrap();
+  assert!(rsf::Fits::open_with_checksum(&copy_path).is_err());
+}
+
+#[test]
+fn scaled_checksum_test() {
+  //Write a checksummed copy of a scaled image (BSCALE = 2.0587209E-16)
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+  let raw = rsf::Fits::open_with_scaling(&path, rsf::ScalingMode::Raw).unwrap();
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("scaled_checksum.fits");
+  raw.write_with_checksum(&copy_path).unwrap();
+
+  //Scaling changes the data, so the checksums are verified before scaling
+  let scaled = rsf::Fits::open_with_checksum(&copy_path).unwrap();
+  let header = scaled.get_hdu(0).unwrap().get_header();
+  assert!(!header.contains("CHECKSUM") && !header.contains("DATASUM"));
+
+  //...and the scaled file can be written and verified again
+  let mut rewritten = dirs::cache_dir().unwrap();
+  rewritten.push("scaled_checksum_rewritten.fits");
+  rsf::Fits::open(&copy_path).unwrap().write(&rewritten).unwrap();
+  assert!(rsf::Fits::open_with_checksum(&rewritten).is_ok());
+  let mut fits = rsf::Fits::open(&copy_path).unwrap();
+  fits.update_checksums().unwrap();
+  fits.write(&rewritten).unwrap();
+  assert!(rsf::Fits::open_with_checksum(&rewritten).is_ok());
+  let raw_copy = rsf::Fits::open_with_scaling(&rewritten, rsf::ScalingMode::Raw).unwrap();
+  assert!(raw_copy.get_hdu(0).unwrap().get_header().contains("DATASUM"));
+
+  //Corrupt scaled data is still detected
+  let mut bytes = std::fs::read(&copy_path).unwrap();
+  let last = bytes.len() - 1;
+  bytes[last] ^= 1;
+  std::fs::write(&copy_path, bytes).unwrap();
+  assert!(rsf::Fits::open_with_checksum(&copy_path).is_err());
+}
+
+#[test]
+fn verify_checksums_test() {
+  //A data unit of zeroes has a zero checksum
+  assert_eq!(rsf::compute_datasum(&[0u8; 2880]), 0);
+
+  //Encoding example from the checksum convention (Seaman, Pence & Rots)
+  assert_eq!(rsf::encode_checksum(868229149), "hcHjjc9ghcEghc9g");
+  assert_eq!(rsf::compute_checksum(&[], 868229149), "hcHjjc9ghcEghc9g");
+  //1's complement sums wrap around: -0 + 1 = 1
+  assert_eq!(rsf::compute_datasum(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1]), 1);
+
+  //Checksums can be added to files that are only in memory
+  let data = ndarray::Array::from_elem(vec![10, 10], 3i16);
+  let bytes = rsf::TypedImage::I16Img(rsf::Image::new(data)).to_fits_bytes().unwrap();
+  let mut fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  assert!(fits.verify_checksums().is_ok()); //nothing to verify yet
+  fits.update_checksums().unwrap();
+  let header = fits.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_value("CHECKSUM").unwrap().len(), 18);
+  assert!(fits.verify_checksums().is_ok());
+
+  //Corrupting the written data should be detected after reading
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("verify_checksums.fits");
+  fits.write(&copy_path).unwrap();
+  let mut bytes = std::fs::read(&copy_path).unwrap();
+  bytes[2880] ^= 0x10;
+  let tested = rsf::Fits::from_bytes(&bytes).unwrap();
+  let failures = tested.verify_checksums().unwrap_err();
+  assert_eq!(failures.len(), 1);
+  assert_eq!(failures[0].0, 0);
+
+  //The status of each HDU tells which keyword did not match
+  let status = &tested.checksum_status().unwrap()[0];
+  assert_eq!(status.hdu_index, 0);
+  match &status.result {
+    rsf::ChecksumResult::Fail { keyword, expected, computed } => {
+      assert_eq!(*keyword, "DATASUM");
+      assert_ne!(expected.trim_matches('\'').parse::<u32>().unwrap(), *computed);
+    }
+    other => panic!("{other:?}"),
+  }
+  let written = rsf::Fits::open(&copy_path).unwrap();
+  assert_eq!(written.checksum_status().unwrap()[0].result, rsf::ChecksumResult::Pass);
+
+  //None of the bundled files contain checksums
+  for file in ["Hubble_FOC.fits", "Hubble_NICMOS.fits", "Hubble_HRS.fits"] {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    let statuses = rsf::Fits::open(&path).unwrap().checksum_status().unwrap();
+    assert!(statuses.iter().all(|status| status.result == rsf::ChecksumResult::Missing));
+  }
+}
+
+#[test]
+fn empty_hdu_test() {
+  let mut real_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real_path.push(REAL_FILE);
+
+  //Append two metadata-only HDU's, the second with copied metadata
+  let mut fits = rsf::Fits::open(&real_path).unwrap();
+  let meta = fits.get_hdu(0).unwrap().get_header().clone();
+  let first = fits.append_empty_hdu("META");
+  let second = fits.append_empty_hdu_with_meta("INSTRUMENT", meta);
+  assert_eq!((first, second), (6, 7)); //NICMOS file has six HDU's
+
+  //Write and read back the new file
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("empty_hdu.fits");
+  fits.write(&copy_path).unwrap();
+  let tested = rsf::Fits::open(&copy_path).unwrap();
+
+  let hdu = tested.get_hdu_by_name("META").unwrap();
+  assert!(hdu.get_data().is_none());
+  let hdu = tested.get_hdu_by_name("INSTRUMENT").unwrap();
+  assert!(hdu.get_data().is_none());
+  assert_eq!(hdu.get_header().get_value("TELESCOP"), Some(&String::from("'HST'")));
+  assert!(tested.get_hdu_by_name("NONEXISTENT").is_none());
+}
+
+#[test]
+fn reduce_along_axis_test() {
+  //3x4x5 cube filled with 0..60
+  let cube = ndarray::Array::from_shape_vec(vec![3, 4, 5], (0..60).map(|x| x as f64).collect());
+  let img = rsf::Image::new(cube.unwrap());
+
+  //Collapsing removes the reduced axis
+  let sum = img.reduce_along_axis(0, rsf::ReduceOp::Sum).unwrap();
+  assert_eq!(sum.get_shape(), &vec![4, 5]);
+  assert_eq!(sum.get_data().mean().unwrap(), 3.0 * 29.5);
+
+  //Each lane along axis 0 is {x, x+20, x+40}
+  let mean = img.reduce_along_axis(0, rsf::ReduceOp::Mean).unwrap();
+  let median = img.reduce_along_axis(0, rsf::ReduceOp::Median).unwrap();
+  let min = img.reduce_along_axis(0, rsf::ReduceOp::Min).unwrap();
+  let max = img.reduce_along_axis(0, rsf::ReduceOp::Max).unwrap();
+  assert_eq!(mean.get_data(), median.get_data());
+  assert_eq!(min.get_data()[[1, 2]], 7.0);
+  assert_eq!(max.get_data()[[1, 2]], 47.0);
+
+  //Median of an even number of values (lanes along axis 1 are {x, .., x+15})
+  let median = img.reduce_along_axis(1, rsf::ReduceOp::Median).unwrap();
+  assert_eq!(median.get_data()[[0, 0]], 7.5);
+
+  //There is no fourth axis
+  assert!(img.reduce_along_axis(3, rsf::ReduceOp::Sum).is_err());
+
+  //NaN's are skipped, also when a lane starts with one
+  let nans = ndarray::array![[f64::NAN, f64::NAN], [2.0, f64::NAN], [1.0, f64::NAN]];
+  let img = rsf::Image::new(nans.into_dyn());
+  let min = img.reduce_along_axis(0, rsf::ReduceOp::Min).unwrap();
+  let max = img.reduce_along_axis(0, rsf::ReduceOp::Max).unwrap();
+  assert_eq!(min.get_data()[[0]], 1.0);
+  assert_eq!(max.get_data()[[0]], 2.0);
+  assert!(min.get_data()[[1]].is_nan() && max.get_data()[[1]].is_nan());
+}
+
+#[test]
+fn image_arithmetic_test() {
+  let data = ndarray::Array::from_shape_vec(vec![2, 3], (0..6).collect::<Vec<i32>>());
+  let img = rsf::Image::new(data.unwrap());
+
+  //Scalar operations apply to every pixel
+  let img = (img + 2) * 3 - 6;
+  assert_eq!(img.get_data().as_slice().unwrap(), &[0, 3, 6, 9, 12, 15]);
+  let mut img = img;
+  img -= 3;
+  img *= 2;
+  img += 1;
+  assert_eq!(img.get_data().as_slice().unwrap(), &[-5, 1, 7, 13, 19, 25]);
+
+  //Images of the same shape are added pixel by pixel
+  let sum = (img.clone() + img.clone()).unwrap();
+  assert_eq!(sum.get_data(), &(img.get_data() * 2));
+  let mut acc = sum.clone();
+  acc += img.clone();
+  assert_eq!(acc.get_data(), &(img.get_data() * 3));
+
+  //Large images are processed in parallel, with the same result
+  let big = rsf::Image::new(ndarray::Array::from_elem(vec![1000, 1001], 1.5f64));
+  let big = (big.clone() + big * 2.0).unwrap() - 0.5;
+  assert!(big.get_data().iter().all(|&px| px == 4.0));
+
+  //Images with different shapes cannot be added
+  let other = rsf::Image::new(ndarray::Array::zeros(vec![3, 2]));
+  let err = (img + other).unwrap_err();
+  let err: Box<dyn std::error::Error> = Box::new(err);
+  assert!(err.is::<rsf::img_err::ShapeMismatchErr>());
+}
+
+#[test]
+#[should_panic]
+fn image_add_assign_mismatch_test() {
+  let mut img = rsf::Image::new(ndarray::Array::<f32, _>::zeros(vec![2, 3]));
+  img += rsf::Image::new(ndarray::Array::zeros(vec![2, 2]));
+}
+
+#[test]
+fn subimage_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(REAL_FILE);
+  let fits = rsf::Fits::open(&real).unwrap();
+  let img = match fits.get_hdu(1).unwrap().get_data() {
+    Some(rsf::Extension::Image(rsf::TypedImage::SpfImg(img))) => img.clone(),
+    _ => panic!(),
+  };
+  let (nx, ny) = (img.get_shape()[0], img.get_shape()[1]);
+
+  //2D cut-out: the shape and block size follow from the slice
+  let sub = img.subimage(&[10..20, 100..150]).unwrap();
+  assert_eq!(sub.get_shape(), &vec![10, 50]);
+  assert_eq!(sub.get_data()[[3, 7]], img.get_data()[[13, 107]]);
+  let bytes = rsf::TypedImage::SpfImg(sub.clone()).to_fits_bytes().unwrap();
+  assert_eq!(bytes.len(), 2 * 2880); //header + 10*50 f32s in a single data block
+  let whole = img.subimage(&[0..nx, 0..ny]).unwrap();
+  assert_eq!(whole.get_data(), img.get_data());
+
+  //3D cube made from two copies of the image: take a single channel
+  let cube =
+    ndarray::stack(ndarray::Axis(2), &[img.get_data().view(), (img.get_data() * 2.0).view()]);
+  let cube = rsf::Image::new(cube.unwrap());
+  let channel = cube.subimage(&[0..nx, 0..ny, 1..2]).unwrap();
+  assert_eq!(channel.get_shape(), &vec![nx, ny, 1]);
+  assert_eq!(channel.get_data()[[5, 6, 0]], 2.0 * img.get_data()[[5, 6]]);
+  let corner = cube.subimage(&[nx - 2..nx, ny - 3..ny, 0..2]).unwrap();
+  assert_eq!(corner.get_shape(), &vec![2, 3, 2]);
+  assert_eq!(corner.get_data()[[1, 2, 0]], img.get_data()[[nx - 1, ny - 1]]);
+
+  //Ranges have to fit inside the image
+  assert!(img.subimage(&[0..10]).is_err());
+  assert!(img.subimage(&[0..nx + 1, 0..10]).is_err());
+  assert!(cube.subimage(&[0..1, 0..1, 2..1]).is_err());
+}
+
+#[test]
+fn global_reduction_test() {
+  let ones = ndarray::Array::from_elem(vec![2, 2], 1.0f64);
+  let img = rsf::TypedImage::DpfImg(rsf::Image::new(ones));
+  assert_eq!(img.sum(), 4.0);
+  assert_eq!(img.product(), 1.0);
+
+  //NaN's are skipped when looking for the extrema
+  let data = ndarray::arr1(&[3.0f32, f32::NAN, -2.5, 8.0]).into_dyn();
+  let img = rsf::TypedImage::SpfImg(rsf::Image::new(data));
+  assert_eq!(img.min_pixel(), -2.5);
+  assert_eq!(img.max_pixel(), 8.0);
+  let nans = ndarray::Array::from_elem(vec![3], f32::NAN);
+  assert!(rsf::TypedImage::SpfImg(rsf::Image::new(nans)).min_pixel().is_nan());
+
+  //Integer products saturate
+  let big = ndarray::Array::from_elem(vec![4], i64::MAX / 2);
+  assert_eq!(rsf::TypedImage::I64Img(rsf::Image::new(big)).product(), i64::MAX as f64);
+
+  //...regardless of factors that come after the product saturated
+  let mut factors = vec![-3i32; 65];
+  factors.extend([-1, -1]);
+  let img = rsf::TypedImage::I32Img(rsf::Image::new(ndarray::Array::from_vec(factors).into_dyn()));
+  assert_eq!(img.product(), i64::MIN as f64);
+  let data = ndarray::arr1(&[i32::MAX, i32::MAX, i32::MAX, 0]).into_dyn();
+  assert_eq!(rsf::TypedImage::I32Img(rsf::Image::new(data)).product(), 0.0);
+}
+
+#[test]
+fn statistics_test() {
+  let data = ndarray::arr1(&[2.0f64, 4.0, f64::NAN, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).into_dyn();
+  let img = rsf::TypedImage::DpfImg(rsf::Image::new(data));
+  assert_eq!(img.min_max(), (2.0, 9.0));
+  assert_eq!(img.mean(), 5.0);
+  assert_eq!(img.stddev(), 2.0);
+
+  //Bins span [min, max], the largest pixel is counted in the last bin
+  let (edges, counts) = img.histogram(7);
+  assert_eq!(edges, vec![2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+  assert_eq!(counts, vec![1, 0, 3, 2, 0, 1, 1]);
+
+  //Integer images work the same way
+  let data = ndarray::Array::from_elem(vec![2, 3], 7u8);
+  let img = rsf::TypedImage::ByteImg(rsf::Image::new(data));
+  assert_eq!((img.mean(), img.stddev()), (7.0, 0.0));
+  assert_eq!(img.histogram(2), (vec![7.0, 7.0, 7.0], vec![6, 0]));
+}
+
+#[test]
+fn rescale_test() {
+  let data = ndarray::arr1(&[-2i16, 0, 6]).into_dyn();
+  let img = rsf::TypedImage::I16Img(rsf::Image::new(data));
+  assert_eq!(img.rescale(0.5, 10.0), ndarray::arr1(&[9.0, 10.0, 13.0]).into_dyn());
+  assert_eq!(img.normalize(), ndarray::arr1(&[0.0, 0.25, 1.0]).into_dyn());
+  assert_eq!(img.apply(|px| px * px), ndarray::arr1(&[4.0, 0.0, 36.0]).into_dyn());
+
+  //The stored image is left untouched
+  assert_eq!(img.as_i16_array().unwrap(), &ndarray::arr1(&[-2i16, 0, 6]).into_dyn());
+
+  //Constant images normalize to zero, NaN's stay NaN
+  let data = ndarray::arr1(&[3.0f32, f32::NAN, 3.0]).into_dyn();
+  let norm = rsf::TypedImage::SpfImg(rsf::Image::new(data)).normalize();
+  assert_eq!((norm[0], norm[2]), (0.0, 0.0));
+  assert!(norm[1].is_nan());
+}
+
+#[test]
+fn data_info_test() {
+  use rsf::{Extension::Image, Image as Img, TypedImage::*};
+  let shape = vec![2, 3, 4];
+  let images = [
+    (ByteImg(Img::new(ndarray::Array::zeros(shape.clone()))), "u8", 1),
+    (I16Img(Img::new(ndarray::Array::zeros(shape.clone()))), "i16", 2),
+    (I32Img(Img::new(ndarray::Array::zeros(shape.clone()))), "i32", 4),
+    (I64Img(Img::new(ndarray::Array::zeros(shape.clone()))), "i64", 8),
+    (SpfImg(Img::new(ndarray::Array::zeros(shape.clone()))), "f32", 4),
+    (DpfImg(Img::new(ndarray::Array::zeros(shape.clone()))), "f64", 8),
+  ];
+
+  for (img, name, size) in images {
+    let data = Image(img);
+    assert_eq!(data.shape(), Some(&shape[..]));
+    assert_eq!(data.ndim(), Some(3));
+    assert_eq!(data.element_count(), 24);
+    assert_eq!(data.byte_size(), 24 * size);
+    assert_eq!(data.dtype_name(), name);
+  }
+}
+
+fn roundtrip_hdu(img: rsf::TypedImage) -> rsf::HeaderDataUnit {
+  let fits = rsf::Fits::from_bytes(&img.to_fits_bytes().unwrap()).unwrap();
+  fits.get_hdu(0).unwrap().clone()
+}
+
+fn check_typed_access<T: rsf::Pixel + std::fmt::Debug + PartialEq + From<u8>>(
+  img: rsf::TypedImage,
+) {
+  let mut hdu = roundtrip_hdu(img);
+  assert_eq!(hdu.image::<T>().unwrap().shape(), &[2, 2]);
+  hdu.image_mut::<T>().unwrap()[[1, 0]] = T::from(7);
+  assert_eq!(hdu.image::<T>().unwrap()[[1, 0]], T::from(7));
+
+  //Only T itself is accepted as the pixel type
+  let ok = [
+    hdu.image::<u8>().is_ok(),
+    hdu.image::<i16>().is_ok(),
+    hdu.image::<i32>().is_ok(),
+    hdu.image::<i64>().is_ok(),
+    hdu.image::<f32>().is_ok(),
+    hdu.image::<f64>().is_ok(),
+  ];
+  assert_eq!(ok.iter().filter(|&&ok| ok).count(), 1);
+  assert!(hdu.table().is_err());
+}
+
+#[test]
+fn typed_access_test() {
+  use rsf::{Image, TypedImage::*};
+  let data = || ndarray::Array::from_elem(vec![2, 2], 1u8);
+  check_typed_access::<u8>(ByteImg(Image::new(data())));
+  check_typed_access::<i16>(I16Img(Image::new(data().mapv(i16::from))));
+  check_typed_access::<i32>(I32Img(Image::new(data().mapv(i32::from))));
+  check_typed_access::<i64>(I64Img(Image::new(data().mapv(i64::from))));
+  check_typed_access::<f32>(SpfImg(Image::new(data().mapv(f32::from))));
+  check_typed_access::<f64>(DpfImg(Image::new(data().mapv(f64::from))));
+
+  //Asking for the wrong pixel type is an error
+  let hdu = roundtrip_hdu(I16Img(Image::new(data().mapv(i16::from))));
+  let err = hdu.image::<f32>().unwrap_err();
+  assert!(err.to_string().contains("Short"));
+}
+
+#[test]
+fn to_fits_bytes_test() {
+  let data = ndarray::Array::from_shape_vec(vec![3, 3], vec![1i16, -2, 3, -4, 5, -6, 7, -8, 9]);
+  let img = rsf::TypedImage::I16Img(rsf::Image::new(data.unwrap()));
+
+  //Serialize the image and check that it's a valid FITS file
+  let bytes = img.to_fits_bytes().unwrap();
+  assert_eq!(bytes.len() % 2880, 0);
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("to_fits_bytes.fits");
+  std::fs::write(&path, bytes).unwrap();
+
+  let fits = rsf::Fits::open(&path).unwrap();
+  let tested = match fits.get_hdu(0).unwrap().get_data().unwrap() {
+    rsf::Extension::Image(img) => img.as_i16_array().unwrap().clone(),
+    _ => panic!(),
+  };
+  assert_eq!(&tested, img.as_i16_array().unwrap());
+}
+
+#[test]
+fn cast_test() {
+  let data = ndarray::Array::from_shape_vec(vec![2, 2], vec![1i16, -2, 300, 4]).unwrap();
+  let img = rsf::TypedImage::I16Img(rsf::Image::new(data));
+
+  //Every image can be converted to f64
+  let as_f64 = img.clone().into_f64();
+  assert_eq!(as_f64.iter().copied().collect::<Vec<_>>(), vec![1.0, -2.0, 300.0, 4.0]);
+  assert_eq!(as_f64.shape(), &[2, 2]);
+
+  //Casts only succeed if all pixels fit in the new type
+  assert_eq!(img.clone().cast::<i64>().unwrap()[[1, 0]], 300);
+  assert_eq!(img.clone().cast::<f32>().unwrap()[[0, 1]], -2.0);
+  let err = img.cast::<u8>().unwrap_err();
+  assert!(err.to_string().contains("u8"));
+
+  let nan = rsf::TypedImage::DpfImg(rsf::Image::new(ndarray::arr1(&[1.0, f64::NAN]).into_dyn()));
+  assert!(nan.clone().cast::<i32>().is_err());
+  assert!(nan.into_f64()[1].is_nan());
+}
+
+#[test]
+fn scaling_test() {
+  //The UIT image is stored as i16 with BSCALE = 2.0587209E-16
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+  let get_img = |fits: &rsf::Fits, index| match fits.get_hdu(index).unwrap().get_data().unwrap() {
+    rsf::Extension::Image(img) => img.clone(),
+    _ => panic!(),
+  };
+
+  let raw_fits = rsf::Fits::open_with_scaling(&path, rsf::ScalingMode::Raw).unwrap();
+  let raw = get_img(&raw_fits, 0);
+  let physical_fits = rsf::Fits::open(&path).unwrap();
+  let physical = get_img(&physical_fits, 0);
+  let (raw, physical) = (raw.as_i16_array().unwrap(), physical.as_f64_array().unwrap());
+  assert_eq!(raw.shape(), physical.shape());
+  for (stored, value) in raw.iter().zip(physical.iter()).step_by(97) {
+    assert_eq!(*value, 2.0587209E-16 * *stored as f64);
+  }
+
+  //The header of the scaled image describes the f64 data
+  let header = physical_fits.get_hdu(0).unwrap().get_header();
+  assert_eq!(header.get_value_as::<i64>("BITPIX").unwrap(), -64);
+  assert!(header.get_value("BSCALE").is_none());
+
+  //Images without scaling keywords are only converted if we ask for it
+  let mut nicmos = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  nicmos.push(REAL_FILE);
+  let fits = rsf::Fits::open(&nicmos).unwrap();
+  assert!(get_img(&fits, 1).as_f32_array().is_ok());
+  let fits = rsf::Fits::open_with_scaling(&nicmos, rsf::ScalingMode::Physical).unwrap();
+  assert!(get_img(&fits, 1).as_f64_array().is_ok());
+}
+
+#[test]
+fn blank_mask_test() {
+  let mut data = ndarray::Array::from_elem(vec![3, 2], 5i16);
+  data[[1, 0]] = -1;
+  let img = rsf::TypedImage::I16Img(rsf::Image::new(data));
+
+  //Add a BLANK record right before the END record
+  let mut bytes = img.to_fits_bytes().unwrap();
+  let end = (0..2880).step_by(80).find(|&i| bytes[i..].starts_with(b"END ")).unwrap();
+  let blank = format!("{:<8}= {:>20}", "BLANK", -1);
+  bytes[end..end + 80].copy_from_slice(format!("{blank:<80}").as_bytes());
+  bytes[end + 80..end + 83].copy_from_slice(b"END");
+
+  //Undefined pixels are masked, but keep their stored value
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let hdu = fits.primary().unwrap();
+  let mask = hdu.blank_mask().unwrap();
+  assert_eq!(mask.iter().filter(|&&px| px).count(), 1);
+  assert!(mask[[1, 0]]);
+  let stored = match hdu.get_data().unwrap() {
+    rsf::Extension::Image(img) => img.as_i16_array().unwrap().clone(),
+    _ => panic!(),
+  };
+  assert_eq!(stored[[1, 0]], -1);
+
+  //Without a BLANK record there is no mask
+  let fits = rsf::Fits::from_bytes(&img.to_fits_bytes().unwrap()).unwrap();
+  assert!(fits.primary().unwrap().blank_mask().is_none());
+  assert_eq!(img.blank_mask(5).unwrap().iter().filter(|&&px| px).count(), 5);
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn rgb_image_test() {
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Astro_UIT.fits");
+
+  let fits = rsf::Fits::open_with_scaling(&path, rsf::ScalingMode::Raw).unwrap();
+  let img = match fits.get_hdu(0).unwrap().get_data().unwrap() {
+    rsf::Extension::Image(img) => img,
+    _ => panic!(),
+  };
+
+  //Stretch the image between its smallest and largest pixel
+  let array = img.as_i16_array().unwrap();
+  let min = *array.iter().min().unwrap() as f64;
+  let max = *array.iter().max().unwrap() as f64;
+
+  let rgb = img.to_rgb_image(rsf::Colormap::Grayscale, rsf::Stretch::Linear(min, max));
+  assert_eq!(rgb.dimensions(), (array.shape()[0] as u32, array.shape()[1] as u32));
+}
+
+#[test]
+fn display_test() {
+  let data = ndarray::Array::from_elem(vec![3, 4], 1.0f64);
+  let img = rsf::TypedImage::DpfImg(rsf::Image::new(data));
+  assert_eq!(format!("{img}"), "TypedImage(f64, 3×4, 1 blocks)");
+
+  //HDU's show both their header and their data
+  let mut real_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real_path.push(REAL_FILE);
+  let fits = rsf::Fits::open(&real_path).unwrap();
+  let primary = format!("{}", fits.get_hdu(0).unwrap());
+  assert!(primary.starts_with("[Header] - #records: "));
+  assert!(primary.ends_with("[Data] (NO_DATA)"));
+  assert!(format!("{}", fits.get_hdu(1).unwrap()).contains("[Data] (IMAGE)"));
+}
+
+#[test]
+#[cfg(feature = "rice")]
+fn rice_test() {
+  let open = |file: &str| {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources");
+    path.push(file);
+    rsf::Fits::open(&path).unwrap()
+  };
+  let flat = |hdu: &rsf::HeaderDataUnit| -> Vec<f64> {
+    //Pixels in the order in which they are stored (first axis fastest)
+    let img = hdu.get_data().unwrap();
+    match img {
+      rsf::Extension::Image(img) => img.apply(|px| px).t().iter().copied().collect(),
+      _ => panic!("not an image"),
+    }
+  };
+
+  //(1) Integer images are compressed losslessly, and their headers look
+  //    like the headers of the original images
+  let original = open("Hubble_NICMOS.fits");
+  let compressed = open("Hubble_NICMOS_rice.fits");
+  for index in [3, 4] {
+    let (hdu, copy) = (original.get_hdu(index).unwrap(), compressed.get_hdu(index).unwrap());
+    assert_eq!(hdu.image::<i16>().unwrap(), copy.image::<i16>().unwrap());
+    assert_eq!(hdu.get_extname(), copy.get_extname());
+    let header = copy.get_header();
+    assert_eq!(header.get_string("XTENSION").as_deref(), Some("IMAGE"));
+    assert_eq!(header.get_int("NAXIS1"), Some(270));
+    assert!(!header.contains("ZIMAGE") && !header.contains("TFIELDS"));
+  }
+
+  //(2) Floating point images are quantized (and dithered). These pixel
+  //    values were decompressed with cfitsio
+  let expected: [(usize, [u32; 5]); 2] = [
+    (1, [0x3c612f2a, 0x3c25f244, 0x3e956741, 0x3f373ece, 0xbd835770]),
+    (5, [0x3eb7f1b0, 0xbe2ba895, 0x43428073, 0x43bfe612, 0x4286b8ec]),
+  ];
+  for (index, bits) in expected {
+    let pixels = flat(compressed.get_hdu(index).unwrap());
+    for (px, bits) in [0, 1, 1000, 35000, 71009].into_iter().zip(bits) {
+      assert_eq!((pixels[px] as f32).to_bits(), bits, "HDU {index}, pixel {px}");
+    }
+    assert!(compressed.get_hdu(index).unwrap().image::<f32>().is_ok());
+  }
+
+  //(3) Images of other types and shapes, with tiles at the edges that are
+  //    smaller than the others
+  let synthetic = open("rice_synthetic.fits");
+  let bytes = (0..37 * 23).map(|i: u64| ((i * 7 + i / 37 * 3) % 251) as f64);
+  assert_eq!(flat(synthetic.get_hdu(1).unwrap()), bytes.collect::<Vec<_>>());
+  assert!(synthetic.get_hdu(1).unwrap().image::<u8>().is_ok());
+
+  let ints = (0..50 * 40).map(|i: i64| {
+    let (x, y) = (i % 50, i / 50);
+    match y {
+      0..10 => -12345,
+      10..20 => ((x * 2654435761 + y * 40503) % 2000000007 - 1000000000) as i32,
+      _ => (x * x - y * 3) as i32,
+    }
+  });
+  let hdu = synthetic.get_hdu(2).unwrap();
+  assert_eq!(flat(hdu), ints.map(f64::from).collect::<Vec<_>>());
+  assert!(hdu.image::<i32>().is_ok());
+
+  let shorts = (0..9 * 7 * 5).map(|i: i32| f64::from(i * 97 % 3001 - 1500));
+  let hdu = synthetic.get_hdu(3).unwrap();
+  assert_eq!(hdu.image::<i16>().unwrap().shape(), [9, 7, 5]);
+  assert_eq!(flat(hdu), shorts.collect::<Vec<_>>());
+
+  //(4) Decompressed images are written as ordinary images
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("rice_decompressed.fits");
+  synthetic.clone().write(&path).unwrap();
+  let written = rsf::Fits::open(&path).unwrap();
+  let hdu = written.get_hdu(3).unwrap();
+  assert_eq!(hdu.image::<i16>().unwrap(), synthetic.get_hdu(3).unwrap().image::<i16>().unwrap());
+  assert!(!hdu.get_header().contains("ZCMPTYPE"));
+}
+
+#[test]
+fn primary_image_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push("resources/Hubble_FOC.fits");
+
+  //The primary image is read in one go, the type has to match
+  let (array, header) = rsf::Fits::read_primary_image::<f32>(&real).unwrap();
+  assert_eq!(array.shape(), [1024, 1024]);
+  assert_eq!(header.get_value("BITPIX").unwrap(), "-32");
+  let fits = rsf::Fits::open(&real).unwrap();
+  assert_eq!(array, fits.primary().unwrap().image::<f32>().unwrap());
+  let err = rsf::Fits::read_primary_image::<i16>(&real).unwrap_err();
+  let err = err.downcast_ref::<rsf::img_err::WrongImgTypeErr>().unwrap();
+  assert_eq!(err.get_expected_bitpix(), rsf::Bitpix::Short);
+  assert_eq!(err.get_actual_bitpix(), rsf::Bitpix::Spf);
+
+  //Writing keeps the user-supplied records, but not the old data layout
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("primary_image.fits");
+  let small = ndarray::Array::from_shape_fn(vec![4, 3], |ix| (ix[0] * 3 + ix[1]) as i16).into_dyn();
+  rsf::Fits::write_primary_image(&path, small.clone(), Some(header)).unwrap();
+  let (read, header) = rsf::Fits::read_primary_image::<i16>(&path).unwrap();
+  assert_eq!(read, small);
+  assert_eq!(header.get_value("BITPIX").unwrap(), "16");
+  assert_eq!(header.get_value("NAXIS1").unwrap(), "4");
+  assert_eq!(
+    header.get_value("TARGNAME"),
+    fits.primary().unwrap().get_header().get_value("TARGNAME")
+  );
+
+  //Files without a primary image are rejected
+  real.set_file_name("Hubble_NICMOS.fits");
+  let err = rsf::Fits::read_primary_image::<f32>(&real).unwrap_err();
+  let err = err.downcast_ref::<rsf::hdu_err::WrongExtensionErr>().unwrap();
+  assert!(err.is_no_data());
+  assert!(!err.is_variant_error());
+}
+
+#[test]
+fn bitpix_test() {
+  use rsf::Bitpix;
+
+  //The BITPIX codes of the standard and the rust types that go with them
+  let codes: [(i8, Bitpix, usize, &str); 6] = [
+    (8, Bitpix::Byte, 1, "u8"),
+    (16, Bitpix::Short, 2, "i16"),
+    (32, Bitpix::Int, 4, "i32"),
+    (64, Bitpix::Long, 8, "i64"),
+    (-32, Bitpix::Spf, 4, "f32"),
+    (-64, Bitpix::Dpf, 8, "f64"),
+  ];
+  for (code, bitpix, width, name) in codes {
+    assert_eq!(Bitpix::try_from(code).unwrap(), bitpix);
+    assert_eq!(Bitpix::try_from(code as i64).unwrap(), bitpix);
+    assert_eq!(bitpix.byte_width(), width);
+    assert_eq!(bitpix.dtype_name(), name);
+    assert_eq!(Bitpix::from_rust_type_name(name), Some(bitpix));
+  }
+  assert!(Bitpix::try_from(12i8).is_err());
+  assert!(Bitpix::try_from(-16i64).is_err());
+  assert_eq!(Bitpix::from_rust_type_name("u16"), None);
+
+  //The header summary stores BITPIX as an i8
+  let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  path.push("resources/Hubble_FOC.fits");
+  let summary = &rsf::Fits::scan_headers_only(&path).unwrap()[0];
+  assert_eq!(Bitpix::try_from(summary.bitpix).unwrap(), Bitpix::Spf);
+}
+
+#[test]
+fn image_eq_test() {
+  use rsf::{Image, TypedImage::*};
+  let data = || ndarray::Array::from_shape_fn(vec![3, 2], |idx| (idx[0] * 2 + idx[1]) as u8);
+  let images = [
+    ByteImg(Image::new(data())),
+    I16Img(Image::new(data().mapv(i16::from))),
+    I32Img(Image::new(data().mapv(i32::from))),
+    I64Img(Image::new(data().mapv(i64::from))),
+    SpfImg(Image::new(data().mapv(f32::from))),
+    DpfImg(Image::new(data().mapv(f64::from))),
+  ];
+
+  //Every data type survives a round trip through a FITS file
+  for img in &images {
+    let fits = rsf::Fits::from_bytes(&img.to_fits_bytes().unwrap()).unwrap();
+    match fits.get_hdu(0).unwrap().get_data().unwrap() {
+      rsf::Extension::Image(tested) => assert_eq!(tested, img),
+      _ => panic!(),
+    }
+  }
+
+  //Images with other pixels, another shape or another data type differ
+  let mut other = data();
+  other[[2, 1]] = 0;
+  assert_ne!(images[0], ByteImg(Image::new(other)));
+  assert_ne!(images[0], ByteImg(Image::new(data().into_shape(vec![2, 3]).unwrap())));
+  assert_ne!(images[4], images[5]);
+  let nan = || SpfImg(Image::new(ndarray::Array::from_elem(vec![1], f32::NAN)));
+  assert_ne!(nan(), nan());
+}
@@ -49,3 +49,823 @@ fn read_test() {
   //Print formatted rows with strings
   println!("{:?}", tbl.get_fmtd_column(10).unwrap());
 }
+
+#[test]
+fn column_stats_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+
+  let mut fits = rsf::Fits::open(&real).unwrap();
+  let (_h, xt) = fits.remove_hdu(1).unwrap().to_parts();
+  let tbl = match xt.unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl,
+    _ => panic!(),
+  };
+
+  //Calculate the statistics of a float column by hand
+  let values: Vec<f64> = (0..tbl.get_shape().1)
+    .map(|row| match tbl.get_entry(3, row).unwrap() {
+      rsf::TableEntry::Float(num) => num,
+      _ => panic!(),
+    })
+    .collect();
+  let n = values.len() as f64;
+  let mean = values.iter().sum::<f64>() / n;
+  let stddev = (values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+
+  let stats = tbl.column_stats(3).unwrap();
+  assert_eq!(stats.n_values, values.len());
+  assert_eq!(stats.n_nulls, 0);
+  assert!((stats.mean - mean).abs() <= 1e-12 * mean.abs());
+  assert!((stats.stddev - stddev).abs() <= 1e-12 * stddev.abs());
+
+  //Integer columns are cast to floats, string columns are rejected
+  assert_eq!(tbl.column_stats(19).unwrap().min, 2.0);
+  assert!(tbl.column_stats(10).is_err());
+
+  //Column with known statistics: mean 4, population stddev 2
+  let values = vec![1.0, 7.0, 3.0, 5.0, 4.0];
+  let tbl = rsf::AsciiTable::new().with_column(rsf::Column::from_vec(None, None, values));
+  let stats = tbl.column_stats(0).unwrap();
+  assert_eq!((stats.n_values, stats.n_nulls), (5, 0));
+  assert_eq!((stats.min, stats.max), (1.0, 7.0));
+  assert!((stats.mean - 4.0).abs() <= 1e-12);
+  assert!((stats.stddev - 2.0).abs() <= 1e-12);
+}
+
+#[test]
+fn column_unit_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+  let table = |fits: &rsf::Fits| match fits.get_hdu(1).unwrap().get_data() {
+    Some(rsf::Extension::AsciiTable(tbl)) => tbl.clone(),
+    _ => panic!("HRS file should contain an ASCII table"),
+  };
+
+  //The HRS table has no TUNIT keywords, its units are only documented in
+  //the descriptions of the columns
+  let tbl = table(&rsf::Fits::open(&real).unwrap());
+  assert!((0..tbl.get_shape().0).all(|col| tbl.column_unit(col).is_none()));
+
+  //Add the documented unit of the EXPOSURE column by overwriting END
+  let mut bytes = std::fs::read(&real).unwrap();
+  let tbl_header = 19 * 2880;
+  let end = (tbl_header..bytes.len())
+    .step_by(80)
+    .find(|&rec| bytes[rec..rec + 80].starts_with(b"END "))
+    .unwrap();
+  bytes[end..end + 160]
+    .copy_from_slice(format!("{:80}{:80}", "TUNIT19 = 'seconds '", "END").as_bytes());
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let tbl = table(&fits);
+  assert_eq!(tbl.column_unit(18), Some("seconds"));
+  assert_eq!(tbl.column_unit(0), None);
+  assert_eq!(tbl.column_unit(tbl.get_shape().0), None);
+
+  //The unit is written back as TUNIT19
+  let copy = rsf::Fits::from_bytes(&fits.to_bytes().unwrap()).unwrap();
+  assert_eq!(
+    copy.get_hdu(1).unwrap().get_header().get_string("TUNIT19").as_deref(),
+    Some("seconds")
+  );
+  assert_eq!(table(&copy).column_unit(18), Some("seconds"));
+}
+
+#[test]
+fn dedup_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+
+  let mut fits = rsf::Fits::open(&real).unwrap();
+  let (_h, xt) = fits.remove_hdu(1).unwrap().to_parts();
+  let mut tbl = match xt.unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl,
+    _ => panic!(),
+  };
+  assert!(!tbl.has_duplicates());
+
+  //Append a copy of the first row
+  let (n_cols, n_rows) = tbl.get_shape();
+  let row = (0..n_cols).map(|col| tbl.get_entry(col, 0).unwrap()).collect();
+  tbl.add_row(row).unwrap();
+  assert!(tbl.row_equals(0, n_rows));
+  assert!(!tbl.row_equals(0, 1));
+  assert!(tbl.has_duplicates());
+
+  //Deduplicating should remove the copy again
+  tbl.dedup();
+  assert_eq!(tbl.get_shape(), (n_cols, n_rows));
+  assert!(!tbl.has_duplicates());
+
+  //Entries compare by value, but deduplication compares floats bit-by-bit
+  use rsf::TableEntry::Float;
+  assert_eq!(Float(0.0), Float(-0.0));
+  assert_ne!(Float(f64::NAN), Float(f64::NAN));
+  let floats = vec![f64::NAN, f64::NAN, 0.0, -0.0];
+  let mut tbl = rsf::AsciiTable::new().with_column(rsf::Column::from_vec(None, None, floats));
+  assert!(!tbl.row_equals(0, 1));
+  assert!(tbl.row_equals(2, 3));
+  assert!(tbl.has_duplicates());
+  tbl.dedup();
+  assert_eq!(tbl.get_shape(), (1, 3));
+  assert!(f64::try_from(tbl.get_entry(0, 2).unwrap()).unwrap().is_sign_negative());
+}
+
+#[test]
+fn is_sorted_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+
+  let mut fits = rsf::Fits::open(&real).unwrap();
+  let (_h, xt) = fits.remove_hdu(1).unwrap().to_parts();
+  let tbl = match xt.unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl,
+    _ => panic!(),
+  };
+
+  //Column 9 (PKTTIME) is increasing, column 3 (DATAMIN) is not sorted
+  assert_eq!(tbl.is_sorted_asc(9), Some(true));
+  assert_eq!(tbl.is_sorted_desc(9), Some(false));
+  assert_eq!(tbl.is_sorted_asc(3), Some(false));
+  assert_eq!(tbl.is_sorted_desc(3), Some(false));
+  assert_eq!(tbl.is_sorted_asc(tbl.get_shape().0), None);
+
+  //Constant columns are sorted both ways
+  assert_eq!(tbl.is_sorted_asc(10), Some(true));
+  assert_eq!(tbl.is_sorted_desc(10), Some(true));
+
+  //Custom comparator: reversing the order turns ascending into descending
+  let reversed = |a: &rsf::TableEntry, b: &rsf::TableEntry| match (a, b) {
+    (rsf::TableEntry::Float(a), rsf::TableEntry::Float(b)) => b.partial_cmp(a).unwrap(),
+    _ => panic!(),
+  };
+  assert_eq!(tbl.is_sorted_by(9, reversed), Some(false));
+  assert_eq!(tbl.is_sorted_by(3, reversed), Some(false));
+  assert_eq!(tbl.is_sorted_by(9, |a, b| reversed(b, a)), Some(true));
+
+  //Single-row tables are always sorted
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push("resources/Hubble_FOC.fits");
+  let mut fits = rsf::Fits::open(&real).unwrap();
+  let (_h, xt) = fits.remove_hdu(1).unwrap().to_parts();
+  let tbl = match xt.unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl,
+    _ => panic!(),
+  };
+  assert_eq!(tbl.get_shape().1, 1);
+  assert_eq!(tbl.is_sorted_asc(0), Some(true));
+  assert_eq!(tbl.is_sorted_desc(0), Some(true));
+}
+
+#[test]
+fn write_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+
+  //Write a copy of the file containing the table
+  let fits = rsf::Fits::open(&real).unwrap();
+  let original = match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl.clone(),
+    _ => panic!(),
+  };
+  let mut copy_path = dirs::cache_dir().unwrap();
+  copy_path.push("table_copy.fits");
+  fits.write(&copy_path).unwrap();
+
+  //Read it back and compare all entries
+  let fits = rsf::Fits::open(&copy_path).unwrap();
+  let tested = match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl,
+    _ => panic!(),
+  };
+  assert_eq!(tested.get_shape(), original.get_shape());
+  assert_eq!(*tested, original);
+
+  let (n_cols, n_rows) = original.get_shape();
+  for col in 0..n_cols {
+    for row in 0..n_rows {
+      match (original.get_entry(col, row).unwrap(), tested.get_entry(col, row).unwrap()) {
+        //Floats are written with 15 digits after the decimal point
+        (rsf::TableEntry::Float(a), rsf::TableEntry::Float(b)) => {
+          assert!((a - b).abs() <= 1e-14 * a.abs())
+        }
+        (a, b) => assert_eq!(a, b),
+      }
+    }
+  }
+}
+
+#[test]
+fn table_eq_test() {
+  let table = |values: Vec<f64>| {
+    let ids = rsf::Column::from_vec(Some("ID".into()), None, vec![1i64, 2, 3]);
+    rsf::AsciiTable::new().with_column(ids).with_column(rsf::Column::from_vec(None, None, values))
+  };
+
+  //Floats only have to agree up to a relative tolerance, NaN's are equal
+  let original = table(vec![0.1, 1e20, f64::NAN]);
+  assert_eq!(original, table(vec![0.1 + 1e-17, 1e20 * (1.0 + 1e-12), f64::NAN]));
+  assert_ne!(original, table(vec![0.1 + 1e-9, 1e20, f64::NAN]));
+  assert_ne!(original, table(vec![0.1, 1e20, 0.0]));
+
+  //Tables of a different shape are never equal
+  assert_ne!(original, table(vec![0.1, 1e20]));
+  assert_ne!(original, original.clone().with_column(rsf::Column::from_vec(None, None, vec![1i64])));
+}
+
+#[test]
+fn bin_table_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push("resources/EUVE.fits");
+
+  //The first binary table contains a text column and two float columns
+  let fits = rsf::Fits::open(&real).unwrap();
+  let tbl = match fits.get_hdu_by_name("ds_limits").unwrap().get_data().unwrap() {
+    rsf::Extension::BinTable(tbl) => tbl,
+    _ => panic!(),
+  };
+  println!("{tbl}");
+  assert_eq!(tbl.get_shape(), (3, 3));
+
+  let names = tbl.get_column_by_name("NAME").unwrap();
+  assert_eq!(
+    names.get_data(),
+    &rsf::BinColumnData::Text(vec!["dsq1sf".into(), "dsadct".into(), "lookzen".into()])
+  );
+  let high = tbl.get_column(2).unwrap();
+  assert_eq!(high.get_label(), Some("HIGH"));
+  assert_eq!(high.get_data(), &rsf::BinColumnData::Float(vec![50.0, 5000.0, 102.0]));
+
+  //This table has fields containing 640-element arrays
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push("resources/IUE_LWP.fits");
+  let fits = rsf::Fits::open(&real).unwrap();
+  let tbl = match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::BinTable(tbl) => tbl,
+    _ => panic!(),
+  };
+  assert_eq!(tbl.get_shape(), (9, 1));
+  assert_eq!(tbl.get_column(1).unwrap().get_data(), &rsf::BinColumnData::Short(vec![640]));
+
+  let net = tbl.get_column_by_name("NET").unwrap();
+  assert_eq!(net.get_repeat(), 640);
+  match net.get_data() {
+    rsf::BinColumnData::Float(vals) => {
+      assert_eq!(vals.len(), 640);
+      assert_eq!(vals[0], 8.16181755065918);
+    }
+    _ => panic!(),
+  }
+  assert_eq!(tbl.get_column(2).unwrap().get_unit(), Some("ANGSTROM"));
+}
+
+#[test]
+fn builder_test() {
+  //Build a table from scratch: one text and one integer column
+  let names =
+    rsf::Column::from_vec(Some("NAME".into()), None, vec!["abcdefghij".to_string(); 1000]);
+  let mut ids = rsf::Column::new(Some("ID".into()), Some("none".into()));
+  (0..1000i64).for_each(|id| ids.push(id));
+  let mut tbl = rsf::AsciiTable::new().with_column(names);
+  tbl
+    .add_column(ids)
+    .add_row(vec![rsf::TableEntry::Text("k".into()), rsf::TableEntry::Int(-7)])
+    .unwrap();
+
+  assert_eq!(tbl.get_shape(), (2, 1001));
+  assert_eq!(tbl.get_entry(1, 999).unwrap(), rsf::TableEntry::Int(999));
+  assert_eq!(tbl.column_unit(1), Some("none"));
+
+  //Rows are 10 + 1 + 5 characters wide, which takes up 6 FITS blocks
+  let data = rsf::Extension::AsciiTable(tbl);
+  assert_eq!(format!("{data}"), "(TABLE) - #columns: 2, #rows: 1001, size: 6");
+  assert_eq!((data.shape(), data.ndim()), (None, None));
+  assert_eq!((data.element_count(), data.byte_size()), (2002, 6 * 2880));
+  assert_eq!(data.dtype_name(), "table");
+}
+
+#[test]
+fn unequal_columns_test() {
+  //The longest column sets the number of rows, wherever it is in the table
+  let names = vec!["vega".to_string(), "deneb".to_string(), "altair".to_string()];
+  let tbl = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(Some("ID".into()), None, vec![7i64]));
+  assert_eq!(tbl.get_shape(), (2, 3));
+  assert_eq!(tbl.iter_rows().count(), 3);
+  assert_eq!(tbl.row(2).unwrap(), vec![rsf::TableEntry::Text("altair".into())]);
+
+  //Shorter columns are padded with empty fields when they are written
+  let fits = rsf::FitsBuilder::new().extension(rsf::HduBuilder::table(tbl)).build().unwrap();
+  let read = rsf::Fits::from_bytes(&fits.to_bytes().unwrap()).unwrap();
+  let hdu = read.get_hdu(1).unwrap();
+  assert_eq!(hdu.get_header().get_value("NAXIS2").unwrap(), "3");
+  let read = hdu.table().unwrap();
+  assert_eq!(read.get_shape(), (2, 3));
+  assert_eq!(read.get_entry(0, 2).unwrap(), rsf::TableEntry::Text("altair".into()));
+  assert_eq!(read.get_entry(1, 0).unwrap(), rsf::TableEntry::Int(7));
+  //padded entries are undefined
+  assert_eq!(read.get_entry(1, 2).unwrap(), rsf::TableEntry::Int(rsf::TableEntry::NULL_INT));
+}
+
+#[test]
+fn row_access_test() {
+  let names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+  let tbl = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(Some("MAG".into()), None, vec![1.5, 2.5, 3.5]));
+  assert_eq!((tbl.n_cols(), tbl.n_rows()), (2, 3));
+
+  //Rows contain one entry per column
+  let row = tbl.row(1).unwrap();
+  assert_eq!(row, vec![rsf::TableEntry::Text("b".into()), rsf::TableEntry::Float(2.5)]);
+  assert!(tbl.row(3).is_none());
+  assert_eq!(tbl.iter_rows().count(), 3);
+  assert_eq!(tbl.iter_rows().last().unwrap()[0], rsf::TableEntry::Text("c".into()));
+
+  //Columns can be looked up by their label
+  let mags = tbl.get_column_by_name("MAG").unwrap();
+  assert_eq!(mags[2], rsf::TableEntry::Float(3.5));
+  assert!(tbl.get_column_by_name("FLUX").is_none());
+
+  //...and read as a Vec if the entries have the requested type
+  assert_eq!(tbl.get_float_column("MAG").unwrap(), vec![1.5, 2.5, 3.5]);
+  assert_eq!(tbl.get_string_column("NAME").unwrap(), ["a", "b", "c"]);
+  assert_eq!(tbl.get_typed_column::<String>("NAME"), tbl.get_string_column("NAME"));
+  assert!(tbl.get_int_column("MAG").is_none());
+  assert!(tbl.get_float_column("FLUX").is_none());
+}
+
+#[test]
+fn sort_filter_test() {
+  let names = ["vega", "sirius", "deneb", "altair"].map(String::from).to_vec();
+  let mut tbl = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(Some("MAG".into()), None, vec![0.03, -1.46, 1.25, 0.77]));
+
+  //Sorting by magnitude keeps the names with their magnitudes
+  tbl.sort_by_column(1).unwrap();
+  let sorted = tbl.iter_rows().map(|row| row[0].clone()).collect::<Vec<_>>();
+  let expected = ["sirius", "vega", "altair", "deneb"];
+  assert_eq!(sorted, expected.map(|name| rsf::TableEntry::Text(name.into())).to_vec());
+  assert!(tbl.is_sorted_asc(1).unwrap());
+  assert!(tbl.sort_by_column(2).is_err());
+
+  //Filtering returns a new table with only the bright stars
+  let bright = tbl.filter_rows(|row| row[1] < rsf::TableEntry::Float(0.5));
+  assert_eq!(bright.n_rows(), 2);
+  assert_eq!(bright.row(1).unwrap()[0], rsf::TableEntry::Text("vega".into()));
+  assert_eq!(tbl.n_rows(), 4);
+
+  //Reordering a table with columns of unequal length would move entries of
+  //the shorter columns into the wrong rows, filtering keeps them aligned
+  let names = ["vega", "sirius", "deneb"].map(String::from).to_vec();
+  let mut ragged = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(Some("MAG".into()), None, vec![0.03]));
+  let err = ragged.sort_by_column(0).unwrap_err();
+  assert!(err.to_string().contains("1 to 3 entries"));
+  assert_eq!(ragged.get_entry(0, 0).unwrap(), rsf::TableEntry::Text("vega".into()));
+  let text = |name: &str| rsf::TableEntry::Text(name.into());
+  let kept = ragged.filter_rows(|row| row[0] != text("sirius"));
+  assert_eq!(kept.row(0).unwrap(), vec![text("vega"), rsf::TableEntry::Float(0.03)]);
+  assert_eq!(kept.row(1).unwrap(), vec![text("deneb")]);
+  let kept = ragged.filter_rows(|row| row[0] != text("vega"));
+  assert_eq!(kept.get_column_by_name("MAG").unwrap(), vec![]);
+
+  //Entries of different types are ordered as Text < Int < Float
+  assert!(rsf::TableEntry::Text("z".into()) < rsf::TableEntry::Int(-5));
+  assert!(rsf::TableEntry::Int(5) < rsf::TableEntry::Float(-5.0));
+}
+
+#[test]
+fn typed_entry_test() {
+  use rsf::TableEntry::*;
+
+  //Typed accessors only return the value for matching variants
+  assert_eq!(Text("abc".into()).as_string(), Some("abc"));
+  assert_eq!(Int(7).as_int(), Some(7));
+  assert_eq!(Int(7).as_float(), None);
+  assert_eq!(Float(2.5).as_float(), Some(2.5));
+  assert_eq!(Float(2.5).into_string(), Err(Float(2.5)));
+
+  //Conversions report the actual variant when they fail
+  assert_eq!(String::try_from(Text("abc".into())).unwrap(), "abc");
+  assert_eq!(i64::try_from(Int(-3)).unwrap(), -3);
+  assert_eq!(f64::try_from(Float(1.0)).unwrap(), 1.0);
+  assert!(bool::try_from(Text("T".into())).unwrap());
+  assert!(!bool::try_from(Text("F".into())).unwrap());
+  let err = i64::try_from(Float(1.0)).unwrap_err();
+  assert_eq!(err.to_string(), "cannot convert TableEntry::Float to i64");
+  assert!(bool::try_from(Int(1)).is_err());
+}
+
+#[test]
+fn typed_column_test() {
+  let names = vec!["vega".to_string(), "deneb".to_string()];
+  let tbl = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(Some("MAG".into()), None, vec![0.03, 1.25]));
+
+  //Typed columns give direct access to their entries
+  let mags = tbl.column::<f64>(1).unwrap();
+  assert_eq!(mags.as_slice().iter().sum::<f64>(), 1.28);
+  assert_eq!((mags.get(1), mags.get(2)), (Some(&1.25), None));
+  assert_eq!((&mags).into_iter().count(), 2);
+  let mut longest = 0;
+  for name in tbl.column::<String>(0).unwrap() {
+    longest = longest.max(name.len());
+  }
+  assert_eq!(longest, 5);
+
+  //Asking for the wrong type (or a missing column) gives nothing
+  assert!(tbl.column::<i64>(1).is_none());
+  assert!(tbl.column::<f64>(2).is_none());
+
+  //Untyped iteration matches the entries of the table
+  let entries = tbl.iter_column(0).unwrap().collect::<Vec<_>>();
+  assert_eq!(entries, vec![tbl.get_entry(0, 0).unwrap(), tbl.get_entry(0, 1).unwrap()]);
+  assert!(tbl.iter_column(2).is_none());
+}
+
+#[test]
+fn csv_test() {
+  let names = vec!["vega".to_string(), "alpha, cen".to_string(), "\"x\"".to_string()];
+  let ids = vec![1, rsf::TableEntry::NULL_INT, 3];
+  let tbl = rsf::AsciiTable::new()
+    .with_column(rsf::Column::from_vec(Some("NAME".into()), None, names))
+    .with_column(rsf::Column::from_vec(None, None, vec![0.03, 1.0, f64::NAN]))
+    .with_column(rsf::Column::from_vec(Some("ID".into()), None, ids));
+
+  //Fields with delimiters or quotes are quoted, undefined floats are empty
+  let mut csv = Vec::new();
+  tbl.to_csv(&mut csv, b',').unwrap();
+  let csv = String::from_utf8(csv).unwrap();
+  let expected =
+    "NAME,,ID\nvega,0.03,1\n\"alpha, cen\",1.0,-9223372036854775808\n\"\"\"x\"\"\",,3\n";
+  assert_eq!(csv, expected);
+
+  //Column types are detected when reading the table back
+  let read = rsf::AsciiTable::from_csv(csv.as_bytes(), b',', true).unwrap();
+  assert_eq!(read.get_shape(), (3, 3));
+  assert_eq!((read.column_label(0), read.column_label(1)), (Some("NAME"), None));
+  assert_eq!(
+    read.column::<String>(0).unwrap().as_slice(),
+    tbl.column::<String>(0).unwrap().as_slice()
+  );
+  assert_eq!(read.column::<f64>(1).unwrap().get(1), Some(&1.0));
+  assert!(read.column::<f64>(1).unwrap().get(2).unwrap().is_nan());
+  assert_eq!(read.column::<i64>(2).unwrap().as_slice(), &[1, rsf::TableEntry::NULL_INT, 3]);
+
+  //Empty integer fields become undefined integers
+  let read = rsf::AsciiTable::from_csv("1;x\r\n\r\n;y\r\n".as_bytes(), b';', false).unwrap();
+  assert_eq!(read.get_shape(), (2, 2));
+  assert_eq!(read.column::<i64>(0).unwrap().as_slice(), &[1, rsf::TableEntry::NULL_INT]);
+  assert!(read.column_null(0).is_some() && read.column_null(1).is_none());
+  let mut tsv = Vec::new();
+  read.to_csv(&mut tsv, b'\t').unwrap();
+  assert_eq!(tsv, b"1\tx\n\ty\n");
+
+  //Rows of the wrong length are reported with their line number
+  let err = rsf::AsciiTable::from_csv("a,b\n1,2\n\n3\n".as_bytes(), b',', true).unwrap_err();
+  assert_eq!(err.to_string(), "could not parse line 4 of CSV file: expected 2 fields, found 1");
+  assert!(rsf::AsciiTable::from_csv("a,\"b\n".as_bytes(), b',', false).is_err());
+}
+
+#[test]
+fn hrs_csv_test() {
+  let mut real = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  real.push(TABLE_FILE);
+  let fits = rsf::Fits::open(&real).unwrap();
+  let Some(rsf::Extension::AsciiTable(tbl)) = fits.get_hdu(1).unwrap().get_data() else {
+    panic!("HRS file should contain an ASCII table");
+  };
+
+  //One row of labels and one line per row of the table
+  let mut csv = Vec::new();
+  tbl.to_csv(&mut csv, b',').unwrap();
+  let csv = String::from_utf8(csv).unwrap();
+  let lines = csv.lines().collect::<Vec<_>>();
+  assert_eq!(lines.len(), 5);
+  let first = lines[1].split(',').collect::<Vec<_>>();
+  assert_eq!(first.len(), 25);
+  assert_eq!(first[5], "182.63579607519998");
+  assert_eq!(first[10], "PIXEL   ");
+  assert_eq!(first[20], "50684");
+  assert!(lines[4].starts_with("1.0,1.0,1.0,3.8113999e-14,"));
+
+  //The typed rows hold the same values
+  let last = tbl.iter_rows().last().unwrap();
+  assert_eq!(last[9], rsf::TableEntry::Float(48807.902278727684));
+  assert_eq!(last[20], rsf::TableEntry::Int(50687));
+
+  //...and the table survives the round trip through CSV
+  assert_eq!(rsf::AsciiTable::from_csv(csv.as_bytes(), b',', true).unwrap(), *tbl);
+
+  //write_csv() names unlabelled columns after their column number
+  let mut labelled = Vec::new();
+  tbl.write_csv(&mut labelled).unwrap();
+  assert_eq!(String::from_utf8(labelled).unwrap(), csv);
+  let unlabelled = rsf::AsciiTable::from_csv("1,a\n2,b\n".as_bytes(), b',', false).unwrap();
+  let mut out = Vec::new();
+  unlabelled.to_csv(&mut out, b',').unwrap();
+  assert_eq!(String::from_utf8(out).unwrap(), "1,a\n2,b\n");
+  let mut out = Vec::new();
+  unlabelled.write_csv(&mut out).unwrap();
+  assert_eq!(String::from_utf8(out).unwrap(), "col_1,col_2\n1,a\n2,b\n");
+}
+
+fn ascii_table_bytes(records: &[&str], rows: &str) -> Vec<u8> {
+  //Puts the table in the first extension of an otherwise empty file
+  let header = |records: &[&str]| {
+    let mut block = records.iter().map(|rec| format!("{rec:80}")).collect::<String>();
+    block.push_str(&format!("{:2880}", "END")[..2880 - block.len() % 2880]);
+    block.into_bytes()
+  };
+  let mut bytes = header(&[
+    "SIMPLE  =                    T",
+    "BITPIX  =                    8",
+    "NAXIS   =                    0",
+    "EXTEND  =                    T",
+  ]);
+  bytes.extend(header(records));
+  bytes.extend(format!("{rows:2880}").into_bytes());
+  bytes
+}
+
+#[test]
+fn fortran_formats_test() {
+  //Table with logical, double, general and bit fields
+  let bytes = ascii_table_bytes(
+    &[
+      "XTENSION= 'TABLE   '",
+      "BITPIX  =                    8",
+      "NAXIS   =                    2",
+      "NAXIS1  =                   26",
+      "NAXIS2  =                    2",
+      "PCOUNT  =                    0",
+      "GCOUNT  =                    1",
+      "TFIELDS =                    4",
+      "TBCOL1  =                    1",
+      "TFORM1  = 'L1      '",
+      "TBCOL2  =                    3",
+      "TFORM2  = 'D10.3   '",
+      "TBCOL3  =                   14",
+      "TFORM3  = 'G8.2    '",
+      "TBCOL4  =                   23",
+      "TFORM4  = 'X4      '",
+    ],
+    "T  1.500D+02     2.50 0101F -2.000D-01    12.00 1111",
+  );
+
+  let check = |fits: &rsf::Fits| {
+    let tbl = fits.get_hdu(1).unwrap().table().unwrap();
+    use rsf::TableEntry::*;
+    assert_eq!(tbl.row(0).unwrap(), vec![Bool(true), Float(150.0), Float(2.5), Int(5)]);
+    assert_eq!(tbl.row(1).unwrap(), vec![Bool(false), Float(-0.2), Float(12.0), Int(15)]);
+  };
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  check(&fits);
+  assert!(fits.get_hdu(1).unwrap().image::<u8>().is_err());
+  assert!(fits.get_hdu(0).unwrap().clone().into_table().is_err());
+  assert_eq!(fits.get_hdu(1).unwrap().clone().into_table().unwrap().n_rows(), 2);
+
+  //Logical columns survive a round trip
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("fortran_formats.fits");
+  fits.write(&path).unwrap();
+  check(&rsf::Fits::open(&path).unwrap());
+}
+
+#[test]
+fn tdim_test() {
+  //Binary table with a 3x2 integer array and four floats in each row
+  let mut bytes = ascii_table_bytes(
+    &[
+      "XTENSION= 'BINTABLE'",
+      "BITPIX  =                    8",
+      "NAXIS   =                    2",
+      "NAXIS1  =                   40",
+      "NAXIS2  =                    2",
+      "PCOUNT  =                    0",
+      "GCOUNT  =                    1",
+      "TFIELDS =                    2",
+      "TFORM1  = '6J      '",
+      "TDIM1   = '(3,2)   '",
+      "TFORM2  = '4E      '",
+    ],
+    "",
+  );
+  let data_start = bytes.len() - 2880;
+  let rows = (0..2i32).flat_map(|row| {
+    let ints = (0..6).flat_map(move |i| (10 * row + i).to_be_bytes());
+    ints.chain((0..4).flat_map(move |i| (row as f32 + i as f32 / 4.0).to_be_bytes()))
+  });
+  bytes.splice(data_start..data_start + 80, rows);
+
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  let tbl = match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::BinTable(tbl) => tbl,
+    _ => panic!(),
+  };
+
+  //Fields are reshaped in the Fortran memory layout, like images
+  let arrays = tbl.get_column(0).unwrap();
+  assert_eq!(arrays.get_cell_shape(), vec![3, 2]);
+  let cell = arrays.get_cell::<i32>(1).unwrap();
+  assert_eq!(cell.shape(), &[3, 2]);
+  assert_eq!((cell[[0, 0]], cell[[2, 0]], cell[[0, 1]], cell[[2, 1]]), (10, 12, 13, 15));
+  assert!(arrays.get_cell::<i32>(2).is_none());
+  assert!(arrays.get_cell::<f32>(0).is_none());
+
+  //Without TDIM the fields are one-dimensional
+  let floats = tbl.get_column(1).unwrap();
+  assert_eq!(floats.get_cell_shape(), vec![4]);
+  assert_eq!(floats.get_cell::<f32>(1).unwrap().into_raw_vec(), vec![1.0, 1.25, 1.5, 1.75]);
+}
+
+#[test]
+fn var_len_test() {
+  //Binary table with variable-length integer and float arrays. The heap
+  //starts 16 bytes after the end of the table
+  let records = [
+    "XTENSION= 'BINTABLE'",
+    "BITPIX  =                    8",
+    "NAXIS   =                    2",
+    "NAXIS1  =                   24",
+    "NAXIS2  =                    2",
+    "PCOUNT  =                   40",
+    "GCOUNT  =                    1",
+    "TFIELDS =                    2",
+    "TFORM1  = '1PJ(3)  '",
+    "TFORM2  = '1QE(2)  '",
+    "TSCAL2  =                  2.0",
+    "THEAP   =                   64",
+  ];
+  let descriptors: [(u32, u64); 4] = [(3, 0), (1, 12), (0, 0), (2, 16)];
+  let mut data = Vec::new();
+  for (i, (len, offset)) in descriptors.into_iter().enumerate() {
+    match i % 2 {
+      0 => data.extend(len.to_be_bytes().into_iter().chain((offset as u32).to_be_bytes())),
+      _ => data.extend((len as u64).to_be_bytes().into_iter().chain(offset.to_be_bytes())),
+    }
+  }
+  data.resize(64, 0);
+  data.extend([1i32, 2, 3].iter().flat_map(|int| int.to_be_bytes()));
+  data.extend([0.5f32, 1.5, 2.5].iter().flat_map(|float| float.to_be_bytes()));
+
+  let with_data = |data: &[u8]| {
+    let mut bytes = ascii_table_bytes(&records, "");
+    let data_start = bytes.len() - 2880;
+    bytes.splice(data_start..data_start + data.len(), data.iter().cloned());
+    rsf::Fits::from_bytes(&bytes)
+  };
+  let fits = with_data(&data).unwrap();
+  let tbl = match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::BinTable(tbl) => tbl,
+    _ => panic!(),
+  };
+
+  //Each row has its own array, scaling applies to the elements
+  use rsf::BinColumnData::*;
+  let ints = tbl.get_column(0).unwrap();
+  assert_eq!(ints.get_data(), &VarLen(vec![Int(vec![1, 2, 3]), Int(vec![])]));
+  assert_eq!(ints.get_cell::<i32>(0).unwrap().into_raw_vec(), vec![1, 2, 3]);
+  assert_eq!(ints.get_cell::<i32>(1).unwrap().len(), 0);
+  let floats = tbl.get_column(1).unwrap();
+  assert_eq!(floats.get_data(), &VarLen(vec![Double(vec![1.0]), Double(vec![3.0, 5.0])]));
+
+  //Arrays that do not fit in the heap cannot be read
+  data[4..8].copy_from_slice(&1000u32.to_be_bytes());
+  let err = with_data(&data).unwrap_err();
+  assert!(err.to_string().contains("variable-length array in row 0 extends beyond the heap"));
+}
+
+#[test]
+fn scaling_null_test() {
+  //Scaled integers, plain integers and floats, all with undefined values
+  let bytes = ascii_table_bytes(
+    &[
+      "XTENSION= 'TABLE   '",
+      "BITPIX  =                    8",
+      "NAXIS   =                    2",
+      "NAXIS1  =                   17",
+      "NAXIS2  =                    2",
+      "PCOUNT  =                    0",
+      "GCOUNT  =                    1",
+      "TFIELDS =                    3",
+      "TBCOL1  =                    1",
+      "TFORM1  = 'I5      '",
+      "TSCAL1  =                  0.5",
+      "TZERO1  =                 10.0",
+      "TNULL1  = '-999    '",
+      "TBCOL2  =                    7",
+      "TFORM2  = 'I4      '",
+      "TNULL2  = '-999    '",
+      "TBCOL3  =                   12",
+      "TFORM3  = 'F6.2    '",
+      "TNULL3  = 'NULL    '",
+    ],
+    "   20 -999  1.25 -999   42  NULL",
+  );
+  let get_tbl = |fits: &rsf::Fits| match fits.get_hdu(1).unwrap().get_data().unwrap() {
+    rsf::Extension::AsciiTable(tbl) => tbl.clone(),
+    _ => panic!(),
+  };
+  use rsf::TableEntry::*;
+
+  let check = |tbl: &rsf::AsciiTable| {
+    //TSCAL/TZERO turn the first column into floats
+    assert_eq!(tbl.get_entry(0, 0).unwrap(), Float(20.0));
+    assert!(tbl.get_entry(0, 1).unwrap().as_float().unwrap().is_nan());
+    assert_eq!(tbl.row(0).unwrap()[1], Int(rsf::TableEntry::NULL_INT));
+    assert_eq!(tbl.row(1).unwrap()[1], Int(42));
+    assert!(tbl.get_entry(2, 1).unwrap().as_float().unwrap().is_nan());
+    assert_eq!(tbl.column_null(1), Some("-999"));
+    assert_eq!(tbl.column_stats(1).unwrap().n_nulls, 1);
+  };
+  let fits = rsf::Fits::from_bytes(&bytes).unwrap();
+  check(&get_tbl(&fits));
+  assert!(fits.get_hdu(1).unwrap().get_header().get_value("TSCAL1").is_none());
+
+  //Undefined values are written back using the TNULL strings
+  let mut path = dirs::cache_dir().unwrap();
+  path.push("scaling_null.fits");
+  fits.write(&path).unwrap();
+  check(&get_tbl(&rsf::Fits::open(&path).unwrap()));
+
+  //Raw tables are not scaled
+  let raw_fits = {
+    let mut path = dirs::cache_dir().unwrap();
+    path.push("scaling_null_raw.fits");
+    std::fs::write(&path, &bytes).unwrap();
+    rsf::Fits::open_with_scaling(&path, rsf::ScalingMode::Raw).unwrap()
+  };
+  assert_eq!(get_tbl(&raw_fits).get_entry(0, 0).unwrap(), Int(20));
+  assert_eq!(get_tbl(&raw_fits).get_entry(0, 1).unwrap(), Int(rsf::TableEntry::NULL_INT));
+}
+
+#[cfg(any(feature = "polars", feature = "arrow"))]
+fn dataframe_tbl() -> rsf::AsciiTable {
+  //Table with a missing entry and an unlabeled column
+  let ids = rsf::Column::from_vec(Some("ID".into()), None, vec![1, rsf::TableEntry::NULL_INT, 3]);
+  rsf::AsciiTable::new()
+    .with_column(ids)
+    .with_column(rsf::Column::from_vec(Some("MAG".into()), None, vec![0.5, 1.5, 2.5]))
+    .with_column(rsf::Column::from_vec(None, None, vec!["a".to_string(), "b".to_string()]))
+}
+
+#[cfg(feature = "polars")]
+#[test]
+fn polars_test() {
+  use polars::prelude::*;
+
+  let df = dataframe_tbl().to_polars().unwrap();
+  assert_eq!(df.shape(), (3, 3));
+  let names = df.get_column_names().into_iter().map(|name| name.as_str()).collect::<Vec<_>>();
+  assert_eq!(names, ["ID", "MAG", "COL3"]);
+  //Without a TNULL value NULL_INT is just a very negative number
+  let ids = df.column("ID").unwrap().i64().unwrap().iter().collect::<Vec<_>>();
+  assert_eq!(ids, [Some(1), Some(rsf::TableEntry::NULL_INT), Some(3)]);
+  let names = df.column("COL3").unwrap().str().unwrap().iter().collect::<Vec<_>>();
+  assert_eq!(names, [Some("a"), Some("b"), None]);
+
+  //Nulls are stored with a TNULL value and show up as nulls again
+  let tbl = rsf::AsciiTable::from_polars(df).unwrap();
+  assert_eq!(tbl.get_entry(0, 1).unwrap(), rsf::TableEntry::Int(rsf::TableEntry::NULL_INT));
+  assert_eq!(tbl.get_entry(2, 2).unwrap(), rsf::TableEntry::Text(String::new()));
+  let df = rsf::AsciiTable::from_polars(
+    df![
+      "ID" => [Some(7i32), None],
+      "OK" => [true, false],
+    ]
+    .unwrap(),
+  )
+  .unwrap()
+  .to_polars()
+  .unwrap();
+  let ids = df.column("ID").unwrap().i64().unwrap().iter().collect::<Vec<_>>();
+  assert_eq!(ids, [Some(7), None]);
+  assert_eq!(df.column("OK").unwrap().dtype(), &DataType::Boolean);
+
+  //Booleans cannot be undefined
+  let nulls = df!["OK" => [Some(true), None]].unwrap();
+  assert!(rsf::AsciiTable::from_polars(nulls).is_err());
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn record_batch_test() {
+  use arrow::array::{Array, Float64Array, StringArray};
+
+  let batch = dataframe_tbl().to_record_batch().unwrap();
+  assert_eq!((batch.num_rows(), batch.num_columns()), (3, 3));
+  assert_eq!(batch.schema().field(2).name(), "COL3");
+  let mags = batch.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+  assert_eq!(mags.values(), &[0.5, 1.5, 2.5]);
+  let names = batch.column(2).as_any().downcast_ref::<StringArray>().unwrap();
+  assert!(names.is_null(2));
+
+  //Tables without columns still have a height
+  assert_eq!(rsf::AsciiTable::new().to_record_batch().unwrap().num_rows(), 0);
+}